@@ -42,25 +42,46 @@ impl From<RumaEventInput> for RumaEvent {
         let name = input.name;
         let content_name = format_ident!("{}Content", name, span = Span::call_site());
         let event_type = input.event_type;
+        let mut content = input.content;
+
+        // Serde attributes of a type alias apply to the event's `content` field instead.
+        let content_field_attrs = match &mut content {
+            Content::Typedef(typedef) => {
+                let (serde_attrs, attrs) = typedef
+                    .attrs
+                    .drain(..)
+                    .partition(|attr| attr.path.is_ident("serde"));
+                typedef.attrs = attrs;
+
+                serde_attrs
+            }
+            Content::Struct(_) => Vec::new(),
+        };
 
         let mut fields = match kind {
             EventKind::Event => {
-                populate_event_fields(content_name.clone(), input.fields.unwrap_or_else(Vec::new))
+                populate_event_fields(content_name.clone(), input.fields.unwrap_or_default())
+            }
+            EventKind::RoomEvent => {
+                populate_room_event_fields(content_name.clone(), input.fields.unwrap_or_default())
             }
-            EventKind::RoomEvent => populate_room_event_fields(
-                content_name.clone(),
-                input.fields.unwrap_or_else(Vec::new),
-            ),
             EventKind::StateEvent => {
-                populate_state_fields(content_name.clone(), input.fields.unwrap_or_else(Vec::new))
+                populate_state_fields(content_name.clone(), input.fields.unwrap_or_default())
             }
         };
 
         fields.sort_unstable_by_key(|field| field.ident.clone().unwrap());
 
+        if let Some(content_field) = fields.iter_mut().find(|field| match &field.ident {
+            Some(ident) => ident == "content",
+            None => false,
+        }) {
+            content_field.attrs.extend(content_field_attrs);
+        }
+
         Self {
             attrs: input.attrs,
-            content: input.content,
+            content,
             content_name,
             event_type,
             fields,
@@ -163,6 +184,11 @@ impl ToTokens for RumaEvent {
             _ => TokenStream::new(),
         };
 
+        let dyn_event_sender = match self.kind {
+            EventKind::RoomEvent | EventKind::StateEvent => quote! { Some(&self.sender) },
+            _ => quote! { None },
+        };
+
         let impl_state_event = if self.kind == EventKind::StateEvent {
             quote! {
                 impl ::ruma_events::StateEvent for #name {
@@ -242,6 +268,29 @@ impl ToTokens for RumaEvent {
                 }
             }
 
+            impl std::str::FromStr for #name {
+                type Err = ::ruma_events::InvalidEvent;
+
+                /// Deserializes and validates the event from a string of JSON.
+                fn from_str(s: &str) -> Result<Self, Self::Err> {
+                    ::ruma_events::EventResult::from_json_str(s).into_result()
+                }
+            }
+
+            impl ::ruma_events::DynEvent for #name {
+                fn event_type(&self) -> ::ruma_events::EventType {
+                    #event_type_variant
+                }
+
+                fn sender(&self) -> Option<&ruma_identifiers::UserId> {
+                    #dyn_event_sender
+                }
+
+                fn content_json(&self) -> Result<serde_json::Value, serde_json::Error> {
+                    serde_json::to_value(&self.content)
+                }
+            }
+
             #impl_room_event
 
             #impl_state_event
@@ -286,6 +335,7 @@ fn populate_room_event_fields(content_name: Ident, fields: Vec<Field>) -> Vec<Fi
 
         /// Timestamp (milliseconds since the UNIX epoch) on originating homeserver when this
         /// event was sent.
+        #[serde(deserialize_with = "::ruma_events::__private::uint_or_string")]
         pub origin_server_ts: js_int::UInt,
 
         /// The unique identifier for the room associated with this event.
@@ -23,7 +23,6 @@
     clippy::unicode_not_nfc,
     clippy::use_self,
     clippy::used_underscore_binding,
-    clippy::wrong_pub_self_convention,
     clippy::wrong_self_convention
 )]
 // Since we support Rust 1.36.0, we can't apply this suggestion yet
@@ -119,6 +118,9 @@ mod parse;
 /// # }
 /// ```
 ///
+/// Serde attributes on the type alias, such as `#[serde(serialize_with = "...")]`, are applied to
+/// the event's `content` field instead.
+///
 /// If `content` and `content_type_alias` are both supplied, the second one listed will overwrite
 /// the first.
 ///
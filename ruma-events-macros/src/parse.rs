@@ -43,7 +43,7 @@ impl Parse for RumaEventInput {
         let mut fields = None;
         let mut content = None;
 
-        #[allow(clippy::identity_conversion)]
+        #[allow(clippy::useless_conversion)]
         for field_value_inline_struct in
             body.parse_terminated::<RumaEventField, Token![,]>(RumaEventField::parse)?
         {
@@ -212,6 +212,7 @@ impl Parse for RumaEventField {
 /// The value of a field is a block with a type alias in it.
 ///
 /// Used for `content_type_alias`.
+#[allow(dead_code)]
 struct FieldBlock {
     /// Outer attributes on the field, such as a docstring.
     pub attrs: Vec<Attribute>,
@@ -229,6 +230,7 @@ struct FieldBlock {
 /// The value of a field is a block with named struct fields in it.
 ///
 /// Used for `content`.
+#[allow(dead_code)]
 struct FieldInlineStruct {
     /// Outer attributes on the field, such as a docstring.
     pub attrs: Vec<Attribute>,
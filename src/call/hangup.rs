@@ -18,6 +18,7 @@ ruma_event! {
             pub version: UInt,
 
             /// Optional error reason for the hangup.
+            #[serde(skip_serializing_if = "Option::is_none")]
             pub reason: Option<Reason>,
         },
     }
@@ -1,7 +1,12 @@
 //! Enums for heterogeneous collections of events, inclusive for every event type that implements
 //! the trait of the same name.
 
+use std::{cmp::Ordering, convert::TryFrom};
+
+use js_int::UInt;
+use ruma_identifiers::{EventId, UserId};
 use serde::Serialize;
+use serde_json::value::RawValue;
 
 use super::raw::all as raw;
 use crate::{
@@ -14,8 +19,11 @@ use crate::{
     fully_read::FullyReadEvent,
     ignored_user_list::IgnoredUserListEvent,
     key::verification::{
-        accept::AcceptEvent, cancel::CancelEvent, key::KeyEvent, mac::MacEvent,
-        request::RequestEvent, start::StartEvent,
+        accept::AcceptEvent, cancel::CancelEvent, in_room::InRoomVerificationEvent, key::KeyEvent,
+        mac::MacEvent, request::RequestEvent, start::StartEvent, KeyVerificationContent,
+    },
+    policy::rule::{
+        room::PolicyRuleRoomEvent, server::PolicyRuleServerEvent, user::PolicyRuleUserEvent,
     },
     presence::PresenceEvent,
     push_rules::PushRulesEvent,
@@ -31,7 +39,7 @@ use crate::{
         history_visibility::HistoryVisibilityEvent,
         join_rules::JoinRulesEvent,
         member::MemberEvent,
-        message::{feedback::FeedbackEvent, MessageEvent},
+        message::{feedback::FeedbackEvent, MessageEvent, MessageEventContent},
         name::NameEvent,
         pinned_events::PinnedEventsEvent,
         power_levels::PowerLevelsEvent,
@@ -43,10 +51,13 @@ use crate::{
     },
     room_key::RoomKeyEvent,
     room_key_request::RoomKeyRequestEvent,
+    space::{child::SpaceChildEvent, parent::SpaceParentEvent},
     sticker::StickerEvent,
     tag::TagEvent,
     typing::TypingEvent,
-    CustomEvent, CustomRoomEvent, CustomStateEvent, TryFromRaw,
+    util::{cmp_display, split_event_array},
+    CustomEvent, CustomEventContent, CustomRoomEvent, CustomStateEvent, EventResult, InvalidEvent,
+    TryFromRaw,
 };
 
 /// A basic event, room event, or state event.
@@ -99,6 +110,9 @@ pub enum Event {
     /// m.key.verification.start
     KeyVerificationStart(StartEvent),
 
+    /// m.key.verification.* sent in a room
+    KeyVerificationInRoom(InRoomVerificationEvent),
+
     /// m.presence
     Presence(PresenceEvent),
 
@@ -168,6 +182,21 @@ pub enum Event {
     /// m.room.topic
     RoomTopic(TopicEvent),
 
+    /// m.space.child
+    SpaceChild(SpaceChildEvent),
+
+    /// m.space.parent
+    SpaceParent(SpaceParentEvent),
+
+    /// m.policy.rule.room
+    PolicyRuleRoom(PolicyRuleRoomEvent),
+
+    /// m.policy.rule.server
+    PolicyRuleServer(PolicyRuleServerEvent),
+
+    /// m.policy.rule.user
+    PolicyRuleUser(PolicyRuleUserEvent),
+
     /// m.room_key
     RoomKey(RoomKeyEvent),
 
@@ -210,6 +239,9 @@ pub enum RoomEvent {
     /// m.call.invite
     CallInvite(InviteEvent),
 
+    /// m.key.verification.* sent in a room
+    KeyVerificationInRoom(InRoomVerificationEvent),
+
     /// m.room.aliases
     RoomAliases(AliasesEvent),
 
@@ -270,6 +302,21 @@ pub enum RoomEvent {
     /// m.room.topic
     RoomTopic(TopicEvent),
 
+    /// m.space.child
+    SpaceChild(SpaceChildEvent),
+
+    /// m.space.parent
+    SpaceParent(SpaceParentEvent),
+
+    /// m.policy.rule.room
+    PolicyRuleRoom(PolicyRuleRoomEvent),
+
+    /// m.policy.rule.server
+    PolicyRuleServer(PolicyRuleServerEvent),
+
+    /// m.policy.rule.user
+    PolicyRuleUser(PolicyRuleUserEvent),
+
     /// m.sticker
     Sticker(StickerEvent),
 
@@ -333,6 +380,21 @@ pub enum StateEvent {
     /// m.room.topic
     RoomTopic(TopicEvent),
 
+    /// m.space.child
+    SpaceChild(SpaceChildEvent),
+
+    /// m.space.parent
+    SpaceParent(SpaceParentEvent),
+
+    /// m.policy.rule.room
+    PolicyRuleRoom(PolicyRuleRoomEvent),
+
+    /// m.policy.rule.server
+    PolicyRuleServer(PolicyRuleServerEvent),
+
+    /// m.policy.rule.user
+    PolicyRuleUser(PolicyRuleUserEvent),
+
     /// Any state event that is not part of the specification.
     CustomState(CustomStateEvent),
 }
@@ -361,6 +423,7 @@ impl TryFromRaw for Event {
             KeyVerificationMac(c) => conv(Event::KeyVerificationMac, c),
             KeyVerificationRequest(c) => conv(Event::KeyVerificationRequest, c),
             KeyVerificationStart(c) => conv(Event::KeyVerificationStart, c),
+            KeyVerificationInRoom(c) => conv(Event::KeyVerificationInRoom, c),
             Presence(c) => conv(Event::Presence, c),
             PushRules(c) => conv(Event::PushRules, c),
             Receipt(c) => conv(Event::Receipt, c),
@@ -384,6 +447,11 @@ impl TryFromRaw for Event {
             RoomThirdPartyInvite(c) => conv(Event::RoomThirdPartyInvite, c),
             RoomTombstone(c) => conv(Event::RoomTombstone, c),
             RoomTopic(c) => conv(Event::RoomTopic, c),
+            SpaceChild(c) => conv(Event::SpaceChild, c),
+            SpaceParent(c) => conv(Event::SpaceParent, c),
+            PolicyRuleRoom(c) => conv(Event::PolicyRuleRoom, c),
+            PolicyRuleServer(c) => conv(Event::PolicyRuleServer, c),
+            PolicyRuleUser(c) => conv(Event::PolicyRuleUser, c),
             RoomKey(c) => conv(Event::RoomKey, c),
             RoomKeyRequest(c) => conv(Event::RoomKeyRequest, c),
             Sticker(c) => conv(Event::Sticker, c),
@@ -396,6 +464,123 @@ impl TryFromRaw for Event {
     }
 }
 
+impl Event {
+    /// The key verification content of this event, if it is part of a key verification.
+    ///
+    /// This recognizes the dedicated events of the *m.key.verification* namespace as well as the
+    /// in-room forms recognized by `RoomEvent::as_key_verification`.
+    pub fn as_key_verification(&self) -> Option<KeyVerificationContent> {
+        let content = match self {
+            Event::KeyVerificationAccept(e) => KeyVerificationContent::Accept(e.content.clone()),
+            Event::KeyVerificationCancel(e) => KeyVerificationContent::Cancel(e.content.clone()),
+            Event::KeyVerificationKey(e) => KeyVerificationContent::Key(e.content.clone()),
+            Event::KeyVerificationMac(e) => KeyVerificationContent::Mac(e.content.clone()),
+            Event::KeyVerificationRequest(e) => KeyVerificationContent::Request(e.content.clone()),
+            Event::KeyVerificationStart(e) => KeyVerificationContent::Start(e.content.clone()),
+            Event::KeyVerificationInRoom(e) => return in_room_key_verification(e),
+            Event::RoomMessage(e) => return message_key_verification(e),
+            _ => return None,
+        };
+
+        Some(content)
+    }
+
+    /// Parses the content of a custom event into `T`.
+    ///
+    /// Returns `None` if this is not a custom event of type `T::EVENT_TYPE`, and an error if its
+    /// content is not a valid `T`.
+    pub fn deserialize_custom<T: CustomEventContent>(
+        &self,
+    ) -> Option<Result<T, serde_json::Error>> {
+        let (event_type, content) = match self {
+            Event::Custom(e) => (&e.event_type, &e.content),
+            Event::CustomRoom(e) => (&e.event_type, &e.content),
+            Event::CustomState(e) => (&e.event_type, &e.content),
+            _ => return None,
+        };
+
+        if event_type != T::EVENT_TYPE {
+            return None;
+        }
+
+        Some(serde_json::from_value(content.clone()))
+    }
+}
+
+impl RoomEvent {
+    /// The key verification content of this event, if it is part of a key verification.
+    ///
+    /// This recognizes the *m.key.verification* events sent in a room as well as in-room
+    /// verification requests, which are sent as *m.room.message* events.
+    pub fn as_key_verification(&self) -> Option<KeyVerificationContent> {
+        match self {
+            RoomEvent::KeyVerificationInRoom(e) => in_room_key_verification(e),
+            RoomEvent::RoomMessage(e) => message_key_verification(e),
+            _ => None,
+        }
+    }
+
+    /// The key by which this event is ordered in a room's timeline: its `origin_server_ts`,
+    /// with the event ID as a tiebreaker.
+    pub fn timeline_ordering_key(&self) -> (UInt, &EventId) {
+        use crate::RoomEvent as _;
+
+        match self {
+            RoomEvent::CallAnswer(e) => (e.origin_server_ts(), e.event_id()),
+            RoomEvent::CallCandidates(e) => (e.origin_server_ts(), e.event_id()),
+            RoomEvent::CallHangup(e) => (e.origin_server_ts(), e.event_id()),
+            RoomEvent::CallInvite(e) => (e.origin_server_ts(), e.event_id()),
+            RoomEvent::KeyVerificationInRoom(e) => (e.origin_server_ts, &e.event_id),
+            RoomEvent::RoomAliases(e) => (e.origin_server_ts(), e.event_id()),
+            RoomEvent::RoomAvatar(e) => (e.origin_server_ts(), e.event_id()),
+            RoomEvent::RoomCanonicalAlias(e) => (e.origin_server_ts(), e.event_id()),
+            RoomEvent::RoomCreate(e) => (e.origin_server_ts(), e.event_id()),
+            RoomEvent::RoomEncrypted(e) => (e.origin_server_ts(), e.event_id()),
+            RoomEvent::RoomEncryption(e) => (e.origin_server_ts(), e.event_id()),
+            RoomEvent::RoomGuestAccess(e) => (e.origin_server_ts(), e.event_id()),
+            RoomEvent::RoomHistoryVisibility(e) => (e.origin_server_ts(), e.event_id()),
+            RoomEvent::RoomJoinRules(e) => (e.origin_server_ts(), e.event_id()),
+            RoomEvent::RoomMember(e) => (e.origin_server_ts(), e.event_id()),
+            RoomEvent::RoomMessage(e) => (e.origin_server_ts(), e.event_id()),
+            RoomEvent::RoomMessageFeedback(e) => (e.origin_server_ts(), e.event_id()),
+            RoomEvent::RoomName(e) => (e.origin_server_ts(), e.event_id()),
+            RoomEvent::RoomPinnedEvents(e) => (e.origin_server_ts(), e.event_id()),
+            RoomEvent::RoomPowerLevels(e) => (e.origin_server_ts(), e.event_id()),
+            RoomEvent::RoomRedaction(e) => (e.origin_server_ts(), e.event_id()),
+            RoomEvent::RoomServerAcl(e) => (e.origin_server_ts(), e.event_id()),
+            RoomEvent::RoomThirdPartyInvite(e) => (e.origin_server_ts(), e.event_id()),
+            RoomEvent::RoomTombstone(e) => (e.origin_server_ts(), e.event_id()),
+            RoomEvent::RoomTopic(e) => (e.origin_server_ts(), e.event_id()),
+            RoomEvent::SpaceChild(e) => (e.origin_server_ts(), e.event_id()),
+            RoomEvent::SpaceParent(e) => (e.origin_server_ts(), e.event_id()),
+            RoomEvent::PolicyRuleRoom(e) => (e.origin_server_ts(), e.event_id()),
+            RoomEvent::PolicyRuleServer(e) => (e.origin_server_ts(), e.event_id()),
+            RoomEvent::PolicyRuleUser(e) => (e.origin_server_ts(), e.event_id()),
+            RoomEvent::Sticker(e) => (e.origin_server_ts(), e.event_id()),
+            RoomEvent::CustomRoom(e) => (e.origin_server_ts(), e.event_id()),
+            RoomEvent::CustomState(e) => (e.origin_server_ts(), e.event_id()),
+        }
+    }
+
+    /// Compares two events by their `timeline_ordering_key`.
+    ///
+    /// `EventId` doesn't implement `Ord`, so event IDs are compared by their string
+    /// representation, without allocating. This can be used to sort a batch of events into a stable timeline order:
+    ///
+    /// ```
+    /// # use ruma_events::collections::all::RoomEvent;
+    /// # let mut events: Vec<RoomEvent> = Vec::new();
+    /// events.sort_by(RoomEvent::cmp_timeline_order);
+    /// ```
+    pub fn cmp_timeline_order(&self, other: &Self) -> Ordering {
+        let (ts, event_id) = self.timeline_ordering_key();
+        let (other_ts, other_event_id) = other.timeline_ordering_key();
+
+        ts.cmp(&other_ts)
+            .then_with(|| cmp_display(event_id, other_event_id))
+    }
+}
+
 impl TryFromRaw for RoomEvent {
     type Raw = raw::RoomEvent;
     type Err = String;
@@ -409,6 +594,7 @@ impl TryFromRaw for RoomEvent {
             CallCandidates(c) => conv(RoomEvent::CallCandidates, c),
             CallHangup(c) => conv(RoomEvent::CallHangup, c),
             CallInvite(c) => conv(RoomEvent::CallInvite, c),
+            KeyVerificationInRoom(c) => conv(RoomEvent::KeyVerificationInRoom, c),
             RoomAliases(c) => conv(RoomEvent::RoomAliases, c),
             RoomAvatar(c) => conv(RoomEvent::RoomAvatar, c),
             RoomCanonicalAlias(c) => conv(RoomEvent::RoomCanonicalAlias, c),
@@ -429,6 +615,11 @@ impl TryFromRaw for RoomEvent {
             RoomThirdPartyInvite(c) => conv(RoomEvent::RoomThirdPartyInvite, c),
             RoomTombstone(c) => conv(RoomEvent::RoomTombstone, c),
             RoomTopic(c) => conv(RoomEvent::RoomTopic, c),
+            SpaceChild(c) => conv(RoomEvent::SpaceChild, c),
+            SpaceParent(c) => conv(RoomEvent::SpaceParent, c),
+            PolicyRuleRoom(c) => conv(RoomEvent::PolicyRuleRoom, c),
+            PolicyRuleServer(c) => conv(RoomEvent::PolicyRuleServer, c),
+            PolicyRuleUser(c) => conv(RoomEvent::PolicyRuleUser, c),
             Sticker(c) => conv(RoomEvent::Sticker, c),
             CustomRoom(c) => conv(RoomEvent::CustomRoom, c),
             CustomState(c) => conv(RoomEvent::CustomState, c),
@@ -461,11 +652,173 @@ impl TryFromRaw for StateEvent {
             RoomThirdPartyInvite(c) => conv(StateEvent::RoomThirdPartyInvite, c),
             RoomTombstone(c) => conv(StateEvent::RoomTombstone, c),
             RoomTopic(c) => conv(StateEvent::RoomTopic, c),
+            SpaceChild(c) => conv(StateEvent::SpaceChild, c),
+            SpaceParent(c) => conv(StateEvent::SpaceParent, c),
+            PolicyRuleRoom(c) => conv(StateEvent::PolicyRuleRoom, c),
+            PolicyRuleServer(c) => conv(StateEvent::PolicyRuleServer, c),
+            PolicyRuleUser(c) => conv(StateEvent::PolicyRuleUser, c),
             CustomState(c) => conv(StateEvent::CustomState, c),
         }
     }
 }
 
+impl StateEvent {
+    /// Whether the state key of this event has the form its event type requires.
+    ///
+    /// The state key of *m.room.member* events must be a user ID, that of *m.room.aliases* events a
+    /// server name and that of *m.space.child* and *m.space.parent* events a room ID. All other
+    /// event types accept any state key.
+    pub fn state_key_is_valid(&self) -> bool {
+        match self {
+            StateEvent::RoomMember(e) => UserId::try_from(e.state_key.as_str()).is_ok(),
+            StateEvent::RoomAliases(e) => is_valid_server_name(&e.state_key),
+            StateEvent::SpaceChild(e) => e.child_room_id().is_some(),
+            StateEvent::SpaceParent(e) => e.parent_room_id().is_some(),
+            _ => true,
+        }
+    }
+}
+
+fn in_room_key_verification(event: &InRoomVerificationEvent) -> Option<KeyVerificationContent> {
+    Some(event.content.clone())
+}
+
+fn message_key_verification(event: &MessageEvent) -> Option<KeyVerificationContent> {
+    match &event.content {
+        MessageEventContent::KeyVerificationRequest(content) => {
+            Some(KeyVerificationContent::RoomRequest(content.clone()))
+        }
+        _ => None,
+    }
+}
+
+/// Whether `name` is a valid server name: a DNS name, IPv4 address or bracketed IPv6 address,
+/// optionally followed by a port between 0 and 65535.
+fn is_valid_server_name(name: &str) -> bool {
+    let host = match name.rfind(':') {
+        Some(colon) if !name[colon..].contains(']') => {
+            let port = &name[colon + 1..];
+            // `u16::from_str` also accepts a leading `+`, which isn't allowed here.
+            if !port.bytes().all(|b| b.is_ascii_digit()) || port.parse::<u16>().is_err() {
+                return false;
+            }
+
+            &name[..colon]
+        }
+        _ => name,
+    };
+
+    if host.starts_with('[') && host.ends_with(']') {
+        let address = &host[1..host.len() - 1];
+
+        !address.is_empty()
+            && address
+                .bytes()
+                .all(|b| b.is_ascii_hexdigit() || b == b':' || b == b'.')
+    } else {
+        !host.is_empty()
+            && host
+                .bytes()
+                .all(|b| b.is_ascii_alphanumeric() || b == b'-' || b == b'.')
+    }
+}
+
+/// Narrows an `Event` down to a `RoomEvent`, giving back the event if it is not a room event.
+impl TryFrom<Event> for RoomEvent {
+    type Error = Event;
+
+    fn try_from(event: Event) -> Result<Self, Self::Error> {
+        match event {
+            Event::CallAnswer(e) => Ok(RoomEvent::CallAnswer(e)),
+            Event::CallCandidates(e) => Ok(RoomEvent::CallCandidates(e)),
+            Event::CallHangup(e) => Ok(RoomEvent::CallHangup(e)),
+            Event::CallInvite(e) => Ok(RoomEvent::CallInvite(e)),
+            Event::KeyVerificationInRoom(e) => Ok(RoomEvent::KeyVerificationInRoom(e)),
+            Event::RoomAliases(e) => Ok(RoomEvent::RoomAliases(e)),
+            Event::RoomAvatar(e) => Ok(RoomEvent::RoomAvatar(e)),
+            Event::RoomCanonicalAlias(e) => Ok(RoomEvent::RoomCanonicalAlias(e)),
+            Event::RoomCreate(e) => Ok(RoomEvent::RoomCreate(e)),
+            Event::RoomEncrypted(e) => Ok(RoomEvent::RoomEncrypted(e)),
+            Event::RoomEncryption(e) => Ok(RoomEvent::RoomEncryption(e)),
+            Event::RoomGuestAccess(e) => Ok(RoomEvent::RoomGuestAccess(e)),
+            Event::RoomHistoryVisibility(e) => Ok(RoomEvent::RoomHistoryVisibility(e)),
+            Event::RoomJoinRules(e) => Ok(RoomEvent::RoomJoinRules(e)),
+            Event::RoomMember(e) => Ok(RoomEvent::RoomMember(e)),
+            Event::RoomMessage(e) => Ok(RoomEvent::RoomMessage(e)),
+            Event::RoomMessageFeedback(e) => Ok(RoomEvent::RoomMessageFeedback(e)),
+            Event::RoomName(e) => Ok(RoomEvent::RoomName(e)),
+            Event::RoomPinnedEvents(e) => Ok(RoomEvent::RoomPinnedEvents(e)),
+            Event::RoomPowerLevels(e) => Ok(RoomEvent::RoomPowerLevels(e)),
+            Event::RoomRedaction(e) => Ok(RoomEvent::RoomRedaction(e)),
+            Event::RoomServerAcl(e) => Ok(RoomEvent::RoomServerAcl(e)),
+            Event::RoomThirdPartyInvite(e) => Ok(RoomEvent::RoomThirdPartyInvite(e)),
+            Event::RoomTombstone(e) => Ok(RoomEvent::RoomTombstone(e)),
+            Event::RoomTopic(e) => Ok(RoomEvent::RoomTopic(e)),
+            Event::SpaceChild(e) => Ok(RoomEvent::SpaceChild(e)),
+            Event::SpaceParent(e) => Ok(RoomEvent::SpaceParent(e)),
+            Event::PolicyRuleRoom(e) => Ok(RoomEvent::PolicyRuleRoom(e)),
+            Event::PolicyRuleServer(e) => Ok(RoomEvent::PolicyRuleServer(e)),
+            Event::PolicyRuleUser(e) => Ok(RoomEvent::PolicyRuleUser(e)),
+            Event::Sticker(e) => Ok(RoomEvent::Sticker(e)),
+            Event::CustomRoom(e) => Ok(RoomEvent::CustomRoom(e)),
+            Event::CustomState(e) => Ok(RoomEvent::CustomState(e)),
+            event => Err(event),
+        }
+    }
+}
+
+/// Narrows an `Event` down to a `StateEvent`, giving back the event if it is not a state event.
+impl TryFrom<Event> for StateEvent {
+    type Error = Event;
+
+    fn try_from(event: Event) -> Result<Self, Self::Error> {
+        match event {
+            Event::RoomAliases(e) => Ok(StateEvent::RoomAliases(e)),
+            Event::RoomAvatar(e) => Ok(StateEvent::RoomAvatar(e)),
+            Event::RoomCanonicalAlias(e) => Ok(StateEvent::RoomCanonicalAlias(e)),
+            Event::RoomCreate(e) => Ok(StateEvent::RoomCreate(e)),
+            Event::RoomEncryption(e) => Ok(StateEvent::RoomEncryption(e)),
+            Event::RoomGuestAccess(e) => Ok(StateEvent::RoomGuestAccess(e)),
+            Event::RoomHistoryVisibility(e) => Ok(StateEvent::RoomHistoryVisibility(e)),
+            Event::RoomJoinRules(e) => Ok(StateEvent::RoomJoinRules(e)),
+            Event::RoomMember(e) => Ok(StateEvent::RoomMember(e)),
+            Event::RoomName(e) => Ok(StateEvent::RoomName(e)),
+            Event::RoomPinnedEvents(e) => Ok(StateEvent::RoomPinnedEvents(e)),
+            Event::RoomPowerLevels(e) => Ok(StateEvent::RoomPowerLevels(e)),
+            Event::RoomServerAcl(e) => Ok(StateEvent::RoomServerAcl(e)),
+            Event::RoomThirdPartyInvite(e) => Ok(StateEvent::RoomThirdPartyInvite(e)),
+            Event::RoomTombstone(e) => Ok(StateEvent::RoomTombstone(e)),
+            Event::RoomTopic(e) => Ok(StateEvent::RoomTopic(e)),
+            Event::SpaceChild(e) => Ok(StateEvent::SpaceChild(e)),
+            Event::SpaceParent(e) => Ok(StateEvent::SpaceParent(e)),
+            Event::PolicyRuleRoom(e) => Ok(StateEvent::PolicyRuleRoom(e)),
+            Event::PolicyRuleServer(e) => Ok(StateEvent::PolicyRuleServer(e)),
+            Event::PolicyRuleUser(e) => Ok(StateEvent::PolicyRuleUser(e)),
+            Event::CustomState(e) => Ok(StateEvent::CustomState(e)),
+            event => Err(event),
+        }
+    }
+}
+
+impl_from_str!(Event);
+impl_from_str!(RoomEvent);
+impl_from_str!(StateEvent);
+
+/// Deserializes the `events` array of a room timeline, such as the one in a `/sync` response.
+///
+/// Each event is deserialized and validated on its own, so an invalid event results in an `Err`
+/// at its position without affecting the others. If `events` is not an array, the result is a
+/// single `Err`.
+pub fn deserialize_timeline(events: &RawValue) -> Vec<Result<RoomEvent, InvalidEvent>> {
+    match split_event_array(events) {
+        Ok(events) => events
+            .into_iter()
+            .map(|event| EventResult::from_json_str(event.get()).into_result())
+            .collect(),
+        Err(error) => vec![Err(error)],
+    }
+}
+
 impl_from_for_enum!(Event, AnswerEvent, CallAnswer);
 impl_from_for_enum!(Event, CandidatesEvent, CallCandidates);
 impl_from_for_enum!(Event, HangupEvent, CallHangup);
@@ -480,6 +833,7 @@ impl_from_for_enum!(Event, KeyEvent, KeyVerificationKey);
 impl_from_for_enum!(Event, MacEvent, KeyVerificationMac);
 impl_from_for_enum!(Event, RequestEvent, KeyVerificationRequest);
 impl_from_for_enum!(Event, StartEvent, KeyVerificationStart);
+impl_from_for_enum!(Event, InRoomVerificationEvent, KeyVerificationInRoom);
 impl_from_for_enum!(Event, IgnoredUserListEvent, IgnoredUserList);
 impl_from_for_enum!(Event, PresenceEvent, Presence);
 impl_from_for_enum!(Event, PushRulesEvent, PushRules);
@@ -504,6 +858,11 @@ impl_from_for_enum!(Event, ServerAclEvent, RoomServerAcl);
 impl_from_for_enum!(Event, ThirdPartyInviteEvent, RoomThirdPartyInvite);
 impl_from_for_enum!(Event, TombstoneEvent, RoomTombstone);
 impl_from_for_enum!(Event, TopicEvent, RoomTopic);
+impl_from_for_enum!(Event, SpaceChildEvent, SpaceChild);
+impl_from_for_enum!(Event, SpaceParentEvent, SpaceParent);
+impl_from_for_enum!(Event, PolicyRuleRoomEvent, PolicyRuleRoom);
+impl_from_for_enum!(Event, PolicyRuleServerEvent, PolicyRuleServer);
+impl_from_for_enum!(Event, PolicyRuleUserEvent, PolicyRuleUser);
 impl_from_for_enum!(Event, RoomKeyEvent, RoomKey);
 impl_from_for_enum!(Event, RoomKeyRequestEvent, RoomKeyRequest);
 impl_from_for_enum!(Event, StickerEvent, Sticker);
@@ -517,6 +876,7 @@ impl_from_for_enum!(RoomEvent, AnswerEvent, CallAnswer);
 impl_from_for_enum!(RoomEvent, CandidatesEvent, CallCandidates);
 impl_from_for_enum!(RoomEvent, HangupEvent, CallHangup);
 impl_from_for_enum!(RoomEvent, InviteEvent, CallInvite);
+impl_from_for_enum!(RoomEvent, InRoomVerificationEvent, KeyVerificationInRoom);
 impl_from_for_enum!(RoomEvent, AliasesEvent, RoomAliases);
 impl_from_for_enum!(RoomEvent, AvatarEvent, RoomAvatar);
 impl_from_for_enum!(RoomEvent, CanonicalAliasEvent, RoomCanonicalAlias);
@@ -538,6 +898,11 @@ impl_from_for_enum!(RoomEvent, StickerEvent, Sticker);
 impl_from_for_enum!(RoomEvent, ThirdPartyInviteEvent, RoomThirdPartyInvite);
 impl_from_for_enum!(RoomEvent, TombstoneEvent, RoomTombstone);
 impl_from_for_enum!(RoomEvent, TopicEvent, RoomTopic);
+impl_from_for_enum!(RoomEvent, SpaceChildEvent, SpaceChild);
+impl_from_for_enum!(RoomEvent, SpaceParentEvent, SpaceParent);
+impl_from_for_enum!(RoomEvent, PolicyRuleRoomEvent, PolicyRuleRoom);
+impl_from_for_enum!(RoomEvent, PolicyRuleServerEvent, PolicyRuleServer);
+impl_from_for_enum!(RoomEvent, PolicyRuleUserEvent, PolicyRuleUser);
 impl_from_for_enum!(RoomEvent, CustomRoomEvent, CustomRoom);
 impl_from_for_enum!(RoomEvent, CustomStateEvent, CustomState);
 
@@ -557,4 +922,373 @@ impl_from_for_enum!(StateEvent, ServerAclEvent, RoomServerAcl);
 impl_from_for_enum!(StateEvent, ThirdPartyInviteEvent, RoomThirdPartyInvite);
 impl_from_for_enum!(StateEvent, TombstoneEvent, RoomTombstone);
 impl_from_for_enum!(StateEvent, TopicEvent, RoomTopic);
+impl_from_for_enum!(StateEvent, SpaceChildEvent, SpaceChild);
+impl_from_for_enum!(StateEvent, SpaceParentEvent, SpaceParent);
+impl_from_for_enum!(StateEvent, PolicyRuleRoomEvent, PolicyRuleRoom);
+impl_from_for_enum!(StateEvent, PolicyRuleServerEvent, PolicyRuleServer);
+impl_from_for_enum!(StateEvent, PolicyRuleUserEvent, PolicyRuleUser);
 impl_from_for_enum!(StateEvent, CustomStateEvent, CustomState);
+
+#[cfg(test)]
+mod tests {
+    use std::convert::TryFrom;
+
+    use serde::Deserialize;
+    use serde_json::{from_value as from_json_value, json, value::to_raw_value, Value};
+
+    use super::{deserialize_timeline, is_valid_server_name, Event, RoomEvent, StateEvent};
+    use crate::{key::verification::KeyVerificationContent, CustomEventContent, EventResult};
+
+    fn message(event_id: &str, origin_server_ts: u64) -> RoomEvent {
+        from_json_value::<EventResult<RoomEvent>>(json!({
+            "content": {
+                "body": "test",
+                "msgtype": "m.text"
+            },
+            "event_id": event_id,
+            "origin_server_ts": origin_server_ts,
+            "sender": "@carl:example.com",
+            "type": "m.room.message"
+        }))
+        .unwrap()
+        .into_result()
+        .unwrap()
+    }
+
+    #[test]
+    fn sort_by_timeline_order() {
+        let topic = from_json_value::<EventResult<RoomEvent>>(json!({
+            "content": {
+                "topic": "Testing room"
+            },
+            "event_id": "$b:example.com",
+            "origin_server_ts": 2,
+            "sender": "@carl:example.com",
+            "state_key": "",
+            "type": "m.room.topic"
+        }))
+        .unwrap()
+        .into_result()
+        .unwrap();
+
+        let mut events = [
+            message("$d:example.com", 3),
+            topic,
+            message("$c:example.com", 2),
+            message("$a:example.com", 1),
+        ];
+        events.sort_by(RoomEvent::cmp_timeline_order);
+
+        let event_ids: Vec<String> = events
+            .iter()
+            .map(|event| event.timeline_ordering_key().1.to_string())
+            .collect();
+
+        assert_eq!(
+            event_ids,
+            vec![
+                "$a:example.com",
+                "$b:example.com",
+                "$c:example.com",
+                "$d:example.com"
+            ]
+        );
+    }
+
+    #[test]
+    fn deserialize_mixed_timeline() {
+        let events = to_raw_value(&json!([
+            {
+                "content": {
+                    "body": "test",
+                    "msgtype": "m.text"
+                },
+                "event_id": "$a:example.com",
+                "origin_server_ts": 1,
+                "sender": "@carl:example.com",
+                "type": "m.room.message"
+            },
+            {
+                "content": {
+                    "topic": "Testing room"
+                },
+                "event_id": "not an event ID",
+                "origin_server_ts": 2,
+                "sender": "@carl:example.com",
+                "state_key": "",
+                "type": "m.room.topic"
+            },
+            42,
+            {
+                "content": {
+                    "topic": "Testing room"
+                },
+                "event_id": "$c:example.com",
+                "origin_server_ts": 3,
+                "sender": "@carl:example.com",
+                "state_key": "",
+                "type": "m.room.topic"
+            }
+        ]))
+        .unwrap();
+
+        let timeline = deserialize_timeline(&events);
+
+        assert_eq!(timeline.len(), 4);
+        match &timeline[0] {
+            Ok(RoomEvent::RoomMessage(_)) => {}
+            _ => panic!("expected a valid m.room.message event"),
+        }
+        assert!(timeline[1].as_ref().unwrap_err().is_deserialization());
+        assert!(timeline[2].is_err());
+        match &timeline[3] {
+            Ok(RoomEvent::RoomTopic(_)) => {}
+            _ => panic!("expected a valid m.room.topic event"),
+        }
+
+        let not_an_array = to_raw_value(&json!({ "events": [] })).unwrap();
+        assert!(deserialize_timeline(&not_an_array)[0].is_err());
+    }
+
+    #[test]
+    fn key_verification_from_room_message() {
+        let event = from_json_value::<EventResult<Event>>(json!({
+            "content": {
+                "body": "Alice is requesting to verify your device",
+                "from_device": "AliceDevice2",
+                "methods": ["m.sas.v1"],
+                "msgtype": "m.key.verification.request",
+                "to": "@bob:example.com"
+            },
+            "event_id": "$request:example.com",
+            "origin_server_ts": 1,
+            "sender": "@alice:example.com",
+            "type": "m.room.message"
+        }))
+        .unwrap()
+        .into_result()
+        .unwrap();
+
+        match event.as_key_verification() {
+            Some(KeyVerificationContent::RoomRequest(request)) => {
+                assert_eq!(request.from_device, "AliceDevice2");
+            }
+            other => panic!("unexpected verification content: {:?}", other),
+        }
+    }
+
+    #[test]
+    fn key_verification_from_accept_room_event() {
+        let json = json!({
+            "content": {
+                "method": "m.sas.v1",
+                "key_agreement_protocol": "curve25519",
+                "hash": "sha256",
+                "message_authentication_code": "hkdf-hmac-sha256",
+                "short_authentication_string": ["decimal"],
+                "commitment": "fQpGIW1Snz+pwLZu6W4T3XJ6l8elZIuRcCu5GdG5w2M",
+                "m.relates_to": {
+                    "rel_type": "m.reference",
+                    "event_id": "$request:example.com"
+                }
+            },
+            "event_id": "$accept:example.com",
+            "origin_server_ts": 2,
+            "room_id": "!room:example.com",
+            "sender": "@bob:example.com",
+            "type": "m.key.verification.accept"
+        });
+        let event = from_json_value::<EventResult<Event>>(json.clone())
+            .unwrap()
+            .into_result()
+            .unwrap();
+
+        match event.as_key_verification() {
+            Some(KeyVerificationContent::Accept(accept)) => {
+                assert_eq!(accept.transaction_id, "$request:example.com");
+            }
+            other => panic!("unexpected verification content: {:?}", other),
+        }
+
+        let room_event = from_json_value::<EventResult<RoomEvent>>(json)
+            .unwrap()
+            .into_result()
+            .unwrap();
+
+        match &room_event {
+            RoomEvent::KeyVerificationInRoom(event) => {
+                assert_eq!(event.sender.to_string(), "@bob:example.com");
+            }
+            other => panic!("expected an in-room verification event, found {:?}", other),
+        }
+        match room_event.as_key_verification() {
+            Some(KeyVerificationContent::Accept(accept)) => {
+                assert_eq!(accept.transaction_id, "$request:example.com");
+            }
+            other => panic!("unexpected verification content: {:?}", other),
+        }
+    }
+
+    #[test]
+    fn no_key_verification_in_text_message() {
+        let event = from_json_value::<EventResult<Event>>(json!({
+            "content": { "body": "test", "msgtype": "m.text" },
+            "event_id": "$message:example.com",
+            "origin_server_ts": 1,
+            "sender": "@carl:example.com",
+            "type": "m.room.message"
+        }))
+        .unwrap()
+        .into_result()
+        .unwrap();
+
+        assert!(event.as_key_verification().is_none());
+    }
+
+    #[derive(Debug, Deserialize, PartialEq)]
+    struct PollContent {
+        question: String,
+        answers: Vec<String>,
+    }
+
+    impl CustomEventContent for PollContent {
+        const EVENT_TYPE: &'static str = "com.example.poll";
+    }
+
+    #[test]
+    fn deserialize_custom_content() {
+        let poll = from_json_value::<EventResult<Event>>(json!({
+            "content": {
+                "question": "Lunch?",
+                "answers": ["Pizza", "Salad"]
+            },
+            "event_id": "$poll:example.com",
+            "origin_server_ts": 1,
+            "sender": "@carl:example.com",
+            "type": "com.example.poll"
+        }))
+        .unwrap()
+        .into_result()
+        .unwrap();
+
+        assert_eq!(
+            poll.deserialize_custom::<PollContent>().unwrap().unwrap(),
+            PollContent {
+                question: "Lunch?".to_string(),
+                answers: vec!["Pizza".to_string(), "Salad".to_string()],
+            }
+        );
+
+        let other = from_json_value::<EventResult<Event>>(json!({
+            "content": { "question": "Lunch?" },
+            "type": "com.example.other"
+        }))
+        .unwrap()
+        .into_result()
+        .unwrap();
+
+        assert!(other.deserialize_custom::<PollContent>().is_none());
+    }
+
+    fn state_event(event_type: &str, state_key: &str, content: serde_json::Value) -> StateEvent {
+        from_json_value::<EventResult<StateEvent>>(json!({
+            "content": content,
+            "event_id": "$h29iv0s8:example.com",
+            "origin_server_ts": 1,
+            "sender": "@carl:example.com",
+            "state_key": state_key,
+            "type": event_type
+        }))
+        .unwrap()
+        .into_result()
+        .unwrap()
+    }
+
+    #[test]
+    fn member_state_key() {
+        let content = json!({ "membership": "join" });
+
+        assert!(
+            state_event("m.room.member", "@carl:example.com", content.clone()).state_key_is_valid()
+        );
+        assert!(!state_event("m.room.member", "carl", content).state_key_is_valid());
+    }
+
+    #[test]
+    fn aliases_state_key() {
+        let content = json!({ "aliases": ["#room:example.com"] });
+
+        assert!(state_event("m.room.aliases", "example.com", content.clone()).state_key_is_valid());
+        assert!(!state_event("m.room.aliases", "@carl:example.com", content).state_key_is_valid());
+        assert!(
+            state_event("m.room.topic", "anything", json!({ "topic": "Test" }))
+                .state_key_is_valid()
+        );
+    }
+
+    #[test]
+    fn server_names() {
+        assert!(is_valid_server_name("example.com"));
+        assert!(is_valid_server_name("example.com:8448"));
+        assert!(is_valid_server_name("1.2.3.4"));
+        assert!(is_valid_server_name("[1234:5678::abcd]"));
+        assert!(is_valid_server_name("[1234:5678::abcd]:5678"));
+        assert!(!is_valid_server_name(""));
+        assert!(!is_valid_server_name("example.com:"));
+        assert!(!is_valid_server_name("example.com:123456"));
+        assert!(is_valid_server_name("example.com:65535"));
+        assert!(!is_valid_server_name("example.com:65536"));
+        assert!(!is_valid_server_name("example.com:99999"));
+        assert!(!is_valid_server_name("example.com:+80"));
+        assert!(!is_valid_server_name("exa mple.com"));
+        assert!(!is_valid_server_name("::1"));
+    }
+
+    fn event(json: Value) -> Event {
+        from_json_value::<EventResult<Event>>(json)
+            .unwrap()
+            .into_result()
+            .unwrap()
+    }
+
+    #[test]
+    fn narrow_message() {
+        let message = event(json!({
+            "content": { "body": "test", "msgtype": "m.text" },
+            "event_id": "$message:example.com",
+            "origin_server_ts": 1,
+            "sender": "@carl:example.com",
+            "type": "m.room.message"
+        }));
+
+        match RoomEvent::try_from(message.clone()) {
+            Ok(RoomEvent::RoomMessage(_)) => {}
+            other => panic!("expected a room message, found {:?}", other),
+        }
+        match StateEvent::try_from(message) {
+            Err(Event::RoomMessage(_)) => {}
+            other => panic!("expected the room message back, found {:?}", other),
+        }
+    }
+
+    #[test]
+    fn narrow_member() {
+        let member = event(json!({
+            "content": { "membership": "join" },
+            "event_id": "$member:example.com",
+            "origin_server_ts": 1,
+            "sender": "@carl:example.com",
+            "state_key": "@carl:example.com",
+            "type": "m.room.member"
+        }));
+
+        match RoomEvent::try_from(member.clone()) {
+            Ok(RoomEvent::RoomMember(_)) => {}
+            other => panic!("expected a room member event, found {:?}", other),
+        }
+        match StateEvent::try_from(member) {
+            Ok(StateEvent::RoomMember(_)) => {}
+            other => panic!("expected a room member event, found {:?}", other),
+        }
+    }
+}
@@ -0,0 +1,188 @@
+//! An enum for the state events that are commonly sent as the `initial_state` of a room when
+//! creating it.
+
+use serde::{ser::SerializeStruct, Serialize, Serializer};
+
+use crate::{
+    room::{
+        encryption::EncryptionEventContent,
+        guest_access::{GuestAccess, GuestAccessEventContent},
+        history_visibility::{HistoryVisibility, HistoryVisibilityEventContent},
+        join_rules::{JoinRule, JoinRulesEventContent},
+        name::NameEventContent,
+        topic::TopicEventContent,
+    },
+    EventEncryptionAlgorithm, EventType, InvalidInput,
+};
+
+/// A state event to be included in the `initial_state` of a `POST /createRoom` request.
+///
+/// All of these events have an empty state key. They serialize to the
+/// `{ "type": ..., "state_key": "", "content": ... }` objects the endpoint expects.
+#[derive(Clone, Debug, PartialEq)]
+pub enum InitialStateEvent {
+    /// m.room.encryption
+    RoomEncryption(EncryptionEventContent),
+
+    /// m.room.guest_access
+    RoomGuestAccess(GuestAccessEventContent),
+
+    /// m.room.history_visibility
+    RoomHistoryVisibility(HistoryVisibilityEventContent),
+
+    /// m.room.join_rules
+    RoomJoinRules(JoinRulesEventContent),
+
+    /// m.room.name
+    RoomName(NameEventContent),
+
+    /// m.room.topic
+    RoomTopic(TopicEventContent),
+}
+
+impl InitialStateEvent {
+    /// Creates an *m.room.encryption* event using the given algorithm, leaving the session
+    /// rotation settings at their defaults.
+    pub fn encryption(algorithm: EventEncryptionAlgorithm) -> Self {
+        InitialStateEvent::RoomEncryption(EncryptionEventContent {
+            algorithm,
+            rotation_period_ms: None,
+            rotation_period_msgs: None,
+        })
+    }
+
+    /// Creates an *m.room.guest_access* event.
+    pub fn guest_access(guest_access: GuestAccess) -> Self {
+        InitialStateEvent::RoomGuestAccess(GuestAccessEventContent::new(guest_access))
+    }
+
+    /// Creates an *m.room.history_visibility* event.
+    pub fn history_visibility(history_visibility: HistoryVisibility) -> Self {
+        InitialStateEvent::RoomHistoryVisibility(HistoryVisibilityEventContent::new(
+            history_visibility,
+        ))
+    }
+
+    /// Creates an *m.room.join_rules* event.
+    pub fn join_rules(join_rule: JoinRule) -> Self {
+        InitialStateEvent::RoomJoinRules(JoinRulesEventContent {
+            join_rule,
+            allow: Vec::new(),
+        })
+    }
+
+    /// Creates an *m.room.name* event.
+    ///
+    /// # Errors
+    ///
+    /// `InvalidInput` will be returned if the name is more than 255 bytes.
+    pub fn name(name: String) -> Result<Self, InvalidInput> {
+        NameEventContent::new(name).map(InitialStateEvent::RoomName)
+    }
+
+    /// Creates an *m.room.topic* event.
+    pub fn topic(topic: String) -> Self {
+        InitialStateEvent::RoomTopic(TopicEventContent { topic })
+    }
+
+    /// The type of this event.
+    pub fn event_type(&self) -> EventType {
+        match self {
+            InitialStateEvent::RoomEncryption(_) => EventType::RoomEncryption,
+            InitialStateEvent::RoomGuestAccess(_) => EventType::RoomGuestAccess,
+            InitialStateEvent::RoomHistoryVisibility(_) => EventType::RoomHistoryVisibility,
+            InitialStateEvent::RoomJoinRules(_) => EventType::RoomJoinRules,
+            InitialStateEvent::RoomName(_) => EventType::RoomName,
+            InitialStateEvent::RoomTopic(_) => EventType::RoomTopic,
+        }
+    }
+}
+
+impl Serialize for InitialStateEvent {
+    fn serialize<S>(&self, serializer: S) -> Result<S::Ok, S::Error>
+    where
+        S: Serializer,
+    {
+        let mut state = serializer.serialize_struct("InitialStateEvent", 3)?;
+
+        state.serialize_field("type", &self.event_type())?;
+        state.serialize_field("state_key", "")?;
+
+        match self {
+            InitialStateEvent::RoomEncryption(content) => {
+                state.serialize_field("content", content)?
+            }
+            InitialStateEvent::RoomGuestAccess(content) => {
+                state.serialize_field("content", content)?
+            }
+            InitialStateEvent::RoomHistoryVisibility(content) => {
+                state.serialize_field("content", content)?
+            }
+            InitialStateEvent::RoomJoinRules(content) => {
+                state.serialize_field("content", content)?
+            }
+            InitialStateEvent::RoomName(content) => state.serialize_field("content", content)?,
+            InitialStateEvent::RoomTopic(content) => state.serialize_field("content", content)?,
+        }
+
+        state.end()
+    }
+}
+
+impl_from_for_enum!(InitialStateEvent, EncryptionEventContent, RoomEncryption);
+impl_from_for_enum!(InitialStateEvent, GuestAccessEventContent, RoomGuestAccess);
+impl_from_for_enum!(
+    InitialStateEvent,
+    HistoryVisibilityEventContent,
+    RoomHistoryVisibility
+);
+impl_from_for_enum!(InitialStateEvent, JoinRulesEventContent, RoomJoinRules);
+impl_from_for_enum!(InitialStateEvent, NameEventContent, RoomName);
+impl_from_for_enum!(InitialStateEvent, TopicEventContent, RoomTopic);
+
+#[cfg(test)]
+mod tests {
+    use serde_json::{json, to_value as to_json_value};
+
+    use super::InitialStateEvent;
+    use crate::{room::join_rules::JoinRule, EventEncryptionAlgorithm, EventType};
+
+    #[test]
+    fn initial_state_array() {
+        let initial_state = vec![
+            InitialStateEvent::name("Ruma room".to_string()).unwrap(),
+            InitialStateEvent::topic("Testing room".to_string()),
+            InitialStateEvent::encryption(EventEncryptionAlgorithm::MegolmV1AesSha2),
+        ];
+
+        assert_eq!(
+            to_json_value(&initial_state).unwrap(),
+            json!([
+                {
+                    "type": "m.room.name",
+                    "state_key": "",
+                    "content": { "name": "Ruma room" }
+                },
+                {
+                    "type": "m.room.topic",
+                    "state_key": "",
+                    "content": { "topic": "Testing room" }
+                },
+                {
+                    "type": "m.room.encryption",
+                    "state_key": "",
+                    "content": { "algorithm": "m.megolm.v1.aes-sha2" }
+                }
+            ])
+        );
+    }
+
+    #[test]
+    fn event_type() {
+        assert_eq!(
+            InitialStateEvent::join_rules(JoinRule::Invite).event_type(),
+            EventType::RoomJoinRules
+        );
+        assert!(InitialStateEvent::name("a".repeat(256)).is_err());
+    }
+}
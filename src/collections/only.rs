@@ -2,6 +2,7 @@
 //! most" the trait of the same name.
 
 use serde::Serialize;
+use serde_json::value::RawValue;
 
 pub use super::all::StateEvent;
 use super::raw::only as raw;
@@ -133,6 +134,63 @@ pub enum RoomEvent {
     CustomRoom(CustomRoomEvent),
 }
 
+/// A room event that is not a state event.
+///
+/// This is meant for consumers that only care about the messages in a room's timeline. State
+/// events, including custom ones, are not parsed into their typed representation but kept as raw
+/// JSON in the `State` variant, so invalid or unknown state events don't cause errors either.
+#[derive(Clone, Debug, Serialize)]
+#[serde(untagged)]
+#[allow(clippy::large_enum_variant)]
+pub enum MessageLikeEvent {
+    /// m.call.answer
+    CallAnswer(AnswerEvent),
+
+    /// m.call.candidates
+    CallCandidates(CandidatesEvent),
+
+    /// m.call.hangup
+    CallHangup(HangupEvent),
+
+    /// m.call.invite
+    CallInvite(InviteEvent),
+
+    /// m.room.encrypted
+    RoomEncrypted(EncryptedEvent),
+
+    /// m.room.message
+    RoomMessage(MessageEvent),
+
+    /// m.room.message.feedback
+    RoomMessageFeedback(FeedbackEvent),
+
+    /// m.room.redaction
+    RoomRedaction(RedactionEvent),
+
+    /// m.sticker
+    Sticker(StickerEvent),
+
+    /// Any room event that is not part of the specification.
+    CustomRoom(CustomRoomEvent),
+
+    /// Any state event, left unparsed.
+    State(Box<RawValue>),
+}
+
+/// An ephemeral event, as found in the `ephemeral` section of a joined room in a sync response.
+#[derive(Clone, Debug, Serialize)]
+#[serde(untagged)]
+pub enum EphemeralEvent {
+    /// m.receipt
+    Receipt(ReceiptEvent),
+
+    /// m.typing
+    Typing(TypingEvent),
+
+    /// Any basic event that is not part of the specification.
+    Custom(CustomEvent),
+}
+
 impl TryFromRaw for Event {
     type Raw = raw::Event;
     type Err = String;
@@ -188,6 +246,51 @@ impl TryFromRaw for RoomEvent {
     }
 }
 
+impl TryFromRaw for MessageLikeEvent {
+    type Raw = raw::MessageLikeEvent;
+    type Err = String;
+
+    fn try_from_raw(raw: raw::MessageLikeEvent) -> Result<Self, Self::Err> {
+        use crate::util::try_convert_variant as conv;
+        use raw::MessageLikeEvent::*;
+
+        match raw {
+            CallAnswer(c) => conv(MessageLikeEvent::CallAnswer, c),
+            CallCandidates(c) => conv(MessageLikeEvent::CallCandidates, c),
+            CallHangup(c) => conv(MessageLikeEvent::CallHangup, c),
+            CallInvite(c) => conv(MessageLikeEvent::CallInvite, c),
+            RoomEncrypted(c) => conv(MessageLikeEvent::RoomEncrypted, c),
+            RoomMessage(c) => conv(MessageLikeEvent::RoomMessage, c),
+            RoomMessageFeedback(c) => conv(MessageLikeEvent::RoomMessageFeedback, c),
+            RoomRedaction(c) => conv(MessageLikeEvent::RoomRedaction, c),
+            Sticker(c) => conv(MessageLikeEvent::Sticker, c),
+            CustomRoom(c) => conv(MessageLikeEvent::CustomRoom, c),
+            State(json) => Ok(MessageLikeEvent::State(json)),
+        }
+    }
+}
+
+impl TryFromRaw for EphemeralEvent {
+    type Raw = raw::EphemeralEvent;
+    type Err = String;
+
+    fn try_from_raw(raw: raw::EphemeralEvent) -> Result<Self, Self::Err> {
+        use crate::util::try_convert_variant as conv;
+        use raw::EphemeralEvent::*;
+
+        match raw {
+            Receipt(c) => conv(EphemeralEvent::Receipt, c),
+            Typing(c) => conv(EphemeralEvent::Typing, c),
+            Custom(c) => conv(EphemeralEvent::Custom, c),
+        }
+    }
+}
+
+impl_from_str!(Event);
+impl_from_str!(RoomEvent);
+impl_from_str!(MessageLikeEvent);
+impl_from_str!(EphemeralEvent);
+
 impl_from_for_enum!(Event, DirectEvent, Direct);
 impl_from_for_enum!(Event, DummyEvent, Dummy);
 impl_from_for_enum!(Event, ForwardedRoomKeyEvent, ForwardedRoomKey);
@@ -216,3 +319,164 @@ impl_from_for_enum!(RoomEvent, FeedbackEvent, RoomMessageFeedback);
 impl_from_for_enum!(RoomEvent, RedactionEvent, RoomRedaction);
 impl_from_for_enum!(RoomEvent, StickerEvent, Sticker);
 impl_from_for_enum!(RoomEvent, CustomRoomEvent, CustomRoom);
+
+impl_from_for_enum!(MessageLikeEvent, AnswerEvent, CallAnswer);
+impl_from_for_enum!(MessageLikeEvent, CandidatesEvent, CallCandidates);
+impl_from_for_enum!(MessageLikeEvent, HangupEvent, CallHangup);
+impl_from_for_enum!(MessageLikeEvent, InviteEvent, CallInvite);
+impl_from_for_enum!(MessageLikeEvent, EncryptedEvent, RoomEncrypted);
+impl_from_for_enum!(MessageLikeEvent, MessageEvent, RoomMessage);
+impl_from_for_enum!(MessageLikeEvent, FeedbackEvent, RoomMessageFeedback);
+impl_from_for_enum!(MessageLikeEvent, RedactionEvent, RoomRedaction);
+impl_from_for_enum!(MessageLikeEvent, StickerEvent, Sticker);
+impl_from_for_enum!(MessageLikeEvent, CustomRoomEvent, CustomRoom);
+
+impl_from_for_enum!(EphemeralEvent, ReceiptEvent, Receipt);
+impl_from_for_enum!(EphemeralEvent, TypingEvent, Typing);
+impl_from_for_enum!(EphemeralEvent, CustomEvent, Custom);
+
+#[cfg(test)]
+mod tests {
+    use serde_json::{from_value as from_json_value, json};
+
+    use super::{raw, EphemeralEvent, MessageLikeEvent};
+    use crate::EventResult;
+
+    #[test]
+    fn timeline_with_state_events() {
+        let timeline = json!([
+            {
+                "content": {
+                    "body": "Hello",
+                    "msgtype": "m.text"
+                },
+                "event_id": "$h29iv0s8:example.com",
+                "origin_server_ts": 1,
+                "sender": "@carl:example.com",
+                "type": "m.room.message"
+            },
+            {
+                "content": {
+                    "membership": "not a membership state"
+                },
+                "event_id": "$h29iv0s9:example.com",
+                "origin_server_ts": 2,
+                "sender": "@carl:example.com",
+                "state_key": "@carl:example.com",
+                "type": "m.room.member"
+            },
+            {
+                "content": {
+                    "anything": ["goes"]
+                },
+                "event_id": "$h29iv0s0:example.com",
+                "origin_server_ts": 3,
+                "sender": "@carl:example.com",
+                "state_key": "",
+                "type": "org.example.custom_state"
+            },
+            {
+                "content": {},
+                "event_id": "$h29iv0s1:example.com",
+                "origin_server_ts": 4,
+                "sender": "@carl:example.com",
+                "type": "org.example.custom"
+            }
+        ]);
+
+        let events: Vec<MessageLikeEvent> =
+            from_json_value::<Vec<EventResult<MessageLikeEvent>>>(timeline)
+                .unwrap()
+                .into_iter()
+                .map(|event| event.into_result().unwrap())
+                .collect();
+
+        match &events[..] {
+            [MessageLikeEvent::RoomMessage(_), MessageLikeEvent::State(member), MessageLikeEvent::State(custom), MessageLikeEvent::CustomRoom(_)] =>
+            {
+                assert!(member.get().contains("not a membership state"));
+                assert!(custom.get().contains("org.example.custom_state"));
+            }
+            _ => panic!("unexpected events: {:?}", events),
+        }
+    }
+
+    #[test]
+    fn state_event_kept_verbatim() {
+        let json = r#"{ "type": "m.room.member", "state_key": "@carl:example.com",
+            "content": { "membership": 42, "nested": { "deep": [1, 2, 3] } },
+            "event_id": "$h29iv0s9:example.com", "sender": "@carl:example.com" }"#;
+
+        match serde_json::from_str::<raw::MessageLikeEvent>(json).unwrap() {
+            raw::MessageLikeEvent::State(event) => assert_eq!(event.get(), json),
+            _ => panic!("expected a state event"),
+        }
+    }
+
+    #[test]
+    fn non_room_event_type() {
+        let json = json!({
+            "content": {
+                "user_ids": []
+            },
+            "type": "m.typing"
+        });
+
+        assert!(from_json_value::<EventResult<MessageLikeEvent>>(json)
+            .unwrap()
+            .into_result()
+            .unwrap_err()
+            .is_deserialization());
+    }
+
+    #[test]
+    fn ephemeral_batch() {
+        let ephemeral = json!([
+            {
+                "content": {
+                    "user_ids": ["@alice:example.com"]
+                },
+                "type": "m.typing"
+            },
+            {
+                "content": {
+                    "$h29iv0s8:example.com": {
+                        "m.read": {
+                            "@alice:example.com": { "ts": 1 }
+                        }
+                    }
+                },
+                "type": "m.receipt"
+            }
+        ]);
+
+        let events: Vec<EphemeralEvent> =
+            from_json_value::<Vec<EventResult<EphemeralEvent>>>(ephemeral)
+                .unwrap()
+                .into_iter()
+                .map(|event| event.into_result().unwrap())
+                .collect();
+
+        match &events[..] {
+            [EphemeralEvent::Typing(typing), EphemeralEvent::Receipt(receipt)] => {
+                assert_eq!(typing.content.user_ids.len(), 1);
+                assert_eq!(receipt.content.len(), 1);
+            }
+            _ => panic!("unexpected events: {:?}", events),
+        }
+    }
+
+    #[test]
+    fn non_ephemeral_event_type() {
+        let json = json!({
+            "content": {},
+            "type": "m.dummy"
+        });
+
+        assert!(from_json_value::<EventResult<EphemeralEvent>>(json)
+            .unwrap()
+            .into_result()
+            .unwrap_err()
+            .is_deserialization());
+    }
+}
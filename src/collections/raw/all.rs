@@ -19,8 +19,12 @@ use crate::{
     fully_read::raw::FullyReadEvent,
     ignored_user_list::raw::IgnoredUserListEvent,
     key::verification::{
-        accept::raw::AcceptEvent, cancel::raw::CancelEvent, key::raw::KeyEvent, mac::raw::MacEvent,
-        request::raw::RequestEvent, start::raw::StartEvent,
+        accept::raw::AcceptEvent, cancel::raw::CancelEvent, in_room::raw::InRoomVerificationEvent,
+        key::raw::KeyEvent, mac::raw::MacEvent, request::raw::RequestEvent, start::raw::StartEvent,
+    },
+    policy::rule::{
+        room::raw::PolicyRuleRoomEvent, server::raw::PolicyRuleServerEvent,
+        user::raw::PolicyRuleUserEvent,
     },
     presence::raw::PresenceEvent,
     push_rules::raw::PushRulesEvent,
@@ -48,6 +52,7 @@ use crate::{
     },
     room_key::raw::RoomKeyEvent,
     room_key_request::raw::RoomKeyRequestEvent,
+    space::{child::raw::SpaceChildEvent, parent::raw::SpaceParentEvent},
     sticker::raw::StickerEvent,
     tag::raw::TagEvent,
     typing::raw::TypingEvent,
@@ -104,6 +109,9 @@ pub enum Event {
     /// m.key.verification.start
     KeyVerificationStart(StartEvent),
 
+    /// m.key.verification.* sent in a room
+    KeyVerificationInRoom(InRoomVerificationEvent),
+
     /// m.presence
     Presence(PresenceEvent),
 
@@ -173,6 +181,21 @@ pub enum Event {
     /// m.room.topic
     RoomTopic(TopicEvent),
 
+    /// m.space.child
+    SpaceChild(SpaceChildEvent),
+
+    /// m.space.parent
+    SpaceParent(SpaceParentEvent),
+
+    /// m.policy.rule.room
+    PolicyRuleRoom(PolicyRuleRoomEvent),
+
+    /// m.policy.rule.server
+    PolicyRuleServer(PolicyRuleServerEvent),
+
+    /// m.policy.rule.user
+    PolicyRuleUser(PolicyRuleUserEvent),
+
     /// m.room_key
     RoomKey(RoomKeyEvent),
 
@@ -214,6 +237,9 @@ pub enum RoomEvent {
     /// m.call.invite
     CallInvite(InviteEvent),
 
+    /// m.key.verification.* sent in a room
+    KeyVerificationInRoom(InRoomVerificationEvent),
+
     /// m.room.aliases
     RoomAliases(AliasesEvent),
 
@@ -274,6 +300,21 @@ pub enum RoomEvent {
     /// m.room.topic
     RoomTopic(TopicEvent),
 
+    /// m.space.child
+    SpaceChild(SpaceChildEvent),
+
+    /// m.space.parent
+    SpaceParent(SpaceParentEvent),
+
+    /// m.policy.rule.room
+    PolicyRuleRoom(PolicyRuleRoomEvent),
+
+    /// m.policy.rule.server
+    PolicyRuleServer(PolicyRuleServerEvent),
+
+    /// m.policy.rule.user
+    PolicyRuleUser(PolicyRuleUserEvent),
+
     /// m.sticker
     Sticker(StickerEvent),
 
@@ -336,6 +377,21 @@ pub enum StateEvent {
     /// m.room.topic
     RoomTopic(TopicEvent),
 
+    /// m.space.child
+    SpaceChild(SpaceChildEvent),
+
+    /// m.space.parent
+    SpaceParent(SpaceParentEvent),
+
+    /// m.policy.rule.room
+    PolicyRuleRoom(PolicyRuleRoomEvent),
+
+    /// m.policy.rule.server
+    PolicyRuleServer(PolicyRuleServerEvent),
+
+    /// m.policy.rule.user
+    PolicyRuleUser(PolicyRuleUserEvent),
+
     /// Any state event that is not part of the specification.
     CustomState(CustomStateEvent),
 }
@@ -361,11 +417,26 @@ impl<'de> Deserialize<'de> for Event {
             ForwardedRoomKey => from_value(value, Event::ForwardedRoomKey),
             FullyRead => from_value(value, Event::FullyRead),
             IgnoredUserList => from_value(value, Event::IgnoredUserList),
+            KeyVerificationAccept if value.get("event_id").is_some() => {
+                from_value(value, Event::KeyVerificationInRoom)
+            }
             KeyVerificationAccept => from_value(value, Event::KeyVerificationAccept),
+            KeyVerificationCancel if value.get("event_id").is_some() => {
+                from_value(value, Event::KeyVerificationInRoom)
+            }
             KeyVerificationCancel => from_value(value, Event::KeyVerificationCancel),
+            KeyVerificationKey if value.get("event_id").is_some() => {
+                from_value(value, Event::KeyVerificationInRoom)
+            }
             KeyVerificationKey => from_value(value, Event::KeyVerificationKey),
+            KeyVerificationMac if value.get("event_id").is_some() => {
+                from_value(value, Event::KeyVerificationInRoom)
+            }
             KeyVerificationMac => from_value(value, Event::KeyVerificationMac),
             KeyVerificationRequest => from_value(value, Event::KeyVerificationRequest),
+            KeyVerificationStart if value.get("event_id").is_some() => {
+                from_value(value, Event::KeyVerificationInRoom)
+            }
             KeyVerificationStart => from_value(value, Event::KeyVerificationStart),
             Presence => from_value(value, Event::Presence),
             PushRules => from_value(value, Event::PushRules),
@@ -390,6 +461,11 @@ impl<'de> Deserialize<'de> for Event {
             RoomThirdPartyInvite => from_value(value, Event::RoomThirdPartyInvite),
             RoomTombstone => from_value(value, Event::RoomTombstone),
             RoomTopic => from_value(value, Event::RoomTopic),
+            SpaceChild => from_value(value, Event::SpaceChild),
+            SpaceParent => from_value(value, Event::SpaceParent),
+            PolicyRuleRoom => from_value(value, Event::PolicyRuleRoom),
+            PolicyRuleServer => from_value(value, Event::PolicyRuleServer),
+            PolicyRuleUser => from_value(value, Event::PolicyRuleUser),
             RoomKey => from_value(value, Event::RoomKey),
             RoomKeyRequest => from_value(value, Event::RoomKeyRequest),
             Sticker => from_value(value, Event::Sticker),
@@ -430,6 +506,11 @@ impl<'de> Deserialize<'de> for RoomEvent {
             CallCandidates => from_value(value, RoomEvent::CallCandidates),
             CallHangup => from_value(value, RoomEvent::CallHangup),
             CallInvite => from_value(value, RoomEvent::CallInvite),
+            KeyVerificationAccept
+            | KeyVerificationCancel
+            | KeyVerificationKey
+            | KeyVerificationMac
+            | KeyVerificationStart => from_value(value, RoomEvent::KeyVerificationInRoom),
             RoomAliases => from_value(value, RoomEvent::RoomAliases),
             RoomAvatar => from_value(value, RoomEvent::RoomAvatar),
             RoomCanonicalAlias => from_value(value, RoomEvent::RoomCanonicalAlias),
@@ -450,6 +531,11 @@ impl<'de> Deserialize<'de> for RoomEvent {
             RoomThirdPartyInvite => from_value(value, RoomEvent::RoomThirdPartyInvite),
             RoomTombstone => from_value(value, RoomEvent::RoomTombstone),
             RoomTopic => from_value(value, RoomEvent::RoomTopic),
+            SpaceChild => from_value(value, RoomEvent::SpaceChild),
+            SpaceParent => from_value(value, RoomEvent::SpaceParent),
+            PolicyRuleRoom => from_value(value, RoomEvent::PolicyRuleRoom),
+            PolicyRuleServer => from_value(value, RoomEvent::PolicyRuleServer),
+            PolicyRuleUser => from_value(value, RoomEvent::PolicyRuleUser),
             Sticker => from_value(value, RoomEvent::Sticker),
             Custom(_event_type_name) => {
                 if value.get("state_key").is_some() {
@@ -463,12 +549,7 @@ impl<'de> Deserialize<'de> for RoomEvent {
             | ForwardedRoomKey
             | FullyRead
             | IgnoredUserList
-            | KeyVerificationAccept
-            | KeyVerificationCancel
-            | KeyVerificationKey
-            | KeyVerificationMac
             | KeyVerificationRequest
-            | KeyVerificationStart
             | Presence
             | PushRules
             | Receipt
@@ -511,6 +592,11 @@ impl<'de> Deserialize<'de> for StateEvent {
             RoomThirdPartyInvite => from_value(value, StateEvent::RoomThirdPartyInvite),
             RoomTombstone => from_value(value, StateEvent::RoomTombstone),
             RoomTopic => from_value(value, StateEvent::RoomTopic),
+            SpaceChild => from_value(value, StateEvent::SpaceChild),
+            SpaceParent => from_value(value, StateEvent::SpaceParent),
+            PolicyRuleRoom => from_value(value, StateEvent::PolicyRuleRoom),
+            PolicyRuleServer => from_value(value, StateEvent::PolicyRuleServer),
+            PolicyRuleUser => from_value(value, StateEvent::PolicyRuleUser),
             Custom(_event_type_name) => from_value(value, StateEvent::CustomState),
             CallAnswer
             | CallCandidates
@@ -583,6 +669,7 @@ impl From<RoomEvent> for Event {
             CallCandidates(ev) => Event::CallCandidates(ev),
             CallHangup(ev) => Event::CallHangup(ev),
             CallInvite(ev) => Event::CallInvite(ev),
+            KeyVerificationInRoom(ev) => Event::KeyVerificationInRoom(ev),
             RoomAliases(ev) => Event::RoomAliases(ev),
             RoomAvatar(ev) => Event::RoomAvatar(ev),
             RoomCanonicalAlias(ev) => Event::RoomCanonicalAlias(ev),
@@ -603,6 +690,11 @@ impl From<RoomEvent> for Event {
             RoomThirdPartyInvite(ev) => Event::RoomThirdPartyInvite(ev),
             RoomTombstone(ev) => Event::RoomTombstone(ev),
             RoomTopic(ev) => Event::RoomTopic(ev),
+            SpaceChild(ev) => Event::SpaceChild(ev),
+            SpaceParent(ev) => Event::SpaceParent(ev),
+            PolicyRuleRoom(ev) => Event::PolicyRuleRoom(ev),
+            PolicyRuleServer(ev) => Event::PolicyRuleServer(ev),
+            PolicyRuleUser(ev) => Event::PolicyRuleUser(ev),
             Sticker(ev) => Event::Sticker(ev),
             CustomRoom(ev) => Event::CustomRoom(ev),
             CustomState(ev) => Event::CustomState(ev),
@@ -650,6 +742,11 @@ impl From<StateEvent> for RoomEvent {
             RoomThirdPartyInvite(ev) => RoomEvent::RoomThirdPartyInvite(ev),
             RoomTombstone(ev) => RoomEvent::RoomTombstone(ev),
             RoomTopic(ev) => RoomEvent::RoomTopic(ev),
+            SpaceChild(ev) => RoomEvent::SpaceChild(ev),
+            SpaceParent(ev) => RoomEvent::SpaceParent(ev),
+            PolicyRuleRoom(ev) => RoomEvent::PolicyRuleRoom(ev),
+            PolicyRuleServer(ev) => RoomEvent::PolicyRuleServer(ev),
+            PolicyRuleUser(ev) => RoomEvent::PolicyRuleUser(ev),
             CustomState(ev) => RoomEvent::CustomState(ev),
         }
     }
@@ -1,9 +1,13 @@
 //! Enums for heterogeneous collections of events, exclusive to event types that implement "at
 //! most" the trait of the same name.
 
-use serde::{de::Error as _, Deserialize, Deserializer};
-use serde_json::Value;
+use serde::{
+    de::{Error as _, IgnoredAny},
+    Deserialize, Deserializer,
+};
+use serde_json::{value::RawValue, Value};
 
+#[allow(unused_imports)]
 pub use super::all::StateEvent;
 use crate::{
     call::{
@@ -135,6 +139,57 @@ pub enum RoomEvent {
     CustomRoom(CustomRoomEvent),
 }
 
+/// A room event that is not a state event, or a state event that is left unparsed.
+#[derive(Clone, Debug)]
+#[allow(clippy::large_enum_variant)]
+pub enum MessageLikeEvent {
+    /// m.call.answer
+    CallAnswer(AnswerEvent),
+
+    /// m.call.candidates
+    CallCandidates(CandidatesEvent),
+
+    /// m.call.hangup
+    CallHangup(HangupEvent),
+
+    /// m.call.invite
+    CallInvite(InviteEvent),
+
+    /// m.room.encrypted
+    RoomEncrypted(EncryptedEvent),
+
+    /// m.room.message
+    RoomMessage(MessageEvent),
+
+    /// m.room.message.feedback
+    RoomMessageFeedback(FeedbackEvent),
+
+    /// m.room.redaction
+    RoomRedaction(RedactionEvent),
+
+    /// m.sticker
+    Sticker(StickerEvent),
+
+    /// Any room event that is not part of the specification.
+    CustomRoom(CustomRoomEvent),
+
+    /// Any state event, left unparsed.
+    State(Box<RawValue>),
+}
+
+/// An ephemeral event, as found in the `ephemeral` section of a joined room in a sync response.
+#[derive(Clone, Debug)]
+pub enum EphemeralEvent {
+    /// m.receipt
+    Receipt(ReceiptEvent),
+
+    /// m.typing
+    Typing(TypingEvent),
+
+    /// Any basic event that is not part of the specification.
+    Custom(CustomEvent),
+}
+
 impl<'de> Deserialize<'de> for Event {
     fn deserialize<D>(deserializer: D) -> Result<Self, D::Error>
     where
@@ -189,6 +244,11 @@ impl<'de> Deserialize<'de> for Event {
             | RoomThirdPartyInvite
             | RoomTombstone
             | RoomTopic
+            | SpaceChild
+            | SpaceParent
+            | PolicyRuleRoom
+            | PolicyRuleServer
+            | PolicyRuleUser
             | RoomRedaction
             | Sticker => Err(D::Error::custom("invalid event type")),
             __Nonexhaustive => {
@@ -252,6 +312,11 @@ impl<'de> Deserialize<'de> for RoomEvent {
             | RoomThirdPartyInvite
             | RoomTombstone
             | RoomTopic
+            | SpaceChild
+            | SpaceParent
+            | PolicyRuleRoom
+            | PolicyRuleServer
+            | PolicyRuleUser
             | Tag
             | Typing => Err(D::Error::custom("invalid event type")),
             __Nonexhaustive => {
@@ -260,3 +325,65 @@ impl<'de> Deserialize<'de> for RoomEvent {
         }
     }
 }
+
+impl<'de> Deserialize<'de> for MessageLikeEvent {
+    fn deserialize<D>(deserializer: D) -> Result<Self, D::Error>
+    where
+        D: Deserializer<'de>,
+    {
+        use crate::util::try_variant_from_value as from_value;
+        use EventType::*;
+
+        /// The fields needed to tell state events apart, without parsing the rest of the event.
+        #[derive(Deserialize)]
+        struct Envelope {
+            #[serde(rename = "type")]
+            event_type: EventType,
+            state_key: Option<IgnoredAny>,
+        }
+
+        let json = Box::<RawValue>::deserialize(deserializer)?;
+        let envelope: Envelope = serde_json::from_str(json.get()).map_err(D::Error::custom)?;
+
+        if envelope.event_type.is_state_event() || envelope.state_key.is_some() {
+            return Ok(MessageLikeEvent::State(json));
+        }
+
+        let value: Value = serde_json::from_str(json.get()).map_err(D::Error::custom)?;
+        let event_type = envelope.event_type;
+
+        match event_type {
+            CallAnswer => from_value(value, MessageLikeEvent::CallAnswer),
+            CallCandidates => from_value(value, MessageLikeEvent::CallCandidates),
+            CallHangup => from_value(value, MessageLikeEvent::CallHangup),
+            CallInvite => from_value(value, MessageLikeEvent::CallInvite),
+            RoomEncrypted => from_value(value, MessageLikeEvent::RoomEncrypted),
+            RoomMessage => from_value(value, MessageLikeEvent::RoomMessage),
+            RoomMessageFeedback => from_value(value, MessageLikeEvent::RoomMessageFeedback),
+            RoomRedaction => from_value(value, MessageLikeEvent::RoomRedaction),
+            Sticker => from_value(value, MessageLikeEvent::Sticker),
+            Custom(_event_type_name) => from_value(value, MessageLikeEvent::CustomRoom),
+            _ => Err(D::Error::custom("invalid event type")),
+        }
+    }
+}
+
+impl<'de> Deserialize<'de> for EphemeralEvent {
+    fn deserialize<D>(deserializer: D) -> Result<Self, D::Error>
+    where
+        D: Deserializer<'de>,
+    {
+        use crate::util::try_variant_from_value as from_value;
+        use EventType::*;
+
+        let value = Value::deserialize(deserializer)?;
+        let event_type = get_field(&value, "type")?;
+
+        match event_type {
+            Receipt => from_value(value, EphemeralEvent::Receipt),
+            Typing => from_value(value, EphemeralEvent::Typing),
+            Custom(_event_type_name) => from_value(value, EphemeralEvent::Custom),
+            _ => Err(D::Error::custom("invalid event type")),
+        }
+    }
+}
@@ -0,0 +1,155 @@
+//! Calculation of the name of a room to display, from a collection of its state events.
+
+use std::convert::TryFrom;
+
+use ruma_identifiers::{RoomAliasId, UserId};
+
+use super::all::StateEvent;
+use crate::room::member::MembershipState;
+
+/// What to display as the name of a room.
+#[derive(Clone, Debug, PartialEq)]
+pub enum RoomDisplayName {
+    /// The name set in the room's *m.room.name* event.
+    Name(String),
+
+    /// The alias set in the room's *m.room.canonical_alias* event.
+    Alias(RoomAliasId),
+
+    /// The room has neither a name nor a canonical alias, so it should be named after these
+    /// members, sorted by user ID.
+    Heroes(Vec<UserId>),
+}
+
+/// Determines the name to display for a room, following the order of the spec: the room name,
+/// then the canonical alias, then the room's members.
+///
+/// `state` should contain the current state of the room. Events with a non-empty state key are
+/// ignored for the name and canonical alias.
+pub fn room_display_name(state: &[StateEvent]) -> RoomDisplayName {
+    let name = state.iter().find_map(|event| match event {
+        StateEvent::RoomName(event) if event.state_key.is_empty() => {
+            event.content.name().filter(|name| !name.is_empty())
+        }
+        _ => None,
+    });
+
+    if let Some(name) = name {
+        return RoomDisplayName::Name(name.to_string());
+    }
+
+    let alias = state.iter().find_map(|event| match event {
+        StateEvent::RoomCanonicalAlias(event) if event.state_key.is_empty() => {
+            event.content.alias.as_ref()
+        }
+        _ => None,
+    });
+
+    if let Some(alias) = alias {
+        return RoomDisplayName::Alias(alias.clone());
+    }
+
+    // TODO: Exclude the user the name is calculated for, limit the heroes to five and turn them
+    // into a name like "Alice, Bob and 3 others" using their display names.
+    let mut heroes: Vec<UserId> = state
+        .iter()
+        .filter_map(|event| match event {
+            StateEvent::RoomMember(event)
+                if event.content.membership == MembershipState::Join
+                    || event.content.membership == MembershipState::Invite =>
+            {
+                UserId::try_from(event.state_key.as_str()).ok()
+            }
+            _ => None,
+        })
+        .collect();
+
+    heroes.sort_by_key(|user_id| user_id.to_string());
+
+    RoomDisplayName::Heroes(heroes)
+}
+
+#[cfg(test)]
+mod tests {
+    use std::convert::TryFrom;
+
+    use ruma_identifiers::{RoomAliasId, UserId};
+    use serde_json::{from_value as from_json_value, json, Value};
+
+    use super::{room_display_name, RoomDisplayName};
+    use crate::{collections::all::StateEvent, EventResult};
+
+    fn state_event(event_type: &str, state_key: &str, content: Value) -> StateEvent {
+        from_json_value::<EventResult<StateEvent>>(json!({
+            "content": content,
+            "event_id": "$h29iv0s8:example.com",
+            "origin_server_ts": 1,
+            "sender": "@carl:example.com",
+            "state_key": state_key,
+            "type": event_type
+        }))
+        .unwrap()
+        .into_result()
+        .unwrap()
+    }
+
+    fn member(user_id: &str, membership: &str) -> StateEvent {
+        state_event(
+            "m.room.member",
+            user_id,
+            json!({ "membership": membership }),
+        )
+    }
+
+    fn canonical_alias() -> StateEvent {
+        state_event(
+            "m.room.canonical_alias",
+            "",
+            json!({ "alias": "#ruma:example.com" }),
+        )
+    }
+
+    #[test]
+    fn named_room() {
+        let state = vec![
+            canonical_alias(),
+            state_event("m.room.name", "", json!({ "name": "Ruma room" })),
+            member("@carl:example.com", "join"),
+        ];
+
+        assert_eq!(
+            room_display_name(&state),
+            RoomDisplayName::Name("Ruma room".to_string())
+        );
+    }
+
+    #[test]
+    fn alias_only_room() {
+        let state = vec![
+            state_event("m.room.name", "", json!({ "name": "" })),
+            canonical_alias(),
+        ];
+
+        assert_eq!(
+            room_display_name(&state),
+            RoomDisplayName::Alias(RoomAliasId::try_from("#ruma:example.com").unwrap())
+        );
+    }
+
+    #[test]
+    fn bare_room() {
+        let state = vec![
+            member("@carl:example.com", "join"),
+            member("@alice:example.com", "invite"),
+            member("@bob:example.com", "leave"),
+        ];
+
+        assert_eq!(
+            room_display_name(&state),
+            RoomDisplayName::Heroes(vec![
+                UserId::try_from("@alice:example.com").unwrap(),
+                UserId::try_from("@carl:example.com").unwrap(),
+            ])
+        );
+    }
+}
@@ -0,0 +1,292 @@
+//! Deserialization of events with configurable resource limits.
+
+use std::{
+    collections::HashSet,
+    fmt::{Formatter, Result as FmtResult},
+};
+
+use serde::{
+    de::{DeserializeSeed, Error as _, IgnoredAny, MapAccess, Visitor},
+    Deserializer,
+};
+use serde_json::value::RawValue;
+
+use crate::{EventResult, TryFromRaw};
+
+/// A builder for deserializing events from JSON with additional safeguards.
+///
+/// Deserializing an `EventResult<T>` directly reads the entire event into memory, no matter how
+/// large it is. Servers and bridges ingesting untrusted traffic can use this type to reject events
+/// whose `content`, `prev_content` or `unsigned` exceeds a size limit before it is parsed:
+///
+/// ```
+/// # use ruma_events::{room::topic::TopicEvent, EventDeserializer};
+/// let deserializer = EventDeserializer::new().max_content_bytes(65_536);
+/// let json = r#"{
+///     "content": { "topic": "Testing room" },
+///     "event_id": "$h29iv0s8:example.com",
+///     "origin_server_ts": 1,
+///     "sender": "@carl:example.com",
+///     "state_key": "",
+///     "type": "m.room.topic"
+/// }"#;
+///
+/// let event = deserializer.deserialize_str::<TopicEvent>(json).unwrap();
+/// assert!(event.into_result().is_ok());
+/// ```
+#[derive(Clone, Copy, Debug, Default, PartialEq)]
+pub struct EventDeserializer {
+    max_content_bytes: Option<usize>,
+}
+
+impl EventDeserializer {
+    /// Creates a new `EventDeserializer` without any limits.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Rejects events whose `content` is larger than `limit` bytes of JSON.
+    ///
+    /// The same limit applies to `prev_content` and `unsigned`, which can contain a whole other
+    /// event in `redacted_because`. With a limit, input that is not a JSON object or that has
+    /// duplicate keys is rejected as well.
+    pub fn max_content_bytes(self, limit: usize) -> Self {
+        Self {
+            max_content_bytes: Some(limit),
+        }
+    }
+
+    /// Deserializes an event from a string of JSON.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the input is not valid JSON or if one of the configured limits is
+    /// exceeded. Events that are valid JSON but not a valid `T` are reported through
+    /// `EventResult::Err`, as usual.
+    pub fn deserialize_str<T: TryFromRaw>(
+        &self,
+        json: &str,
+    ) -> Result<EventResult<T>, serde_json::Error> {
+        match self.max_content_bytes {
+            Some(limit) => {
+                let mut deserializer = serde_json::Deserializer::from_str(json);
+                let checked = LimitedEvent { limit }.deserialize(&mut deserializer)?;
+                deserializer.end()?;
+                checked.map_err(|(field, size)| too_large(field, size, limit))?;
+
+                serde_json::from_str(json)
+            }
+            None => serde_json::from_str(json),
+        }
+    }
+
+    /// Deserializes an event from a slice of bytes containing JSON.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the input is not valid JSON or if one of the configured limits is
+    /// exceeded. Events that are valid JSON but not a valid `T` are reported through
+    /// `EventResult::Err`, as usual.
+    pub fn deserialize_slice<T: TryFromRaw>(
+        &self,
+        json: &[u8],
+    ) -> Result<EventResult<T>, serde_json::Error> {
+        match self.max_content_bytes {
+            Some(limit) => {
+                let mut deserializer = serde_json::Deserializer::from_slice(json);
+                let checked = LimitedEvent { limit }.deserialize(&mut deserializer)?;
+                deserializer.end()?;
+                checked.map_err(|(field, size)| too_large(field, size, limit))?;
+
+                serde_json::from_slice(json)
+            }
+            None => serde_json::from_slice(json),
+        }
+    }
+}
+
+/// The fields of an event that are checked against the size limit.
+const LIMITED_FIELDS: &[&str] = &["content", "prev_content", "unsigned"];
+
+fn too_large(field: &str, size: usize, limit: usize) -> serde_json::Error {
+    serde_json::Error::custom(format!(
+        "event {} is {} bytes, exceeding the limit of {} bytes",
+        field, size, limit
+    ))
+}
+
+/// Checks the size of every `content`, `prev_content` and `unsigned` field of an event object,
+/// without parsing the values.
+///
+/// Each occurrence of these keys is checked, so that a small value can't be used to smuggle a
+/// duplicate, larger one past the limit. The field and size of the first value over the limit are
+/// returned as the error, the rest of the event is skipped. Duplicate keys are rejected like for
+/// derived `Deserialize` implementations.
+struct LimitedEvent {
+    limit: usize,
+}
+
+impl<'de> DeserializeSeed<'de> for LimitedEvent {
+    type Value = Result<(), (&'static str, usize)>;
+
+    fn deserialize<D>(self, deserializer: D) -> Result<Self::Value, D::Error>
+    where
+        D: Deserializer<'de>,
+    {
+        deserializer.deserialize_map(self)
+    }
+}
+
+impl<'de> Visitor<'de> for LimitedEvent {
+    type Value = Result<(), (&'static str, usize)>;
+
+    fn expecting(&self, formatter: &mut Formatter<'_>) -> FmtResult {
+        formatter.write_str("an event object")
+    }
+
+    fn visit_map<A>(self, mut map: A) -> Result<Self::Value, A::Error>
+    where
+        A: MapAccess<'de>,
+    {
+        let mut keys = HashSet::new();
+
+        while let Some(key) = map.next_key::<String>()? {
+            let value: &RawValue = map.next_value()?;
+
+            if let Some(&field) = LIMITED_FIELDS.iter().find(|&&field| field == key) {
+                let size = value.get().len();
+                if size > self.limit {
+                    while map.next_entry::<IgnoredAny, IgnoredAny>()?.is_some() {}
+
+                    return Ok(Err((field, size)));
+                }
+            }
+
+            if keys.contains(&key) {
+                return Err(A::Error::custom(format!("duplicate field `{}`", key)));
+            }
+            keys.insert(key);
+        }
+
+        Ok(Ok(()))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use serde_json::{json, Value};
+
+    use super::EventDeserializer;
+    use crate::room::topic::TopicEvent;
+
+    fn topic_event(topic: &str) -> String {
+        topic_event_json(topic).to_string()
+    }
+
+    fn topic_event_json(topic: &str) -> Value {
+        json!({
+            "content": {
+                "topic": topic
+            },
+            "event_id": "$h29iv0s8:example.com",
+            "origin_server_ts": 1,
+            "room_id": "!n8f893n9:example.com",
+            "sender": "@carl:example.com",
+            "state_key": "",
+            "type": "m.room.topic"
+        })
+    }
+
+    #[test]
+    fn content_under_limit() {
+        let deserializer = EventDeserializer::new().max_content_bytes(64);
+
+        let event = deserializer
+            .deserialize_str::<TopicEvent>(&topic_event("Testing room"))
+            .unwrap()
+            .into_result()
+            .unwrap();
+
+        assert_eq!(event.content.topic, "Testing room");
+    }
+
+    #[test]
+    fn content_over_limit() {
+        let deserializer = EventDeserializer::new().max_content_bytes(64);
+        let json = topic_event(&"a".repeat(64));
+
+        assert_eq!(
+            deserializer
+                .deserialize_slice::<TopicEvent>(json.as_bytes())
+                .unwrap_err()
+                .to_string(),
+            "event content is 76 bytes, exceeding the limit of 64 bytes"
+        );
+        assert!(EventDeserializer::new()
+            .deserialize_slice::<TopicEvent>(json.as_bytes())
+            .unwrap()
+            .into_result()
+            .is_ok());
+    }
+
+    #[test]
+    fn duplicate_content_over_limit() {
+        let deserializer = EventDeserializer::new().max_content_bytes(64);
+        let json = format!(
+            r#"{{"content":{{}},"content":{{"topic":"{}"}},"event_id":"$h29iv0s8:example.com","origin_server_ts":1,"sender":"@carl:example.com","state_key":"","type":"m.room.topic"}}"#,
+            "a".repeat(64)
+        );
+
+        assert_eq!(
+            deserializer
+                .deserialize_str::<TopicEvent>(&json)
+                .unwrap_err()
+                .to_string(),
+            "event content is 76 bytes, exceeding the limit of 64 bytes"
+        );
+    }
+
+    #[test]
+    fn prev_content_over_limit() {
+        let deserializer = EventDeserializer::new().max_content_bytes(64);
+        let mut json = topic_event_json("Testing room");
+        json["prev_content"] = json!({ "topic": "a".repeat(64) });
+
+        assert_eq!(
+            deserializer
+                .deserialize_str::<TopicEvent>(&json.to_string())
+                .unwrap_err()
+                .to_string(),
+            "event prev_content is 76 bytes, exceeding the limit of 64 bytes"
+        );
+    }
+
+    #[test]
+    fn unsigned_over_limit() {
+        let deserializer = EventDeserializer::new().max_content_bytes(64);
+        let mut json = topic_event_json("Testing room");
+        json["unsigned"] = json!({
+            "redacted_because": { "content": { "reason": "a".repeat(64) } }
+        });
+
+        assert_eq!(
+            deserializer
+                .deserialize_str::<TopicEvent>(&json.to_string())
+                .unwrap_err()
+                .to_string(),
+            "event unsigned is 110 bytes, exceeding the limit of 64 bytes"
+        );
+    }
+
+    #[test]
+    fn duplicate_key() {
+        let deserializer = EventDeserializer::new().max_content_bytes(64);
+        let json = r#"{"content":{"topic":"Testing room"},"event_id":"$h29iv0s8:example.com","origin_server_ts":1,"sender":"@carl:example.com","sender":"@dan:example.com","state_key":"","type":"m.room.topic"}"#;
+
+        assert!(deserializer
+            .deserialize_str::<TopicEvent>(json)
+            .unwrap_err()
+            .to_string()
+            .starts_with("duplicate field `sender`"));
+    }
+}
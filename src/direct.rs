@@ -15,6 +15,7 @@ ruma_event! {
             ///
             /// A mapping of `UserId`s to a list of `RoomId`s which are considered *direct* for that
             /// particular user.
+            #[serde(serialize_with = "crate::util::sorted_map")]
             HashMap<UserId, Vec<RoomId>>
         },
     }
@@ -28,7 +29,7 @@ mod tests {
     use serde_json::{from_value as from_json_value, json, to_value as to_json_value};
 
     use super::{DirectEvent, DirectEventContent};
-    use crate::EventResult;
+    use crate::{util::assert_deterministic_serialization, EventResult};
 
     #[test]
     fn serialization() {
@@ -73,4 +74,25 @@ mod tests {
         assert!(direct_rooms.contains(&rooms[0]));
         assert!(direct_rooms.contains(&rooms[1]));
     }
+
+    #[test]
+    fn default_is_empty() {
+        assert_eq!(
+            to_json_value(DirectEventContent::default()).unwrap(),
+            json!({})
+        );
+    }
+
+    #[test]
+    fn deterministic_serialization() {
+        assert_deterministic_serialization::<DirectEvent>(json!({
+            "content": {
+                "@alice:example.com": ["!a:example.com"],
+                "@bob:example.com": ["!b:example.com", "!c:example.com"],
+                "@carl:example.com": ["!d:example.com"],
+                "@dana:example.com": []
+            },
+            "type": "m.direct"
+        }));
+    }
 }
@@ -9,7 +9,7 @@ use serde::{Deserialize, Serialize};
 #[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
 // Cow<str> because deserialization sometimes needs to copy to unescape things
 #[serde(from = "Cow<'_, str>", into = "String")]
-pub enum Algorithm {
+pub enum EventEncryptionAlgorithm {
     /// Olm version 1 using Curve25519, AES-256, and SHA-256.
     OlmV1Curve25519AesSha2,
 
@@ -25,13 +25,13 @@ pub enum Algorithm {
     __Nonexhaustive,
 }
 
-impl Display for Algorithm {
+impl Display for EventEncryptionAlgorithm {
     fn fmt(&self, f: &mut Formatter<'_>) -> FmtResult {
         let algorithm_str = match *self {
-            Algorithm::OlmV1Curve25519AesSha2 => "m.olm.v1.curve25519-aes-sha2",
-            Algorithm::MegolmV1AesSha2 => "m.megolm.v1.aes-sha2",
-            Algorithm::Custom(ref algorithm) => algorithm,
-            Algorithm::__Nonexhaustive => {
+            EventEncryptionAlgorithm::OlmV1Curve25519AesSha2 => "m.olm.v1.curve25519-aes-sha2",
+            EventEncryptionAlgorithm::MegolmV1AesSha2 => "m.megolm.v1.aes-sha2",
+            EventEncryptionAlgorithm::Custom(ref algorithm) => algorithm,
+            EventEncryptionAlgorithm::__Nonexhaustive => {
                 panic!("__Nonexhaustive enum variant is not intended for use.")
             }
         };
@@ -40,24 +40,24 @@ impl Display for Algorithm {
     }
 }
 
-impl From<Cow<'_, str>> for Algorithm {
-    fn from(s: Cow<'_, str>) -> Algorithm {
+impl From<Cow<'_, str>> for EventEncryptionAlgorithm {
+    fn from(s: Cow<'_, str>) -> EventEncryptionAlgorithm {
         match &s as &str {
-            "m.olm.v1.curve25519-aes-sha2" => Algorithm::OlmV1Curve25519AesSha2,
-            "m.megolm.v1.aes-sha2" => Algorithm::MegolmV1AesSha2,
-            _ => Algorithm::Custom(s.into_owned()),
+            "m.olm.v1.curve25519-aes-sha2" => EventEncryptionAlgorithm::OlmV1Curve25519AesSha2,
+            "m.megolm.v1.aes-sha2" => EventEncryptionAlgorithm::MegolmV1AesSha2,
+            _ => EventEncryptionAlgorithm::Custom(s.into_owned()),
         }
     }
 }
 
-impl From<&str> for Algorithm {
-    fn from(s: &str) -> Algorithm {
-        Algorithm::from(Cow::Borrowed(s))
+impl From<&str> for EventEncryptionAlgorithm {
+    fn from(s: &str) -> EventEncryptionAlgorithm {
+        EventEncryptionAlgorithm::from(Cow::Borrowed(s))
     }
 }
 
-impl From<Algorithm> for String {
-    fn from(algorithm: Algorithm) -> String {
+impl From<EventEncryptionAlgorithm> for String {
+    fn from(algorithm: EventEncryptionAlgorithm) -> String {
         algorithm.to_string()
     }
 }
@@ -71,13 +71,16 @@ mod tests {
 
     #[test]
     fn serialize_and_deserialize_from_display_form() {
-        serde_json_eq(Algorithm::MegolmV1AesSha2, json!("m.megolm.v1.aes-sha2"));
         serde_json_eq(
-            Algorithm::OlmV1Curve25519AesSha2,
+            EventEncryptionAlgorithm::MegolmV1AesSha2,
+            json!("m.megolm.v1.aes-sha2"),
+        );
+        serde_json_eq(
+            EventEncryptionAlgorithm::OlmV1Curve25519AesSha2,
             json!("m.olm.v1.curve25519-aes-sha2"),
         );
         serde_json_eq(
-            Algorithm::Custom("io.ruma.test".to_string()),
+            EventEncryptionAlgorithm::Custom("io.ruma.test".to_string()),
             json!("io.ruma.test"),
         );
     }
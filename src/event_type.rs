@@ -1,5 +1,6 @@
 use std::{
     borrow::Cow,
+    cmp::Ordering,
     fmt::{Display, Formatter, Result as FmtResult},
 };
 
@@ -47,6 +48,9 @@ pub enum EventType {
     KeyVerificationMac,
 
     /// m.key.verification.request
+    ///
+    /// In-room verification requests are *m.room.message* events with this msgtype instead, see
+    /// `room::message::MessageType::KeyVerificationRequest`.
     KeyVerificationRequest,
 
     /// m.key.verification.start
@@ -55,6 +59,15 @@ pub enum EventType {
     /// m.ignored_user_list
     IgnoredUserList,
 
+    /// m.policy.rule.room
+    PolicyRuleRoom,
+
+    /// m.policy.rule.server
+    PolicyRuleServer,
+
+    /// m.policy.rule.user
+    PolicyRuleUser,
+
     /// m.presence
     Presence,
 
@@ -130,6 +143,12 @@ pub enum EventType {
     /// m.room_key_request
     RoomKeyRequest,
 
+    /// m.space.child
+    SpaceChild,
+
+    /// m.space.parent
+    SpaceParent,
+
     /// m.sticker
     Sticker,
 
@@ -148,9 +167,100 @@ pub enum EventType {
     __Nonexhaustive,
 }
 
-impl Display for EventType {
-    fn fmt(&self, f: &mut Formatter<'_>) -> FmtResult {
-        let event_type_str = match *self {
+impl EventType {
+    /// Whether this is the type of a state event, i.e. an event that is part of a room's state and
+    /// has a `state_key`.
+    pub fn is_state_event(&self) -> bool {
+        match *self {
+            EventType::PolicyRuleRoom
+            | EventType::PolicyRuleServer
+            | EventType::PolicyRuleUser
+            | EventType::RoomAliases
+            | EventType::RoomAvatar
+            | EventType::RoomCanonicalAlias
+            | EventType::RoomCreate
+            | EventType::RoomEncryption
+            | EventType::RoomGuestAccess
+            | EventType::RoomHistoryVisibility
+            | EventType::RoomJoinRules
+            | EventType::RoomMember
+            | EventType::RoomName
+            | EventType::RoomPinnedEvents
+            | EventType::RoomPowerLevels
+            | EventType::RoomServerAcl
+            | EventType::RoomThirdPartyInvite
+            | EventType::RoomTombstone
+            | EventType::RoomTopic
+            | EventType::SpaceChild
+            | EventType::SpaceParent => true,
+            _ => false,
+        }
+    }
+
+    /// Whether this is the type of a message event, i.e. a room event that is part of a room's
+    /// timeline but not of its state.
+    pub fn is_message_event(&self) -> bool {
+        match *self {
+            EventType::CallAnswer
+            | EventType::CallCandidates
+            | EventType::CallHangup
+            | EventType::CallInvite
+            | EventType::RoomEncrypted
+            | EventType::RoomMessage
+            | EventType::RoomMessageFeedback
+            | EventType::RoomRedaction
+            | EventType::Sticker => true,
+            _ => false,
+        }
+    }
+
+    /// Whether this is the type of an ephemeral room event, i.e. an event that is associated with
+    /// a room but not persisted in its timeline.
+    pub fn is_ephemeral(&self) -> bool {
+        match *self {
+            EventType::Receipt | EventType::Typing => true,
+            _ => false,
+        }
+    }
+
+    /// Whether this is the type of an event that can be sent directly to a device.
+    ///
+    /// Note that *m.room.encrypted* events can be sent both in rooms and to devices, so this
+    /// returns `true` for `EventType::RoomEncrypted` as well as `is_message_event`.
+    pub fn is_to_device(&self) -> bool {
+        match *self {
+            EventType::Dummy
+            | EventType::ForwardedRoomKey
+            | EventType::KeyVerificationAccept
+            | EventType::KeyVerificationCancel
+            | EventType::KeyVerificationKey
+            | EventType::KeyVerificationMac
+            | EventType::KeyVerificationRequest
+            | EventType::KeyVerificationStart
+            | EventType::RoomEncrypted
+            | EventType::RoomKey
+            | EventType::RoomKeyRequest => true,
+            _ => false,
+        }
+    }
+
+    /// Whether this is the type of an account data event, either global or per-room.
+    pub fn is_account_data(&self) -> bool {
+        match *self {
+            EventType::Direct
+            | EventType::FullyRead
+            | EventType::IgnoredUserList
+            | EventType::PushRules
+            | EventType::Tag => true,
+            _ => false,
+        }
+    }
+}
+
+impl EventType {
+    /// The string form of this event type, e.g. *m.room.message*.
+    fn as_str(&self) -> &str {
+        match *self {
             EventType::CallAnswer => "m.call.answer",
             EventType::CallCandidates => "m.call.candidates",
             EventType::CallHangup => "m.call.hangup",
@@ -166,6 +276,9 @@ impl Display for EventType {
             EventType::KeyVerificationRequest => "m.key.verification.request",
             EventType::KeyVerificationStart => "m.key.verification.start",
             EventType::IgnoredUserList => "m.ignored_user_list",
+            EventType::PolicyRuleRoom => "m.policy.rule.room",
+            EventType::PolicyRuleServer => "m.policy.rule.server",
+            EventType::PolicyRuleUser => "m.policy.rule.user",
             EventType::Presence => "m.presence",
             EventType::PushRules => "m.push_rules",
             EventType::Receipt => "m.receipt",
@@ -191,6 +304,8 @@ impl Display for EventType {
             EventType::RoomTopic => "m.room.topic",
             EventType::RoomKey => "m.room_key",
             EventType::RoomKeyRequest => "m.room_key_request",
+            EventType::SpaceChild => "m.space.child",
+            EventType::SpaceParent => "m.space.parent",
             EventType::Sticker => "m.sticker",
             EventType::Tag => "m.tag",
             EventType::Typing => "m.typing",
@@ -198,9 +313,36 @@ impl Display for EventType {
             EventType::__Nonexhaustive => {
                 panic!("__Nonexhaustive enum variant is not intended for use.")
             }
+        }
+    }
+}
+
+impl Display for EventType {
+    fn fmt(&self, f: &mut Formatter<'_>) -> FmtResult {
+        write!(f, "{}", self.as_str())
+    }
+}
+
+/// Event types are ordered by their string form, so that custom types sort among the known ones.
+///
+/// A `Custom` type with the same string as a known type (which `From<&str>` never produces) sorts
+/// right after it.
+impl Ord for EventType {
+    fn cmp(&self, other: &Self) -> Ordering {
+        let is_custom = |event_type: &EventType| match *event_type {
+            EventType::Custom(_) => true,
+            _ => false,
         };
 
-        write!(f, "{}", event_type_str)
+        self.as_str()
+            .cmp(other.as_str())
+            .then_with(|| is_custom(self).cmp(&is_custom(other)))
+    }
+}
+
+impl PartialOrd for EventType {
+    fn partial_cmp(&self, other: &Self) -> Option<Ordering> {
+        Some(self.cmp(other))
     }
 }
 
@@ -222,6 +364,9 @@ impl From<Cow<'_, str>> for EventType {
             "m.key.verification.request" => EventType::KeyVerificationRequest,
             "m.key.verification.start" => EventType::KeyVerificationStart,
             "m.ignored_user_list" => EventType::IgnoredUserList,
+            "m.policy.rule.room" => EventType::PolicyRuleRoom,
+            "m.policy.rule.server" => EventType::PolicyRuleServer,
+            "m.policy.rule.user" => EventType::PolicyRuleUser,
             "m.presence" => EventType::Presence,
             "m.push_rules" => EventType::PushRules,
             "m.receipt" => EventType::Receipt,
@@ -247,6 +392,8 @@ impl From<Cow<'_, str>> for EventType {
             "m.room.topic" => EventType::RoomTopic,
             "m.room_key" => EventType::RoomKey,
             "m.room_key_request" => EventType::RoomKeyRequest,
+            "m.space.child" => EventType::SpaceChild,
+            "m.space.parent" => EventType::SpaceParent,
             "m.sticker" => EventType::Sticker,
             "m.tag" => EventType::Tag,
             "m.typing" => EventType::Typing,
@@ -255,7 +402,7 @@ impl From<Cow<'_, str>> for EventType {
     }
 }
 
-impl<'a> From<&str> for EventType {
+impl From<&str> for EventType {
     fn from(s: &str) -> EventType {
         EventType::from(Cow::Borrowed(s))
     }
@@ -269,6 +416,8 @@ impl From<EventType> for String {
 
 #[cfg(test)]
 mod tests {
+    use std::collections::{BTreeMap, HashMap};
+
     use serde_json::json;
 
     use super::*;
@@ -310,6 +459,9 @@ mod tests {
             json!("m.key.verification.start"),
         );
         serde_json_eq(EventType::IgnoredUserList, json!("m.ignored_user_list"));
+        serde_json_eq(EventType::PolicyRuleRoom, json!("m.policy.rule.room"));
+        serde_json_eq(EventType::PolicyRuleServer, json!("m.policy.rule.server"));
+        serde_json_eq(EventType::PolicyRuleUser, json!("m.policy.rule.user"));
         serde_json_eq(EventType::Presence, json!("m.presence"));
         serde_json_eq(EventType::PushRules, json!("m.push_rules"));
         serde_json_eq(EventType::Receipt, json!("m.receipt"));
@@ -347,6 +499,8 @@ mod tests {
         serde_json_eq(EventType::RoomTopic, json!("m.room.topic"));
         serde_json_eq(EventType::RoomKey, json!("m.room_key"));
         serde_json_eq(EventType::RoomKeyRequest, json!("m.room_key_request"));
+        serde_json_eq(EventType::SpaceChild, json!("m.space.child"));
+        serde_json_eq(EventType::SpaceParent, json!("m.space.parent"));
         serde_json_eq(EventType::Sticker, json!("m.sticker"));
         serde_json_eq(EventType::Tag, json!("m.tag"));
         serde_json_eq(EventType::Typing, json!("m.typing"));
@@ -355,4 +509,78 @@ mod tests {
             json!("io.ruma.test"),
         );
     }
+
+    #[test]
+    fn grouping_predicates() {
+        assert!(EventType::RoomMember.is_state_event());
+        assert!(!EventType::RoomMember.is_message_event());
+
+        assert!(EventType::RoomMessage.is_message_event());
+        assert!(!EventType::RoomMessage.is_state_event());
+
+        assert!(EventType::Typing.is_ephemeral());
+        assert!(!EventType::Typing.is_state_event());
+
+        assert!(EventType::RoomKey.is_to_device());
+        assert!(EventType::RoomEncrypted.is_to_device());
+        assert!(EventType::RoomEncrypted.is_message_event());
+
+        assert!(EventType::PushRules.is_account_data());
+
+        let custom = EventType::Custom("io.ruma.test".to_string());
+        assert!(!custom.is_state_event());
+        assert!(!custom.is_message_event());
+        assert!(!custom.is_ephemeral());
+        assert!(!custom.is_to_device());
+        assert!(!custom.is_account_data());
+    }
+
+    #[test]
+    fn usable_as_map_key() {
+        let mut handlers = HashMap::new();
+        handlers.insert(EventType::RoomMessage, "message");
+        handlers.insert(EventType::RoomMember, "member");
+        handlers.insert(EventType::Custom("io.ruma.a".to_string()), "a");
+        handlers.insert(EventType::Custom("io.ruma.b".to_string()), "b");
+
+        assert_eq!(handlers.len(), 4);
+        assert_eq!(handlers[&EventType::RoomMessage], "message");
+        assert_eq!(handlers[&EventType::from("m.room.member")], "member");
+        assert_eq!(handlers[&EventType::from("io.ruma.a")], "a");
+        assert_eq!(handlers[&EventType::Custom("io.ruma.b".to_string())], "b");
+        assert!(!handlers.contains_key(&EventType::Custom("io.ruma.c".to_string())));
+
+        let ordered: BTreeMap<_, _> = handlers.into_iter().collect();
+        assert_eq!(ordered.len(), 4);
+        assert_eq!(ordered[&EventType::from("io.ruma.b")], "b");
+    }
+
+    #[test]
+    fn ordered_by_string_form() {
+        let mut event_types = vec![
+            EventType::RoomMessage,
+            EventType::Custom("m.room.member".to_string()),
+            EventType::Custom("org.example.event".to_string()),
+            EventType::RoomMember,
+            EventType::Custom("com.example.event".to_string()),
+            EventType::CallAnswer,
+        ];
+        event_types.sort();
+
+        assert_eq!(
+            event_types,
+            vec![
+                EventType::Custom("com.example.event".to_string()),
+                EventType::CallAnswer,
+                EventType::RoomMember,
+                EventType::Custom("m.room.member".to_string()),
+                EventType::RoomMessage,
+                EventType::Custom("org.example.event".to_string()),
+            ]
+        );
+        assert_eq!(
+            EventType::RoomMember.cmp(&EventType::from("m.room.member")),
+            Ordering::Equal
+        );
+    }
 }
@@ -1,47 +1,170 @@
 //! Types for the *m.forwarded_room_key* event.
 
-use ruma_events_macros::ruma_event;
+use std::fmt::{Debug, Formatter, Result as FmtResult};
+
 use ruma_identifiers::RoomId;
+use serde::{Deserialize, Serialize};
 
-use super::Algorithm;
+use super::EventEncryptionAlgorithm;
+use crate::{util::Redacted, EventType, FromRaw};
 
-ruma_event! {
-    /// This event type is used to forward keys for end-to-end encryption.
+/// This event type is used to forward keys for end-to-end encryption.
+///
+/// Typically it is encrypted as an *m.room.encrypted* event, then sent as a to-device event.
+#[derive(Clone, Debug, PartialEq, Serialize)]
+#[serde(rename = "m.forwarded_room_key", tag = "type")]
+pub struct ForwardedRoomKeyEvent {
+    /// The event's content.
+    pub content: ForwardedRoomKeyEventContent,
+}
+
+/// The payload for `ForwardedRoomKeyEvent`.
+///
+/// The `Debug` output doesn't contain the session key.
+#[derive(Clone, PartialEq, Serialize)]
+pub struct ForwardedRoomKeyEventContent {
+    /// The encryption algorithm the key in this event is to be used with.
+    pub algorithm: EventEncryptionAlgorithm,
+
+    /// The room where the key is used.
+    pub room_id: RoomId,
+
+    /// The Curve25519 key of the device which initiated the session originally.
+    pub sender_key: String,
+
+    /// The ID of the session that the key is for.
+    pub session_id: String,
+
+    /// The key to be exchanged.
+    pub session_key: String,
+
+    /// The Ed25519 key of the device which initiated the session originally.
+    ///
+    /// It is "claimed" because the receiving device has no way to tell that the original
+    /// room_key actually came from a device which owns the private part of this key unless
+    /// they have done device verification.
+    pub sender_claimed_ed25519_key: String,
+
+    /// Chain of Curve25519 keys.
     ///
-    /// Typically it is encrypted as an *m.room.encrypted* event, then sent as a to-device event.
-    ForwardedRoomKeyEvent {
-        kind: Event,
-        event_type: "m.forwarded_room_key",
-        content: {
-            /// The encryption algorithm the key in this event is to be used with.
-            pub algorithm: Algorithm,
-
-            /// The room where the key is used.
-            pub room_id: RoomId,
-
-            /// The Curve25519 key of the device which initiated the session originally.
-            pub sender_key: String,
-
-            /// The ID of the session that the key is for.
-            pub session_id: String,
-
-            /// The key to be exchanged.
-            pub session_key: String,
-
-            /// The Ed25519 key of the device which initiated the session originally.
-            ///
-            /// It is "claimed" because the receiving device has no way to tell that the original
-            /// room_key actually came from a device which owns the private part of this key unless
-            /// they have done device verification.
-            pub sender_claimed_ed25519_key: String,
-
-            /// Chain of Curve25519 keys.
-            ///
-            /// It starts out empty, but each time the key is forwarded to another device, the
-            /// previous sender in the chain is added to the end of the list. For example, if the
-            /// key is forwarded from A to B to C, this field is empty between A and B, and contains
-            /// A's Curve25519 key between B and C.
-            pub forwarding_curve25519_key_chain: Vec<String>,
-        },
+    /// It starts out empty, but each time the key is forwarded to another device, the
+    /// previous sender in the chain is added to the end of the list. For example, if the
+    /// key is forwarded from A to B to C, this field is empty between A and B, and contains
+    /// A's Curve25519 key between B and C.
+    pub forwarding_curve25519_key_chain: Vec<String>,
+}
+
+impl Debug for ForwardedRoomKeyEventContent {
+    fn fmt(&self, f: &mut Formatter<'_>) -> FmtResult {
+        f.debug_struct("ForwardedRoomKeyEventContent")
+            .field("algorithm", &self.algorithm)
+            .field("room_id", &self.room_id)
+            .field("sender_key", &self.sender_key)
+            .field("session_id", &self.session_id)
+            .field("session_key", &Redacted)
+            .field(
+                "sender_claimed_ed25519_key",
+                &self.sender_claimed_ed25519_key,
+            )
+            .field(
+                "forwarding_curve25519_key_chain",
+                &self.forwarding_curve25519_key_chain,
+            )
+            .finish()
+    }
+}
+
+impl FromRaw for ForwardedRoomKeyEvent {
+    type Raw = raw::ForwardedRoomKeyEvent;
+
+    fn from_raw(raw: raw::ForwardedRoomKeyEvent) -> Self {
+        Self {
+            content: FromRaw::from_raw(raw.content),
+        }
+    }
+}
+
+impl FromRaw for ForwardedRoomKeyEventContent {
+    type Raw = raw::ForwardedRoomKeyEventContent;
+
+    fn from_raw(raw: raw::ForwardedRoomKeyEventContent) -> Self {
+        Self {
+            algorithm: raw.algorithm,
+            room_id: raw.room_id,
+            sender_key: raw.sender_key,
+            session_id: raw.session_id,
+            session_key: raw.session_key,
+            sender_claimed_ed25519_key: raw.sender_claimed_ed25519_key,
+            forwarding_curve25519_key_chain: raw.forwarding_curve25519_key_chain,
+        }
+    }
+}
+
+impl_event!(
+    ForwardedRoomKeyEvent,
+    ForwardedRoomKeyEventContent,
+    EventType::ForwardedRoomKey
+);
+
+pub(crate) mod raw {
+    use super::*;
+
+    /// This event type is used to forward keys for end-to-end encryption.
+    #[derive(Clone, Debug, Deserialize, PartialEq)]
+    pub struct ForwardedRoomKeyEvent {
+        /// The event's content.
+        pub content: ForwardedRoomKeyEventContent,
+    }
+
+    /// The payload for `ForwardedRoomKeyEvent`.
+    #[derive(Clone, Debug, Deserialize, PartialEq)]
+    pub struct ForwardedRoomKeyEventContent {
+        /// The encryption algorithm the key in this event is to be used with.
+        pub algorithm: EventEncryptionAlgorithm,
+
+        /// The room where the key is used.
+        pub room_id: RoomId,
+
+        /// The Curve25519 key of the device which initiated the session originally.
+        pub sender_key: String,
+
+        /// The ID of the session that the key is for.
+        pub session_id: String,
+
+        /// The key to be exchanged.
+        pub session_key: String,
+
+        /// The Ed25519 key of the device which initiated the session originally.
+        pub sender_claimed_ed25519_key: String,
+
+        /// Chain of Curve25519 keys.
+        pub forwarding_curve25519_key_chain: Vec<String>,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::convert::TryFrom;
+
+    use ruma_identifiers::RoomId;
+
+    use super::ForwardedRoomKeyEventContent;
+    use crate::EventEncryptionAlgorithm;
+
+    #[test]
+    fn debug_hides_session_key() {
+        let content = ForwardedRoomKeyEventContent {
+            algorithm: EventEncryptionAlgorithm::MegolmV1AesSha2,
+            room_id: RoomId::try_from("!test:localhost").unwrap(),
+            sender_key: "sender_key".to_string(),
+            session_id: "fake_id".to_string(),
+            session_key: "secret_session_key".to_string(),
+            sender_claimed_ed25519_key: "claimed_key".to_string(),
+            forwarding_curve25519_key_chain: Vec::new(),
+        };
+        let debug = format!("{:?}", content);
+
+        assert!(debug.contains("fake_id"));
+        assert!(!debug.contains("secret_session_key"));
     }
 }
@@ -16,6 +16,7 @@ ruma_event! {
             ///
             /// `None` if the room is known through other means (such as this even being part of an
             /// event list scoped to a room in a `/sync` response)
+            #[serde(skip_serializing_if = "Option::is_none")]
             pub room_id: Option<RoomId>,
         },
         content: {
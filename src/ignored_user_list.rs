@@ -24,7 +24,7 @@ impl FromRaw for IgnoredUserListEvent {
 }
 
 /// The payload for `IgnoredUserListEvent`.
-#[derive(Clone, Debug, PartialEq, Serialize)]
+#[derive(Clone, Debug, Default, PartialEq, Serialize)]
 pub struct IgnoredUserListEventContent {
     /// A list of users to ignore.
     #[serde(with = "vec_as_map_of_empty")]
@@ -74,7 +74,7 @@ mod tests {
     use serde_json::{from_value as from_json_value, json, to_value as to_json_value};
 
     use super::{IgnoredUserListEvent, IgnoredUserListEventContent};
-    use crate::EventResult;
+    use crate::{util::assert_deterministic_serialization, EventResult};
 
     #[test]
     fn serialization() {
@@ -120,4 +120,40 @@ mod tests {
 
         assert_eq!(actual, expected);
     }
+
+    #[test]
+    fn default_is_empty() {
+        assert_eq!(
+            to_json_value(IgnoredUserListEventContent::default()).unwrap(),
+            json!({ "ignored_users": {} })
+        );
+    }
+
+    #[test]
+    fn deterministic_serialization() {
+        assert_deterministic_serialization::<IgnoredUserListEventContent>(json!({
+            "ignored_users": {
+                "@alice:example.com": {},
+                "@bob:example.com": {},
+                "@carl:example.com": {},
+                "@dana:example.com": {}
+            }
+        }));
+    }
+
+    #[test]
+    fn serialization_is_sorted_and_deduplicated() {
+        let content = IgnoredUserListEventContent {
+            ignored_users: vec![
+                UserId::try_from("@bob:example.com").unwrap(),
+                UserId::try_from("@alice:example.com").unwrap(),
+                UserId::try_from("@bob:example.com").unwrap(),
+            ],
+        };
+
+        assert_eq!(
+            serde_json::to_string(&content).unwrap(),
+            r#"{"ignored_users":{"@alice:example.com":{},"@bob:example.com":{}}}"#
+        );
+    }
 }
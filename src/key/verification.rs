@@ -4,8 +4,11 @@
 
 use serde::{Deserialize, Serialize};
 
+use crate::room::message::KeyVerificationRequestEventContent;
+
 pub mod accept;
 pub mod cancel;
+pub mod in_room;
 pub mod key;
 pub mod mac;
 pub mod request;
@@ -103,6 +106,24 @@ pub enum VerificationMethod {
     #[serde(rename = "m.sas.v1")]
     MSasV1,
 
+    /// The *m.reciprocate.v1* verification method, used for QR code verification.
+    #[serde(rename = "m.reciprocate.v1")]
+    ReciprocateV1,
+
+    /// The *m.qr_code.show.v1* method, advertising that the device can show a QR code.
+    ///
+    /// This is only used in the `methods` of a request or ready event, never to start a
+    /// verification.
+    #[serde(rename = "m.qr_code.show.v1")]
+    QrCodeShowV1,
+
+    /// The *m.qr_code.scan.v1* method, advertising that the device can scan a QR code.
+    ///
+    /// This is only used in the `methods` of a request or ready event, never to start a
+    /// verification.
+    #[serde(rename = "m.qr_code.scan.v1")]
+    QrCodeScanV1,
+
     /// Additional variants may be added in the future and will not be considered breaking changes
     /// to ruma-events.
     #[doc(hidden)]
@@ -113,5 +134,161 @@ pub enum VerificationMethod {
 impl_enum! {
     VerificationMethod {
         MSasV1 => "m.sas.v1",
+        ReciprocateV1 => "m.reciprocate.v1",
+        QrCodeShowV1 => "m.qr_code.show.v1",
+        QrCodeScanV1 => "m.qr_code.scan.v1",
+    }
+}
+
+/// Picks the verification method to use out of the methods supported by both devices.
+///
+/// QR code verification (*m.reciprocate.v1*) is preferred over *m.sas.v1*, since it doesn't
+/// require comparing strings. It is only picked if both devices support it and one of them can
+/// show a QR code (*m.qr_code.show.v1*) that the other can scan (*m.qr_code.scan.v1*). The order
+/// of `mine` and `theirs` doesn't matter. Returns `None` if the devices have no usable method in
+/// common.
+pub fn negotiate_method(
+    mine: &[VerificationMethod],
+    theirs: &[VerificationMethod],
+) -> Option<VerificationMethod> {
+    const PREFERENCE: &[VerificationMethod] = &[
+        VerificationMethod::ReciprocateV1,
+        VerificationMethod::MSasV1,
+    ];
+
+    let can_show_to = |shower: &[VerificationMethod], scanner: &[VerificationMethod]| {
+        shower.contains(&VerificationMethod::QrCodeShowV1)
+            && scanner.contains(&VerificationMethod::QrCodeScanV1)
+    };
+    let qr_code_usable = can_show_to(mine, theirs) || can_show_to(theirs, mine);
+
+    PREFERENCE
+        .iter()
+        .filter(|&&method| method != VerificationMethod::ReciprocateV1 || qr_code_usable)
+        .find(|method| mine.contains(method) && theirs.contains(method))
+        .cloned()
+}
+
+/// The content of a key verification event, regardless of the form it was sent in.
+///
+/// This allows handling verifications with the same code whether they happen through to-device
+/// messages or in a room. For events sent in a room, the transaction ID is the ID of the
+/// *m.room.message* that requested the verification, see
+/// [`in_room::InRoomVerificationEvent`](in_room/struct.InRoomVerificationEvent.html).
+#[derive(Clone, Debug, PartialEq)]
+pub enum KeyVerificationContent {
+    /// *m.key.verification.accept*
+    Accept(accept::AcceptEventContent),
+
+    /// *m.key.verification.cancel*
+    Cancel(cancel::CancelEventContent),
+
+    /// *m.key.verification.key*
+    Key(key::KeyEventContent),
+
+    /// *m.key.verification.mac*
+    Mac(mac::MacEventContent),
+
+    /// *m.key.verification.request*, sent as a to-device event.
+    Request(request::RequestEventContent),
+
+    /// *m.key.verification.request*, sent as an *m.room.message* in a room.
+    RoomRequest(KeyVerificationRequestEventContent),
+
+    /// *m.key.verification.start*
+    Start(start::StartEventContent),
+
+    /// Additional variants may be added in the future and will not be considered breaking changes
+    /// to ruma-events.
+    #[doc(hidden)]
+    __Nonexhaustive,
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{negotiate_method, VerificationMethod};
+
+    #[test]
+    fn prefers_qr_code() {
+        let shower = [
+            VerificationMethod::MSasV1,
+            VerificationMethod::ReciprocateV1,
+            VerificationMethod::QrCodeShowV1,
+        ];
+        let scanner = [
+            VerificationMethod::MSasV1,
+            VerificationMethod::ReciprocateV1,
+            VerificationMethod::QrCodeScanV1,
+        ];
+
+        assert_eq!(
+            negotiate_method(&shower, &scanner),
+            Some(VerificationMethod::ReciprocateV1)
+        );
+        assert_eq!(
+            negotiate_method(&scanner, &shower),
+            Some(VerificationMethod::ReciprocateV1)
+        );
+    }
+
+    #[test]
+    fn qr_code_needs_a_shower_and_a_scanner() {
+        let both = [
+            VerificationMethod::MSasV1,
+            VerificationMethod::ReciprocateV1,
+        ];
+        let shower = [
+            VerificationMethod::MSasV1,
+            VerificationMethod::ReciprocateV1,
+            VerificationMethod::QrCodeShowV1,
+        ];
+
+        assert_eq!(
+            negotiate_method(&both, &both),
+            Some(VerificationMethod::MSasV1)
+        );
+        assert_eq!(
+            negotiate_method(&shower, &shower),
+            Some(VerificationMethod::MSasV1)
+        );
+        assert_eq!(
+            negotiate_method(
+                &[
+                    VerificationMethod::ReciprocateV1,
+                    VerificationMethod::QrCodeScanV1
+                ],
+                &[
+                    VerificationMethod::ReciprocateV1,
+                    VerificationMethod::QrCodeScanV1
+                ]
+            ),
+            None
+        );
+    }
+
+    #[test]
+    fn sas_only_overlap() {
+        assert_eq!(
+            negotiate_method(
+                &[
+                    VerificationMethod::MSasV1,
+                    VerificationMethod::ReciprocateV1
+                ],
+                &[VerificationMethod::MSasV1]
+            ),
+            Some(VerificationMethod::MSasV1)
+        );
+    }
+
+    #[test]
+    fn no_overlap() {
+        assert_eq!(
+            negotiate_method(
+                &[VerificationMethod::ReciprocateV1],
+                &[VerificationMethod::MSasV1]
+            ),
+            None
+        );
+        assert_eq!(negotiate_method(&[VerificationMethod::MSasV1], &[]), None);
     }
 }
@@ -1,51 +1,243 @@
 //! Types for the *m.key.verification.accept* event.
 
-use ruma_events_macros::ruma_event;
+use serde::{Deserialize, Serialize};
 
 use super::{
     HashAlgorithm, KeyAgreementProtocol, MessageAuthenticationCode, ShortAuthenticationString,
     VerificationMethod,
 };
+use crate::{BadEvent, EventType, TryFromRaw};
+
+/// Accepts a previously sent *m.key.verification.start* messge.
+///
+/// Typically sent as a to-device event.
+#[derive(Clone, Debug, PartialEq, Serialize)]
+#[serde(rename = "m.key.verification.accept", tag = "type")]
+pub struct AcceptEvent {
+    /// The event's content.
+    pub content: AcceptEventContent,
+}
+
+/// The payload of an *m.key.verification.accept* event.
+#[derive(Clone, Debug, PartialEq, Serialize)]
+pub struct AcceptEventContent {
+    /// An opaque identifier for the verification process.
+    ///
+    /// Must be the same as the one used for the *m.key.verification.start* message.
+    pub transaction_id: String,
+
+    /// The verification method to use.
+    ///
+    /// Must be `m.sas.v1`.
+    pub method: VerificationMethod,
+
+    /// The key agreement protocol the device is choosing to use, out of the options in the
+    /// *m.key.verification.start* message.
+    pub key_agreement_protocol: KeyAgreementProtocol,
+
+    /// The hash method the device is choosing to use, out of the options in the
+    /// *m.key.verification.start* message.
+    pub hash: HashAlgorithm,
+
+    /// The message authentication code the device is choosing to use, out of the options in the
+    /// *m.key.verification.start* message.
+    pub message_authentication_code: MessageAuthenticationCode,
+
+    /// The SAS methods both devices involved in the verification process understand.
+    ///
+    /// Must be a subset of the options in the *m.key.verification.start* message.
+    pub short_authentication_string: Vec<ShortAuthenticationString>,
+
+    /// The hash (encoded as unpadded base64) of the concatenation of the device's ephemeral public
+    /// key (encoded as unpadded base64) and the canonical JSON representation of the
+    /// *m.key.verification.start* message.
+    pub commitment: String,
+}
+
+impl AcceptEventContent {
+    /// The SAS methods the accepting device chose, i.e. the ways in which the short authentication
+    /// string may be displayed to the users for comparison.
+    pub fn short_authentication_string(&self) -> &[ShortAuthenticationString] {
+        &self.short_authentication_string
+    }
+}
+
+impl TryFromRaw for AcceptEvent {
+    type Raw = raw::AcceptEvent;
+    type Err = BadEvent;
+
+    fn try_from_raw(raw: raw::AcceptEvent) -> Result<Self, Self::Err> {
+        AcceptEventContent::try_from_raw(raw.content).map(|content| Self { content })
+    }
+}
+
+impl_event!(
+    AcceptEvent,
+    AcceptEventContent,
+    EventType::KeyVerificationAccept
+);
+
+impl TryFromRaw for AcceptEventContent {
+    type Raw = raw::AcceptEventContent;
+    type Err = BadEvent;
+
+    fn try_from_raw(raw: raw::AcceptEventContent) -> Result<Self, Self::Err> {
+        fn required<T>(field: &'static str, value: Option<T>) -> Result<T, BadEvent> {
+            value.ok_or_else(|| BadEvent::Validation {
+                field,
+                message: "is required for the m.sas.v1 method".to_string(),
+            })
+        }
+
+        if raw.method != VerificationMethod::MSasV1 {
+            return Err(BadEvent::Validation {
+                field: "method",
+                message: "must be m.sas.v1".to_string(),
+            });
+        }
+
+        Ok(Self {
+            transaction_id: raw.transaction_id,
+            method: raw.method,
+            key_agreement_protocol: required("key_agreement_protocol", raw.key_agreement_protocol)?,
+            hash: required("hash", raw.hash)?,
+            message_authentication_code: required(
+                "message_authentication_code",
+                raw.message_authentication_code,
+            )?,
+            short_authentication_string: required(
+                "short_authentication_string",
+                raw.short_authentication_string,
+            )?,
+            commitment: required("commitment", raw.commitment)?,
+        })
+    }
+}
+
+pub(crate) mod raw {
+    use super::*;
 
-ruma_event! {
     /// Accepts a previously sent *m.key.verification.start* messge.
     ///
     /// Typically sent as a to-device event.
-    AcceptEvent {
-        kind: Event,
-        event_type: "m.key.verification.accept",
-        content: {
-            /// An opaque identifier for the verification process.
-            ///
-            /// Must be the same as the one used for the *m.key.verification.start* message.
-            pub transaction_id: String,
-
-            /// The verification method to use.
-            ///
-            /// Must be `m.sas.v1`.
-            pub method: VerificationMethod,
-
-            /// The key agreement protocol the device is choosing to use, out of the options in the
-            /// *m.key.verification.start* message.
-            pub key_agreement_protocol: KeyAgreementProtocol,
-
-            /// The hash method the device is choosing to use, out of the options in the
-            /// *m.key.verification.start* message.
-            pub hash: HashAlgorithm,
-
-            /// The message authentication code the device is choosing to use, out of the options in the
-            /// *m.key.verification.start* message.
-            pub message_authentication_code: MessageAuthenticationCode,
-
-            /// The SAS methods both devices involved in the verification process understand.
-            ///
-            /// Must be a subset of the options in the *m.key.verification.start* message.
-            pub short_authentication_string: Vec<ShortAuthenticationString>,
-
-            /// The hash (encoded as unpadded base64) of the concatenation of the device's ephemeral public
-            /// key (encoded as unpadded base64) and the canonical JSON representation of the
-            /// *m.key.verification.start* message.
-            pub commitment: String,
+    #[derive(Clone, Debug, Deserialize, PartialEq)]
+    pub struct AcceptEvent {
+        /// The event's content.
+        pub content: AcceptEventContent,
+    }
+
+    /// The payload of an *m.key.verification.accept* event.
+    #[derive(Clone, Debug, Deserialize, PartialEq)]
+    pub struct AcceptEventContent {
+        /// An opaque identifier for the verification process.
+        pub transaction_id: String,
+
+        /// The verification method to use.
+        pub method: VerificationMethod,
+
+        /// The key agreement protocol the device is choosing to use.
+        pub key_agreement_protocol: Option<KeyAgreementProtocol>,
+
+        /// The hash method the device is choosing to use.
+        pub hash: Option<HashAlgorithm>,
+
+        /// The message authentication code the device is choosing to use.
+        pub message_authentication_code: Option<MessageAuthenticationCode>,
+
+        /// The SAS methods both devices involved in the verification process understand.
+        pub short_authentication_string: Option<Vec<ShortAuthenticationString>>,
+
+        /// The commitment to the device's ephemeral public key and the start message.
+        pub commitment: Option<String>,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use serde_json::{from_value as from_json_value, json, to_value as to_json_value};
+
+    use super::AcceptEventContent;
+    use crate::{
+        key::verification::{
+            HashAlgorithm, KeyAgreementProtocol, MessageAuthenticationCode,
+            ShortAuthenticationString,
         },
+        util::try_from_raw_round_trip,
+        EventResult,
+    };
+
+    fn accept_json() -> serde_json::Value {
+        json!({
+            "transaction_id": "456",
+            "method": "m.sas.v1",
+            "key_agreement_protocol": "curve25519",
+            "hash": "sha256",
+            "message_authentication_code": "hkdf-hmac-sha256",
+            "short_authentication_string": ["decimal", "emoji"],
+            "commitment": "fQpGIW1Snz+pwLZu6W4T3XJ6l8elZIuRcCu5GdG5w2M"
+        })
+    }
+
+    #[test]
+    fn valid_accept() {
+        let content = from_json_value::<EventResult<AcceptEventContent>>(accept_json())
+            .unwrap()
+            .into_result()
+            .unwrap();
+
+        assert_eq!(
+            content.key_agreement_protocol,
+            KeyAgreementProtocol::Curve25519
+        );
+        assert_eq!(content.hash, HashAlgorithm::Sha256);
+        assert_eq!(
+            content.message_authentication_code,
+            MessageAuthenticationCode::HkdfHmacSha256
+        );
+        assert_eq!(
+            content.short_authentication_string,
+            vec![
+                ShortAuthenticationString::Decimal,
+                ShortAuthenticationString::Emoji
+            ]
+        );
+        try_from_raw_round_trip::<AcceptEventContent>(accept_json());
+    }
+
+    #[test]
+    fn decimal_and_emoji_sas() {
+        let content = from_json_value::<EventResult<AcceptEventContent>>(accept_json())
+            .unwrap()
+            .into_result()
+            .unwrap();
+
+        assert_eq!(
+            content.short_authentication_string(),
+            &[
+                ShortAuthenticationString::Decimal,
+                ShortAuthenticationString::Emoji
+            ]
+        );
+        assert_eq!(
+            to_json_value(&content).unwrap()["short_authentication_string"],
+            json!(["decimal", "emoji"])
+        );
+    }
+
+    #[test]
+    fn accept_without_commitment() {
+        let mut json = accept_json();
+        json.as_object_mut().unwrap().remove("commitment");
+
+        let error = from_json_value::<EventResult<AcceptEventContent>>(json)
+            .unwrap()
+            .into_result()
+            .unwrap_err();
+
+        assert!(error.is_validation());
+        assert_eq!(
+            error.message(),
+            "`commitment` is required for the m.sas.v1 method"
+        );
     }
 }
@@ -30,6 +30,62 @@ ruma_event! {
     }
 }
 
+impl CancelEventContent {
+    /// Creates a new `CancelEventContent` with the given code and reason.
+    pub fn with_code(transaction_id: String, code: CancelCode, reason: String) -> Self {
+        Self {
+            transaction_id,
+            reason,
+            code,
+        }
+    }
+
+    /// Cancels a verification because the user cancelled it.
+    pub fn user(transaction_id: String) -> Self {
+        Self::with_code(
+            transaction_id,
+            CancelCode::User,
+            "The user cancelled the verification.".to_string(),
+        )
+    }
+
+    /// Cancels a verification because it timed out.
+    pub fn timeout(transaction_id: String) -> Self {
+        Self::with_code(
+            transaction_id,
+            CancelCode::Timeout,
+            "The verification process timed out.".to_string(),
+        )
+    }
+
+    /// Cancels a verification because its transaction ID is not known.
+    pub fn unknown_transaction(transaction_id: String) -> Self {
+        Self::with_code(
+            transaction_id,
+            CancelCode::UnknownTransaction,
+            "The device does not know about this transaction.".to_string(),
+        )
+    }
+
+    /// Cancels a verification because a message was received out of order.
+    pub fn unexpected_message(transaction_id: String) -> Self {
+        Self::with_code(
+            transaction_id,
+            CancelCode::UnexpectedMessage,
+            "The device received an unexpected message.".to_string(),
+        )
+    }
+
+    /// Cancels a verification because the keys didn't match.
+    pub fn key_mismatch(transaction_id: String) -> Self {
+        Self::with_code(
+            transaction_id,
+            CancelCode::KeyMismatch,
+            "The key was not verified.".to_string(),
+        )
+    }
+}
+
 /// An error code for why the process/request was cancelled by the user.
 ///
 /// Custom error codes should use the Java package naming convention.
@@ -136,7 +192,7 @@ impl From<CancelCode> for String {
 mod tests {
     use serde_json::{from_value as from_json_value, json, to_value as to_json_value};
 
-    use super::CancelCode;
+    use super::{CancelCode, CancelEventContent};
 
     #[test]
     fn cancel_codes_serialize_to_display_form() {
@@ -166,4 +222,59 @@ mod tests {
             CancelCode::Custom("io.ruma.test".to_string())
         )
     }
+
+    fn assert_content(content: CancelEventContent, code: CancelCode, reason: &str) {
+        assert_eq!(content.transaction_id, "456");
+        assert_eq!(content.code, code);
+        assert_eq!(content.reason, reason);
+    }
+
+    #[test]
+    fn constructors() {
+        let transaction_id = || "456".to_string();
+
+        assert_content(
+            CancelEventContent::user(transaction_id()),
+            CancelCode::User,
+            "The user cancelled the verification.",
+        );
+        assert_content(
+            CancelEventContent::timeout(transaction_id()),
+            CancelCode::Timeout,
+            "The verification process timed out.",
+        );
+        assert_content(
+            CancelEventContent::unknown_transaction(transaction_id()),
+            CancelCode::UnknownTransaction,
+            "The device does not know about this transaction.",
+        );
+        assert_content(
+            CancelEventContent::unexpected_message(transaction_id()),
+            CancelCode::UnexpectedMessage,
+            "The device received an unexpected message.",
+        );
+        assert_content(
+            CancelEventContent::key_mismatch(transaction_id()),
+            CancelCode::KeyMismatch,
+            "The key was not verified.",
+        );
+    }
+
+    #[test]
+    fn with_code() {
+        let content = CancelEventContent::with_code(
+            "456".to_string(),
+            CancelCode::Custom("io.ruma.test".to_string()),
+            "Testing".to_string(),
+        );
+
+        assert_eq!(
+            to_json_value(&content).unwrap(),
+            json!({
+                "transaction_id": "456",
+                "reason": "Testing",
+                "code": "io.ruma.test"
+            })
+        );
+    }
 }
@@ -0,0 +1,312 @@
+//! Types for key verification events sent in a room rather than as to-device events.
+
+use js_int::UInt;
+use ruma_identifiers::{EventId, RoomId, UserId};
+use serde::{
+    ser::{Error as _, SerializeStruct},
+    Deserialize, Serialize, Serializer,
+};
+use serde_json::{Map, Value};
+
+use super::KeyVerificationContent;
+use crate::{BadEvent, EventType, TryFromRaw};
+
+/// The relation type tying in-room key verification events to the request that started them.
+const REFERENCE_REL_TYPE: &str = "m.reference";
+
+/// An *m.key.verification.accept*, *m.key.verification.cancel*, *m.key.verification.key*,
+/// *m.key.verification.mac* or *m.key.verification.start* event sent in a room.
+///
+/// In a room, these events don't have a `transaction_id`. Instead, their `m.relates_to` field
+/// refers to the *m.room.message* that requested the verification with an *m.reference* relation,
+/// and the ID of that event serves as the transaction ID. It is stored as the `transaction_id` of
+/// `content`, so that the content can be handled like that of the to-device form.
+#[derive(Clone, Debug, PartialEq)]
+pub struct InRoomVerificationEvent {
+    /// The event's content.
+    pub content: KeyVerificationContent,
+
+    /// The unique identifier for the event.
+    pub event_id: EventId,
+
+    /// Timestamp (milliseconds since the UNIX epoch) on originating homeserver when this event was
+    /// sent.
+    pub origin_server_ts: UInt,
+
+    /// The unique identifier for the room associated with this event.
+    pub room_id: Option<RoomId>,
+
+    /// The unique identifier for the user who sent this event.
+    pub sender: UserId,
+
+    /// Additional key-value pairs not signed by the homeserver.
+    pub unsigned: Map<String, Value>,
+}
+
+impl InRoomVerificationEvent {
+    /// The type of this event, e.g. *m.key.verification.accept*.
+    ///
+    /// Returns `None` if `content` is not of a type that can be sent in a room this way.
+    pub fn event_type(&self) -> Option<EventType> {
+        let event_type = match self.content {
+            KeyVerificationContent::Accept(_) => EventType::KeyVerificationAccept,
+            KeyVerificationContent::Cancel(_) => EventType::KeyVerificationCancel,
+            KeyVerificationContent::Key(_) => EventType::KeyVerificationKey,
+            KeyVerificationContent::Mac(_) => EventType::KeyVerificationMac,
+            KeyVerificationContent::Start(_) => EventType::KeyVerificationStart,
+            _ => return None,
+        };
+
+        Some(event_type)
+    }
+}
+
+impl TryFromRaw for InRoomVerificationEvent {
+    type Raw = raw::InRoomVerificationEvent;
+    type Err = BadEvent;
+
+    fn try_from_raw(raw: raw::InRoomVerificationEvent) -> Result<Self, Self::Err> {
+        fn parse<C: TryFromRaw>(content: Value) -> Result<C, BadEvent>
+        where
+            C::Err: Into<BadEvent>,
+        {
+            C::try_from_raw(serde_json::from_value(content)?).map_err(Into::into)
+        }
+
+        let mut content = match raw.content {
+            Value::Object(content) => content,
+            _ => {
+                return Err(BadEvent::Deserialization(
+                    "the content of an event must be an object".to_string(),
+                ))
+            }
+        };
+
+        let relation = match content.remove("m.relates_to") {
+            Some(relation) => serde_json::from_value::<raw::Reference>(relation)?,
+            None => {
+                return Err(BadEvent::Validation {
+                    field: "m.relates_to",
+                    message: "is required for key verification events in a room".to_string(),
+                })
+            }
+        };
+
+        if relation.rel_type != REFERENCE_REL_TYPE {
+            return Err(BadEvent::Validation {
+                field: "m.relates_to",
+                message: "must be an m.reference relation".to_string(),
+            });
+        }
+
+        content.insert(
+            "transaction_id".to_string(),
+            Value::String(relation.event_id.to_string()),
+        );
+        let content = Value::Object(content);
+
+        let content = match raw.event_type {
+            EventType::KeyVerificationAccept => KeyVerificationContent::Accept(parse(content)?),
+            EventType::KeyVerificationCancel => KeyVerificationContent::Cancel(parse(content)?),
+            EventType::KeyVerificationKey => KeyVerificationContent::Key(parse(content)?),
+            EventType::KeyVerificationMac => KeyVerificationContent::Mac(parse(content)?),
+            EventType::KeyVerificationStart => KeyVerificationContent::Start(parse(content)?),
+            event_type => return Err(BadEvent::UnknownEventType(event_type.to_string())),
+        };
+
+        Ok(Self {
+            content,
+            event_id: raw.event_id,
+            origin_server_ts: raw.origin_server_ts,
+            room_id: raw.room_id,
+            sender: raw.sender,
+            unsigned: raw.unsigned,
+        })
+    }
+}
+
+impl_from_str!(InRoomVerificationEvent);
+
+impl Serialize for InRoomVerificationEvent {
+    fn serialize<S>(&self, serializer: S) -> Result<S::Ok, S::Error>
+    where
+        S: Serializer,
+    {
+        let event_type = self.event_type().ok_or_else(|| {
+            S::Error::custom("content can't be sent as an in-room key verification event")
+        })?;
+
+        let content = match &self.content {
+            KeyVerificationContent::Accept(content) => serde_json::to_value(content),
+            KeyVerificationContent::Cancel(content) => serde_json::to_value(content),
+            KeyVerificationContent::Key(content) => serde_json::to_value(content),
+            KeyVerificationContent::Mac(content) => serde_json::to_value(content),
+            KeyVerificationContent::Start(content) => serde_json::to_value(content),
+            _ => unreachable!("event_type() only returns a type for the contents above"),
+        };
+        let mut content = match content.map_err(S::Error::custom)? {
+            Value::Object(content) => content,
+            _ => return Err(S::Error::custom("content didn't serialize to an object")),
+        };
+
+        let transaction_id = content
+            .remove("transaction_id")
+            .ok_or_else(|| S::Error::custom("content is missing a transaction ID"))?;
+        let mut relation = Map::new();
+        relation.insert(
+            "rel_type".to_string(),
+            Value::String(REFERENCE_REL_TYPE.to_string()),
+        );
+        relation.insert("event_id".to_string(), transaction_id);
+        content.insert("m.relates_to".to_string(), Value::Object(relation));
+
+        let mut len = 5;
+        if self.room_id.is_some() {
+            len += 1;
+        }
+        if !self.unsigned.is_empty() {
+            len += 1;
+        }
+
+        let mut state = serializer.serialize_struct("InRoomVerificationEvent", len)?;
+
+        state.serialize_field("content", &content)?;
+        state.serialize_field("event_id", &self.event_id)?;
+        state.serialize_field("origin_server_ts", &self.origin_server_ts)?;
+        if self.room_id.is_some() {
+            state.serialize_field("room_id", &self.room_id)?;
+        }
+        state.serialize_field("sender", &self.sender)?;
+        state.serialize_field("type", &event_type)?;
+        if !self.unsigned.is_empty() {
+            state.serialize_field("unsigned", &self.unsigned)?;
+        }
+
+        state.end()
+    }
+}
+
+pub(crate) mod raw {
+    use super::*;
+
+    /// A key verification event sent in a room.
+    #[derive(Clone, Debug, Deserialize, PartialEq)]
+    pub struct InRoomVerificationEvent {
+        /// The event's content, including its `m.relates_to` field.
+        pub content: Value,
+
+        /// The unique identifier for the event.
+        pub event_id: EventId,
+
+        /// The type of the event.
+        #[serde(rename = "type")]
+        pub event_type: EventType,
+
+        /// Timestamp (milliseconds since the UNIX epoch) on originating homeserver when this event
+        /// was sent.
+        #[serde(deserialize_with = "crate::__private::uint_or_string")]
+        pub origin_server_ts: UInt,
+
+        /// The unique identifier for the room associated with this event.
+        pub room_id: Option<RoomId>,
+
+        /// The unique identifier for the user who sent this event.
+        pub sender: UserId,
+
+        /// Additional key-value pairs not signed by the homeserver.
+        #[serde(default)]
+        pub unsigned: Map<String, Value>,
+    }
+
+    /// The `m.relates_to` field of an in-room key verification event.
+    #[derive(Clone, Debug, Deserialize, PartialEq)]
+    pub struct Reference {
+        /// The type of the relation, which must be *m.reference*.
+        pub rel_type: String,
+
+        /// The *m.room.message* that requested the verification.
+        pub event_id: EventId,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use serde_json::{from_value as from_json_value, json, to_value as to_json_value, Value};
+
+    use super::InRoomVerificationEvent;
+    use crate::{key::verification::KeyVerificationContent, EventResult};
+
+    fn accept_json() -> Value {
+        json!({
+            "content": {
+                "method": "m.sas.v1",
+                "key_agreement_protocol": "curve25519",
+                "hash": "sha256",
+                "message_authentication_code": "hkdf-hmac-sha256",
+                "short_authentication_string": ["decimal"],
+                "commitment": "fQpGIW1Snz+pwLZu6W4T3XJ6l8elZIuRcCu5GdG5w2M",
+                "m.relates_to": {
+                    "rel_type": "m.reference",
+                    "event_id": "$request:example.com"
+                }
+            },
+            "event_id": "$accept:example.com",
+            "origin_server_ts": 2,
+            "room_id": "!room:example.com",
+            "sender": "@bob:example.com",
+            "type": "m.key.verification.accept"
+        })
+    }
+
+    #[test]
+    fn accept_round_trip() {
+        let event = from_json_value::<EventResult<InRoomVerificationEvent>>(accept_json())
+            .unwrap()
+            .into_result()
+            .unwrap();
+
+        match &event.content {
+            KeyVerificationContent::Accept(accept) => {
+                assert_eq!(accept.transaction_id, "$request:example.com");
+            }
+            other => panic!("unexpected verification content: {:?}", other),
+        }
+        assert_eq!(to_json_value(&event).unwrap(), accept_json());
+    }
+
+    #[test]
+    fn missing_reference() {
+        let mut json = accept_json();
+        json["content"]
+            .as_object_mut()
+            .unwrap()
+            .remove("m.relates_to");
+
+        let error = from_json_value::<EventResult<InRoomVerificationEvent>>(json)
+            .unwrap()
+            .into_result()
+            .unwrap_err();
+
+        assert!(error.is_validation());
+        assert_eq!(
+            error.message(),
+            "`m.relates_to` is required for key verification events in a room"
+        );
+    }
+
+    #[test]
+    fn wrong_relation_type() {
+        let mut json = accept_json();
+        json["content"]["m.relates_to"]["rel_type"] = json!("m.replace");
+
+        let error = from_json_value::<EventResult<InRoomVerificationEvent>>(json)
+            .unwrap()
+            .into_result()
+            .unwrap_err();
+
+        assert_eq!(
+            error.message(),
+            "`m.relates_to` must be an m.reference relation"
+        );
+    }
+}
@@ -1,22 +1,167 @@
 //! Types for the *m.key.verification.key* event.
 
-use ruma_events_macros::ruma_event;
+use std::fmt::{Debug, Formatter, Result as FmtResult};
+
+use serde::{Deserialize, Serialize};
+
+use crate::{
+    util::{decode_base64, Redacted},
+    BadEvent, EventType, TryFromRaw,
+};
+
+/// Sends the ephemeral public key for a device to the partner device.
+///
+/// Typically sent as a to-device event.
+#[derive(Clone, Debug, PartialEq, Serialize)]
+#[serde(rename = "m.key.verification.key", tag = "type")]
+pub struct KeyEvent {
+    /// The event's content.
+    pub content: KeyEventContent,
+}
+
+/// The payload of an *m.key.verification.key* event.
+///
+/// The `Debug` output doesn't contain the key.
+#[derive(Clone, PartialEq, Serialize)]
+pub struct KeyEventContent {
+    /// An opaque identifier for the verification process.
+    ///
+    /// Must be the same as the one used for the *m.key.verification.start* message.
+    pub transaction_id: String,
+
+    /// The device's ephemeral public key, encoded as unpadded Base64.
+    pub key: String,
+}
+
+impl KeyEventContent {
+    /// The device's ephemeral public key, decoded from Base64.
+    ///
+    /// Returns `None` if `key` is not valid Base64, which can only happen if this content wasn't
+    /// obtained through deserialization.
+    pub fn key_bytes(&self) -> Option<Vec<u8>> {
+        decode_base64(&self.key)
+    }
+}
+
+impl Debug for KeyEventContent {
+    fn fmt(&self, f: &mut Formatter<'_>) -> FmtResult {
+        f.debug_struct("KeyEventContent")
+            .field("transaction_id", &self.transaction_id)
+            .field("key", &Redacted)
+            .finish()
+    }
+}
+
+impl TryFromRaw for KeyEvent {
+    type Raw = raw::KeyEvent;
+    type Err = BadEvent;
+
+    fn try_from_raw(raw: raw::KeyEvent) -> Result<Self, Self::Err> {
+        KeyEventContent::try_from_raw(raw.content).map(|content| Self { content })
+    }
+}
+
+impl_event!(KeyEvent, KeyEventContent, EventType::KeyVerificationKey);
+
+impl TryFromRaw for KeyEventContent {
+    type Raw = raw::KeyEventContent;
+    type Err = BadEvent;
+
+    fn try_from_raw(raw: raw::KeyEventContent) -> Result<Self, Self::Err> {
+        if decode_base64(&raw.key).is_none() {
+            return Err(BadEvent::Validation {
+                field: "key",
+                message: "must be encoded as unpadded Base64".to_string(),
+            });
+        }
+
+        Ok(Self {
+            transaction_id: raw.transaction_id,
+            key: raw.key,
+        })
+    }
+}
+
+pub(crate) mod raw {
+    use super::*;
 
-ruma_event! {
     /// Sends the ephemeral public key for a device to the partner device.
     ///
     /// Typically sent as a to-device event.
-    KeyEvent {
-        kind: Event,
-        event_type: "m.key.verification.key",
-        content: {
-            /// An opaque identifier for the verification process.
-            ///
-            /// Must be the same as the one used for the *m.key.verification.start* message.
-            pub transaction_id: String,
-
-            /// The device's ephemeral public key, encoded as unpadded Base64.
-            pub key: String,
-        },
+    #[derive(Clone, Debug, Deserialize, PartialEq)]
+    pub struct KeyEvent {
+        /// The event's content.
+        pub content: KeyEventContent,
+    }
+
+    /// The payload of an *m.key.verification.key* event.
+    #[derive(Clone, Debug, Deserialize, PartialEq)]
+    pub struct KeyEventContent {
+        /// An opaque identifier for the verification process.
+        pub transaction_id: String,
+
+        /// The device's ephemeral public key, encoded as unpadded Base64.
+        pub key: String,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use serde_json::{from_value as from_json_value, json};
+
+    use super::{KeyEvent, KeyEventContent};
+    use crate::EventResult;
+
+    #[test]
+    fn valid_key() {
+        let json = json!({
+            "content": {
+                "transaction_id": "456",
+                "key": "ZGV2aWNlIGtleQ"
+            },
+            "type": "m.key.verification.key"
+        });
+
+        let event = from_json_value::<EventResult<KeyEvent>>(json)
+            .unwrap()
+            .into_result()
+            .unwrap();
+
+        assert_eq!(
+            event.content,
+            KeyEventContent {
+                transaction_id: "456".to_string(),
+                key: "ZGV2aWNlIGtleQ".to_string(),
+            }
+        );
+        assert_eq!(event.content.key_bytes(), Some(b"device key".to_vec()));
+    }
+
+    #[test]
+    fn invalid_key() {
+        let json = json!({
+            "transaction_id": "456",
+            "key": "not base64!"
+        });
+
+        let error = from_json_value::<EventResult<KeyEventContent>>(json)
+            .unwrap()
+            .into_result()
+            .unwrap_err();
+
+        assert!(error.is_validation());
+        assert_eq!(error.message(), "`key` must be encoded as unpadded Base64");
+    }
+
+    #[test]
+    fn debug_hides_key() {
+        let content = KeyEventContent {
+            transaction_id: "456".to_string(),
+            key: "ZGV2aWNlIGtleQ".to_string(),
+        };
+        let debug = format!("{:?}", content);
+
+        assert!(debug.contains("456"));
+        assert!(!debug.contains("ZGV2aWNlIGtleQ"));
     }
 }
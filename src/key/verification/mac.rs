@@ -1,30 +1,302 @@
 //! Types for the *m.key.verification.mac* event.
 
-use std::collections::HashMap;
+use std::{
+    collections::HashMap,
+    fmt::{Debug, Formatter, Result as FmtResult},
+};
 
-use ruma_events_macros::ruma_event;
+use serde::{Deserialize, Serialize};
+
+use crate::{
+    util::{decode_base64, Redacted},
+    BadEvent, EventType, TryFromRaw,
+};
+
+/// Sends the MAC of a device's key to the partner device.
+///
+/// Typically sent as a to-device event.
+#[derive(Clone, Debug, PartialEq, Serialize)]
+#[serde(rename = "m.key.verification.mac", tag = "type")]
+pub struct MacEvent {
+    /// The event's content.
+    pub content: MacEventContent,
+}
+
+/// The payload of an *m.key.verification.mac* event.
+///
+/// The `Debug` output contains the key IDs, but none of the MACs.
+#[derive(Clone, PartialEq, Serialize)]
+pub struct MacEventContent {
+    /// An opaque identifier for the verification process.
+    ///
+    /// Must be the same as the one used for the *m.key.verification.start* message.
+    pub transaction_id: String,
+
+    /// A map of the key ID to the MAC of the key, using the algorithm in the verification process.
+    ///
+    /// The MAC is encoded as unpadded Base64.
+    #[serde(serialize_with = "crate::util::sorted_map")]
+    pub mac: HashMap<String, String>,
+
+    /// The MAC of the comma-separated, sorted, list of key IDs given in the `mac` property, encoded
+    /// as unpadded Base64.
+    pub keys: String,
+}
+
+impl MacEventContent {
+    /// The MAC of the key with the given ID, as sent, i.e. encoded as unpadded Base64.
+    // TODO: Switch to `Option::as_deref` and remove this attribute once MSRV is >= 1.40
+    #[allow(clippy::option_as_ref_deref, unknown_lints)]
+    pub fn mac_for(&self, key_id: &str) -> Option<&str> {
+        self.mac.get(key_id).map(String::as_str)
+    }
+
+    /// The MAC of the key with the given ID, decoded from Base64.
+    ///
+    /// Returns `None` if there is no MAC for the key, or if it is not valid Base64, which can only
+    /// happen if this content wasn't obtained through deserialization.
+    pub fn mac_bytes_for(&self, key_id: &str) -> Option<Vec<u8>> {
+        self.mac_for(key_id).and_then(decode_base64)
+    }
+
+    /// The MAC of the list of key IDs, as sent, i.e. encoded as unpadded Base64.
+    pub fn keys_mac(&self) -> &str {
+        &self.keys
+    }
+
+    /// The MAC of the list of key IDs, decoded from Base64.
+    ///
+    /// Returns `None` if `keys` is not valid Base64, which can only happen if this content wasn't
+    /// obtained through deserialization.
+    pub fn keys_mac_bytes(&self) -> Option<Vec<u8>> {
+        decode_base64(&self.keys)
+    }
+
+    /// The sorted IDs of all keys in `mac`.
+    ///
+    /// Joined with commas, these are the input of the MAC in `keys`.
+    pub fn expected_key_ids(&self) -> Vec<&str> {
+        let mut key_ids: Vec<&str> = self.mac.keys().map(String::as_str).collect();
+        key_ids.sort();
+
+        key_ids
+    }
+}
+
+impl Debug for MacEventContent {
+    fn fmt(&self, f: &mut Formatter<'_>) -> FmtResult {
+        f.debug_struct("MacEventContent")
+            .field("transaction_id", &self.transaction_id)
+            .field(
+                "mac",
+                &self
+                    .mac
+                    .keys()
+                    .map(|key_id| (key_id, Redacted))
+                    .collect::<HashMap<_, _>>(),
+            )
+            .field("keys", &Redacted)
+            .finish()
+    }
+}
+
+impl TryFromRaw for MacEvent {
+    type Raw = raw::MacEvent;
+    type Err = BadEvent;
+
+    fn try_from_raw(raw: raw::MacEvent) -> Result<Self, Self::Err> {
+        MacEventContent::try_from_raw(raw.content).map(|content| Self { content })
+    }
+}
+
+impl_event!(MacEvent, MacEventContent, EventType::KeyVerificationMac);
+
+impl TryFromRaw for MacEventContent {
+    type Raw = raw::MacEventContent;
+    type Err = BadEvent;
+
+    fn try_from_raw(raw: raw::MacEventContent) -> Result<Self, Self::Err> {
+        if raw.mac.values().any(|mac| decode_base64(mac).is_none()) {
+            return Err(BadEvent::Validation {
+                field: "mac",
+                message: "must only contain MACs encoded as unpadded Base64".to_string(),
+            });
+        }
+
+        if decode_base64(&raw.keys).is_none() {
+            return Err(BadEvent::Validation {
+                field: "keys",
+                message: "must be encoded as unpadded Base64".to_string(),
+            });
+        }
+
+        Ok(Self {
+            transaction_id: raw.transaction_id,
+            mac: raw.mac,
+            keys: raw.keys,
+        })
+    }
+}
+
+pub(crate) mod raw {
+    use super::*;
 
-ruma_event! {
     /// Sends the MAC of a device's key to the partner device.
     ///
     /// Typically sent as a to-device event.
-    MacEvent {
-        kind: Event,
-        event_type: "m.key.verification.mac",
-        content: {
-            /// An opaque identifier for the verification process.
-            ///
-            /// Must be the same as the one used for the *m.key.verification.start* message.
-            pub transaction_id: String,
-
-            /// A map of the key ID to the MAC of the key, using the algorithm in the verification process.
-            ///
-            /// The MAC is encoded as unpadded Base64.
-            pub mac: HashMap<String, String>,
-
-            /// The MAC of the comma-separated, sorted, list of key IDs given in the `mac` property, encoded
-            /// as unpadded Base64.
-            pub keys: String,
-        },
+    #[derive(Clone, Debug, Deserialize, PartialEq)]
+    pub struct MacEvent {
+        /// The event's content.
+        pub content: MacEventContent,
+    }
+
+    /// The payload of an *m.key.verification.mac* event.
+    #[derive(Clone, Debug, Deserialize, PartialEq)]
+    pub struct MacEventContent {
+        /// An opaque identifier for the verification process.
+        pub transaction_id: String,
+
+        /// A map of the key ID to the MAC of the key, encoded as unpadded Base64.
+        pub mac: HashMap<String, String>,
+
+        /// The MAC of the list of key IDs, encoded as unpadded Base64.
+        pub keys: String,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use maplit::hashmap;
+    use serde_json::{from_value as from_json_value, json, Map, Value};
+
+    use super::{MacEvent, MacEventContent};
+    use crate::{util::assert_deterministic_serialization, EventResult};
+
+    #[test]
+    fn valid_macs() {
+        let json = json!({
+            "content": {
+                "transaction_id": "456",
+                "mac": {
+                    "ed25519:DEVICE": "c2hhMjU2IG1hYw"
+                },
+                "keys": "a2V5cyBtYWM"
+            },
+            "type": "m.key.verification.mac"
+        });
+
+        let content = from_json_value::<EventResult<MacEvent>>(json)
+            .unwrap()
+            .into_result()
+            .unwrap()
+            .content;
+
+        assert_eq!(
+            content.mac_bytes_for("ed25519:DEVICE"),
+            Some(b"sha256 mac".to_vec())
+        );
+        assert_eq!(content.mac_bytes_for("ed25519:OTHER"), None);
+        assert_eq!(content.keys_mac(), "a2V5cyBtYWM");
+        assert_eq!(content.keys_mac_bytes(), Some(b"keys mac".to_vec()));
+    }
+
+    #[test]
+    fn invalid_key_mac() {
+        let json = json!({
+            "transaction_id": "456",
+            "mac": {
+                "ed25519:DEVICE": "c2hhMjU2IG1hYw",
+                "ed25519:OTHER": "not base64!"
+            },
+            "keys": "a2V5cyBtYWM"
+        });
+
+        let error = from_json_value::<EventResult<MacEventContent>>(json)
+            .unwrap()
+            .into_result()
+            .unwrap_err();
+
+        assert!(error.is_validation());
+        assert_eq!(
+            error.message(),
+            "`mac` must only contain MACs encoded as unpadded Base64"
+        );
+    }
+
+    #[test]
+    fn invalid_keys_mac() {
+        let json = json!({
+            "transaction_id": "456",
+            "mac": {
+                "ed25519:DEVICE": "c2hhMjU2IG1hYw"
+            },
+            "keys": "a2V5c*yBtYWM"
+        });
+
+        let error = from_json_value::<EventResult<MacEventContent>>(json)
+            .unwrap()
+            .into_result()
+            .unwrap_err();
+
+        assert!(error.is_validation());
+        assert_eq!(error.message(), "`keys` must be encoded as unpadded Base64");
+    }
+
+    #[test]
+    fn macs_for_two_keys() {
+        let json = json!({
+            "transaction_id": "456",
+            "mac": {
+                "ed25519:DEVICE": "c2hhMjU2IG1hYw",
+                "ed25519:ABCDEFGH": "b3RoZXIgbWFj"
+            },
+            "keys": "a2V5cyBtYWM"
+        });
+
+        let content = from_json_value::<EventResult<MacEventContent>>(json)
+            .unwrap()
+            .into_result()
+            .unwrap();
+
+        assert_eq!(
+            content.expected_key_ids(),
+            vec!["ed25519:ABCDEFGH", "ed25519:DEVICE"]
+        );
+        assert_eq!(content.mac_for("ed25519:DEVICE"), Some("c2hhMjU2IG1hYw"));
+        assert_eq!(content.mac_for("ed25519:ABCDEFGH"), Some("b3RoZXIgbWFj"));
+        assert_eq!(content.mac_for("ed25519:OTHER"), None);
+        assert_eq!(
+            content.mac_bytes_for("ed25519:ABCDEFGH"),
+            Some(b"other mac".to_vec())
+        );
+    }
+
+    #[test]
+    fn deterministic_serialization() {
+        let mac: Map<String, Value> = (0..8)
+            .map(|i| (format!("ed25519:DEVICE{}", i), json!("c2hhMjU2IG1hYw")))
+            .collect();
+
+        assert_deterministic_serialization::<MacEventContent>(json!({
+            "transaction_id": "456",
+            "mac": mac,
+            "keys": "a2V5cyBtYWM"
+        }));
+    }
+
+    #[test]
+    fn debug_hides_macs() {
+        let content = MacEventContent {
+            transaction_id: "456".to_string(),
+            mac: hashmap! {
+                "ed25519:DEVICE".to_string() => "c2hhMjU2IG1hYw".to_string()
+            },
+            keys: "a2V5cyBtYWM".to_string(),
+        };
+        let debug = format!("{:?}", content);
+
+        assert!(debug.contains("ed25519:DEVICE"));
+        assert!(!debug.contains("c2hhMjU2IG1hYw"));
+        assert!(!debug.contains("a2V5cyBtYWM"));
     }
 }
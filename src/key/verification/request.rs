@@ -1,5 +1,7 @@
 //! Types for the *m.key.verification.request* event.
 
+use std::time::{Duration, SystemTime, UNIX_EPOCH};
+
 use js_int::UInt;
 use ruma_events_macros::ruma_event;
 use ruma_identifiers::DeviceId;
@@ -33,3 +35,85 @@ ruma_event! {
         },
     }
 }
+
+/// How long a verification request stays valid after its `timestamp`.
+const REQUEST_VALIDITY: Duration = Duration::from_secs(10 * 60);
+
+/// How far a verification request's `timestamp` may be ahead of the receiver's clock.
+const MAX_CLOCK_SKEW: Duration = Duration::from_secs(5 * 60);
+
+impl RequestEventContent {
+    /// Whether the request was made more than 10 minutes before `now`, or is dated more than 5
+    /// minutes after it, meaning the receiver should ignore it.
+    ///
+    /// Requests with a `timestamp` that can't be represented as a `SystemTime` are invalid and
+    /// considered expired as well.
+    pub fn is_expired(&self, now: SystemTime) -> bool {
+        let requested_at =
+            match UNIX_EPOCH.checked_add(Duration::from_millis(self.timestamp.into())) {
+                Some(requested_at) => requested_at,
+                None => return true,
+            };
+
+        match now.duration_since(requested_at) {
+            Ok(age) => age > REQUEST_VALIDITY,
+            // The request was made after `now`.
+            Err(error) => error.duration() > MAX_CLOCK_SKEW,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::{
+        convert::TryFrom,
+        time::{Duration, UNIX_EPOCH},
+    };
+
+    use js_int::UInt;
+
+    use super::RequestEventContent;
+    use crate::key::verification::VerificationMethod;
+
+    fn request_at(timestamp: u64) -> RequestEventContent {
+        RequestEventContent {
+            from_device: "AliceDevice1".to_string(),
+            transaction_id: "S0meUniqueAndOpaqueString".to_string(),
+            methods: vec![VerificationMethod::MSasV1],
+            timestamp: UInt::try_from(timestamp).unwrap(),
+        }
+    }
+
+    #[test]
+    fn fresh_request() {
+        let request = request_at(1_559_598_944_869);
+        let requested_at = UNIX_EPOCH
+            .checked_add(Duration::from_millis(request.timestamp.into()))
+            .unwrap();
+
+        assert!(!request.is_expired(requested_at));
+        assert!(!request.is_expired(requested_at + Duration::from_secs(9 * 60)));
+        assert!(!request.is_expired(requested_at - Duration::from_secs(60)));
+    }
+
+    #[test]
+    fn expired_request() {
+        let request = request_at(1_559_598_944_869);
+        let requested_at = UNIX_EPOCH
+            .checked_add(Duration::from_millis(request.timestamp.into()))
+            .unwrap();
+
+        assert!(request.is_expired(requested_at + Duration::from_secs(11 * 60)));
+    }
+
+    #[test]
+    fn future_request() {
+        let request = request_at(1_559_598_944_869);
+        let requested_at = UNIX_EPOCH
+            .checked_add(Duration::from_millis(request.timestamp.into()))
+            .unwrap();
+
+        assert!(!request.is_expired(requested_at - Duration::from_secs(4 * 60)));
+        assert!(request.is_expired(requested_at - Duration::from_secs(6 * 60)));
+    }
+}
@@ -1,5 +1,7 @@
 //! Types for the *m.key.verification.start* event.
 
+use std::fmt::{Debug, Formatter, Result as FmtResult};
+
 use ruma_identifiers::DeviceId;
 use serde::{ser::SerializeStruct, Deserialize, Deserializer, Serialize, Serializer};
 use serde_json::{from_value, Value};
@@ -8,9 +10,12 @@ use super::{
     HashAlgorithm, KeyAgreementProtocol, MessageAuthenticationCode, ShortAuthenticationString,
     VerificationMethod,
 };
-use crate::{EventType, InvalidInput, TryFromRaw};
+use crate::{
+    util::{decode_base64, Redacted},
+    BadEvent, EventType, InvalidInput, TryFromRaw,
+};
 
-/// Begins an SAS key verification process.
+/// Begins a key verification process.
 ///
 /// Typically sent as a to-device event.
 #[derive(Clone, Debug, PartialEq, Serialize)]
@@ -26,6 +31,9 @@ pub enum StartEventContent {
     /// The *m.sas.v1* verification method.
     MSasV1(MSasV1Content),
 
+    /// The *m.reciprocate.v1* verification method.
+    ReciprocateV1(ReciprocateV1Content),
+
     /// Additional variants may be added in the future and will not be considered breaking changes
     /// to ruma-events.
     #[doc(hidden)]
@@ -34,7 +42,7 @@ pub enum StartEventContent {
 
 impl TryFromRaw for StartEvent {
     type Raw = raw::StartEvent;
-    type Err = &'static str;
+    type Err = BadEvent;
 
     fn try_from_raw(raw: raw::StartEvent) -> Result<Self, Self::Err> {
         StartEventContent::try_from_raw(raw.content).map(|content| Self { content })
@@ -47,9 +55,22 @@ impl_event!(
     EventType::KeyVerificationStart
 );
 
+impl StartEventContent {
+    /// An opaque identifier for the verification process, regardless of the verification method.
+    pub fn transaction_id(&self) -> &str {
+        match self {
+            StartEventContent::MSasV1(content) => &content.transaction_id,
+            StartEventContent::ReciprocateV1(content) => &content.transaction_id,
+            StartEventContent::__Nonexhaustive => {
+                panic!("__Nonexhaustive enum variant is not intended for use.")
+            }
+        }
+    }
+}
+
 impl TryFromRaw for StartEventContent {
     type Raw = raw::StartEventContent;
-    type Err = &'static str;
+    type Err = BadEvent;
 
     fn try_from_raw(raw: raw::StartEventContent) -> Result<Self, Self::Err> {
         match raw {
@@ -58,35 +79,55 @@ impl TryFromRaw for StartEventContent {
                     .key_agreement_protocols
                     .contains(&KeyAgreementProtocol::Curve25519)
                 {
-                    return Err(
-                        "`key_agreement_protocols` must contain at least `KeyAgreementProtocol::Curve25519`"
-                    );
+                    return Err(BadEvent::Validation {
+                        field: "key_agreement_protocols",
+                        message: "must contain at least `KeyAgreementProtocol::Curve25519`"
+                            .to_string(),
+                    });
                 }
 
                 if !content.hashes.contains(&HashAlgorithm::Sha256) {
-                    return Err("`hashes` must contain at least `HashAlgorithm::Sha256`");
+                    return Err(BadEvent::Validation {
+                        field: "hashes",
+                        message: "must contain at least `HashAlgorithm::Sha256`".to_string(),
+                    });
                 }
 
                 if !content
                     .message_authentication_codes
                     .contains(&MessageAuthenticationCode::HkdfHmacSha256)
                 {
-                    return Err(
-                        "`message_authentication_codes` must contain at least `MessageAuthenticationCode::HkdfHmacSha256`"
-                    );
+                    return Err(BadEvent::Validation {
+                        field: "message_authentication_codes",
+                        message:
+                            "must contain at least `MessageAuthenticationCode::HkdfHmacSha256`"
+                                .to_string(),
+                    });
                 }
 
                 if !content
                     .short_authentication_string
                     .contains(&ShortAuthenticationString::Decimal)
                 {
-                    return Err(
-                        "`short_authentication_string` must contain at least `ShortAuthenticationString::Decimal`",
-                    );
+                    return Err(BadEvent::Validation {
+                        field: "short_authentication_string",
+                        message: "must contain at least `ShortAuthenticationString::Decimal`"
+                            .to_string(),
+                    });
                 }
 
                 Ok(StartEventContent::MSasV1(content))
             }
+            raw::StartEventContent::ReciprocateV1(content) => {
+                if decode_base64(&content.secret).is_none() {
+                    return Err(BadEvent::Validation {
+                        field: "secret",
+                        message: "must be encoded as unpadded Base64".to_string(),
+                    });
+                }
+
+                Ok(StartEventContent::ReciprocateV1(content))
+            }
             raw::StartEventContent::__Nonexhaustive => {
                 panic!("__Nonexhaustive enum variant is not intended for use.");
             }
@@ -101,6 +142,7 @@ impl Serialize for StartEventContent {
     {
         match *self {
             StartEventContent::MSasV1(ref content) => content.serialize(serializer),
+            StartEventContent::ReciprocateV1(ref content) => content.serialize(serializer),
             _ => panic!("Attempted to serialize __Nonexhaustive variant."),
         }
     }
@@ -109,7 +151,7 @@ impl Serialize for StartEventContent {
 pub(crate) mod raw {
     use super::*;
 
-    /// Begins an SAS key verification process.
+    /// Begins a key verification process.
     ///
     /// Typically sent as a to-device event.
     #[derive(Clone, Debug, Deserialize, PartialEq)]
@@ -124,6 +166,9 @@ pub(crate) mod raw {
         /// The *m.sas.v1* verification method.
         MSasV1(MSasV1Content),
 
+        /// The *m.reciprocate.v1* verification method.
+        ReciprocateV1(ReciprocateV1Content),
+
         /// Additional variants may be added in the future and will not be considered breaking changes
         /// to ruma-events.
         #[doc(hidden)]
@@ -158,6 +203,17 @@ pub(crate) mod raw {
 
                     Ok(StartEventContent::MSasV1(content))
                 }
+                VerificationMethod::ReciprocateV1 => {
+                    let content = match from_value::<ReciprocateV1Content>(value) {
+                        Ok(content) => content,
+                        Err(error) => return Err(D::Error::custom(error.to_string())),
+                    };
+
+                    Ok(StartEventContent::ReciprocateV1(content))
+                }
+                VerificationMethod::QrCodeShowV1 | VerificationMethod::QrCodeScanV1 => Err(
+                    D::Error::custom("the QR code methods can't be used to start a verification"),
+                ),
                 VerificationMethod::__Nonexhaustive => Err(D::Error::custom(
                     "Attempted to deserialize __Nonexhaustive variant.",
                 )),
@@ -244,7 +300,7 @@ impl MSasV1Content {
     /// * `key_agreement_protocols` does not include `KeyAgreementProtocol::Curve25519`.
     /// * `hashes` does not include `HashAlgorithm::Sha256`.
     /// * `message_authentication_codes` does not include
-    /// `MessageAuthenticationCode::HkdfHmacSha256`.
+    ///   `MessageAuthenticationCode::HkdfHmacSha256`.
     /// * `short_authentication_string` does not include `ShortAuthenticationString::Decimal`.
     pub fn new(options: MSasV1ContentOptions) -> Result<Self, InvalidInput> {
         if !options
@@ -310,15 +366,63 @@ impl Serialize for MSasV1Content {
     }
 }
 
+/// The payload of an *m.key.verification.start* event using the *m.reciprocate.v1* method.
+///
+/// This is sent after scanning the QR code of the other device.
+///
+/// The `Debug` output doesn't contain the shared secret.
+#[derive(Clone, PartialEq, Deserialize)]
+pub struct ReciprocateV1Content {
+    /// The device ID which is initiating the process.
+    pub from_device: DeviceId,
+
+    /// An opaque identifier for the verification process.
+    ///
+    /// Must be unique with respect to the devices involved. Must be the same as the
+    /// `transaction_id` given in the *m.key.verification.request* if this process is originating
+    /// from a request.
+    pub transaction_id: String,
+
+    /// The shared secret from the QR code, encoded as unpadded Base64.
+    pub secret: String,
+}
+
+impl Debug for ReciprocateV1Content {
+    fn fmt(&self, f: &mut Formatter<'_>) -> FmtResult {
+        f.debug_struct("ReciprocateV1Content")
+            .field("from_device", &self.from_device)
+            .field("transaction_id", &self.transaction_id)
+            .field("secret", &Redacted)
+            .finish()
+    }
+}
+
+impl Serialize for ReciprocateV1Content {
+    fn serialize<S>(&self, serializer: S) -> Result<S::Ok, S::Error>
+    where
+        S: Serializer,
+    {
+        let mut state = serializer.serialize_struct("ReciprocateV1Content", 4)?;
+
+        state.serialize_field("from_device", &self.from_device)?;
+        state.serialize_field("transaction_id", &self.transaction_id)?;
+        state.serialize_field("method", "m.reciprocate.v1")?;
+        state.serialize_field("secret", &self.secret)?;
+
+        state.end()
+    }
+}
+
 #[cfg(test)]
 mod tests {
     use serde_json::{from_value as from_json_value, json, to_value as to_json_value};
 
     use super::{
         HashAlgorithm, KeyAgreementProtocol, MSasV1Content, MSasV1ContentOptions,
-        MessageAuthenticationCode, ShortAuthenticationString, StartEvent, StartEventContent,
+        MessageAuthenticationCode, ReciprocateV1Content, ShortAuthenticationString, StartEvent,
+        StartEventContent,
     };
-    use crate::EventResult;
+    use crate::{util::try_from_raw_round_trip, EventResult};
 
     #[test]
     fn invalid_m_sas_v1_content_missing_required_key_agreement_protocols() {
@@ -600,4 +704,93 @@ mod tests {
         assert!(error.message().contains("key_agreement_protocols"));
         assert!(error.is_validation());
     }
+
+    #[test]
+    fn m_sas_v1_round_trip() {
+        try_from_raw_round_trip::<StartEventContent>(json!({
+            "from_device": "123",
+            "transaction_id": "456",
+            "method": "m.sas.v1",
+            "hashes": ["sha256"],
+            "key_agreement_protocols": ["curve25519"],
+            "message_authentication_codes": ["hkdf-hmac-sha256"],
+            "short_authentication_string": ["decimal", "emoji"]
+        }));
+    }
+
+    #[test]
+    fn reciprocate_v1_round_trip() {
+        let json_data = json!({
+            "from_device": "123",
+            "transaction_id": "456",
+            "method": "m.reciprocate.v1",
+            "secret": "c2hhcmVkIHNlY3JldA"
+        });
+
+        assert_eq!(
+            from_json_value::<EventResult<StartEventContent>>(json_data.clone())
+                .unwrap()
+                .into_result()
+                .unwrap(),
+            StartEventContent::ReciprocateV1(ReciprocateV1Content {
+                from_device: "123".to_string(),
+                transaction_id: "456".to_string(),
+                secret: "c2hhcmVkIHNlY3JldA".to_string(),
+            })
+        );
+
+        try_from_raw_round_trip::<StartEventContent>(json_data);
+    }
+
+    #[test]
+    fn deserialization_validation_reciprocate_v1_invalid_secret() {
+        let json_data = json!({
+            "from_device": "123",
+            "transaction_id": "456",
+            "method": "m.reciprocate.v1",
+            "secret": "not base64!"
+        });
+
+        let error = from_json_value::<EventResult<StartEventContent>>(json_data)
+            .unwrap()
+            .into_result()
+            .unwrap_err();
+
+        assert!(error.is_validation());
+        assert_eq!(
+            error.message(),
+            "`secret` must be encoded as unpadded Base64"
+        );
+    }
+
+    #[test]
+    fn qr_code_method_cannot_start() {
+        let json_data = json!({
+            "from_device": "123",
+            "transaction_id": "456",
+            "method": "m.qr_code.show.v1"
+        });
+
+        let error = from_json_value::<EventResult<StartEventContent>>(json_data)
+            .unwrap()
+            .into_result()
+            .unwrap_err();
+
+        assert!(error
+            .message()
+            .contains("the QR code methods can't be used to start a verification"));
+    }
+
+    #[test]
+    fn debug_hides_reciprocate_v1_secret() {
+        let content = ReciprocateV1Content {
+            from_device: "123".to_string(),
+            transaction_id: "456".to_string(),
+            secret: "c2hhcmVkIHNlY3JldA".to_string(),
+        };
+        let debug = format!("{:?}", content);
+
+        assert!(debug.contains("456"));
+        assert!(!debug.contains("c2hhcmVkIHNlY3JldA"));
+    }
 }
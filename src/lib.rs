@@ -36,7 +36,7 @@
 //!     *   `room_id`, which is a unique identifier for the room in which the event occurred
 //!     *   `sender`, which is the unique identifier of the Matrix user who created the event
 //!     *   Optionally, `unsigned`, which is a JSON object containing arbitrary additional metadata
-//!     that is not digitally signed by Matrix homeservers.
+//!         that is not digitally signed by Matrix homeservers.
 //! 3.  **State events**, which are a superset of room events and represent persistent state
 //!     specific to a room, such as the room's member list or topic.
 //!     Within a single room, state events of the same type and with the same "state key" will
@@ -47,7 +47,7 @@
 //!         You can think of a room's state events as being a `HashMap` where the keys are the tuple
 //!         `(event_type, state_key)`.
 //!     *   Optionally, `prev_content`, a JSON object containing the `content` object from the
-//!     previous event of the given `(event_type, state_key)` tuple in the given room.
+//!         previous event of the given `(event_type, state_key)` tuple in the given room.
 //!
 //! ruma-events represents these three event kinds as traits, allowing any Rust type to serve as a
 //! Matrix event so long as it upholds the contract expected of its kind.
@@ -112,33 +112,85 @@
 #![recursion_limit = "1024"]
 #![warn(rust_2018_idioms)]
 #![deny(missing_debug_implementations, missing_docs)]
-// Since we support Rust 1.36.0, we can't apply this suggestion yet
-#![allow(clippy::use_self)]
+// Since we support Rust 1.36.0, we can't apply these suggestions yet
+#![allow(
+    clippy::use_self,
+    clippy::manual_non_exhaustive,
+    clippy::match_like_matches_macro
+)]
+// The tests are written in the idioms of Rust 1.36.0 as well
+#![cfg_attr(
+    test,
+    allow(
+        clippy::bool_assert_comparison,
+        clippy::manual_repeat_n,
+        clippy::needless_borrows_for_generic_args,
+        clippy::useless_vec,
+        unknown_lints
+    )
+)]
 
 use std::{
+    convert::Infallible,
     error::Error,
     fmt::{Debug, Display, Formatter, Result as FmtResult},
+    time::{Duration, SystemTime, UNIX_EPOCH},
 };
 
 use js_int::UInt;
-use ruma_identifiers::{EventId, RoomId, UserId};
 use serde::{
-    de::{MapAccess, Visitor},
+    de::{DeserializeOwned, MapAccess, Visitor},
     ser::SerializeMap,
     Deserialize, Deserializer, Serialize, Serializer,
 };
-use serde_json::{Map, Value};
+use serde_json::{value::RawValue, Map, Value};
 
 pub use self::{custom::CustomEvent, custom_room::CustomRoomEvent, custom_state::CustomStateEvent};
 
 #[macro_use]
 mod macros;
-mod algorithm;
+mod deserializer;
+mod event_encryption_algorithm;
 mod event_type;
 mod from_raw;
+mod strict;
+mod unstable;
 #[doc(hidden)] // only public for external tests
 pub mod util;
 
+/// Serde helpers used by the code generated by `ruma_event!`. Not part of the public API.
+#[doc(hidden)]
+pub mod __private {
+    use js_int::UInt;
+    use serde::{Deserialize, Deserializer};
+    use serde_json::Value;
+
+    /// Serde deserialization decorator for timestamps that accepts a string of digits in addition
+    /// to a number.
+    ///
+    /// Some servers and bridges are known to send `origin_server_ts` as a string. Serialization is
+    /// not affected, timestamps are always serialized as numbers.
+    ///
+    /// To be used like this:
+    /// `#[serde(deserialize_with = "crate::__private::uint_or_string")]`
+    pub fn uint_or_string<'de, D>(de: D) -> Result<UInt, D::Error>
+    where
+        D: Deserializer<'de>,
+    {
+        use serde::de::{Error as _, Unexpected};
+
+        match Value::deserialize(de)? {
+            Value::String(s) => s.parse().map_err(|_| {
+                D::Error::invalid_value(
+                    Unexpected::Str(&s),
+                    &"a non-negative integer or a string of one",
+                )
+            }),
+            value => serde_json::from_value(value).map_err(D::Error::custom),
+        }
+    }
+}
+
 // Hack to allow both ruma-events itself and external crates (or tests) to use procedural macros
 // that expect `ruma_events` to exist in the prelude.
 extern crate self as ruma_events;
@@ -147,7 +199,9 @@ pub mod call;
 /// Enums for heterogeneous collections of events.
 pub mod collections {
     pub mod all;
+    pub mod initial_state;
     pub mod only;
+    pub mod room_name;
 
     mod raw {
         pub mod all;
@@ -160,22 +214,29 @@ pub mod forwarded_room_key;
 pub mod fully_read;
 pub mod ignored_user_list;
 pub mod key;
+pub mod policy;
 pub mod presence;
 pub mod push_rules;
 pub mod receipt;
+pub mod relations;
 pub mod room;
 pub mod room_key;
 pub mod room_key_request;
+pub mod space;
 pub mod sticker;
 pub mod stripped;
 pub mod tag;
 pub mod to_device;
 pub mod typing;
 
+pub use ruma_identifiers::{EventId, RoomId, UserId};
+
 pub use self::{
-    algorithm::Algorithm,
+    deserializer::EventDeserializer,
+    event_encryption_algorithm::EventEncryptionAlgorithm,
     event_type::EventType,
     from_raw::{FromRaw, TryFromRaw},
+    strict::StrictEvent,
 };
 
 /// An event that is malformed or otherwise invalid.
@@ -243,6 +304,73 @@ impl Display for InvalidInput {
 
 impl Error for InvalidInput {}
 
+/// An error encountered when converting a raw event or event content into its validated form.
+///
+/// Unlike [`InvalidEvent`](struct.InvalidEvent.html), this type can be matched on to find out what
+/// exactly was wrong with the event.
+#[derive(Clone, Debug, PartialEq)]
+pub enum BadEvent {
+    /// The JSON structure of the event didn't match its event type.
+    Deserialization(String),
+
+    /// A field of the event violated a constraint defined in the Matrix specification.
+    Validation {
+        /// The name of the offending field.
+        field: &'static str,
+
+        /// A message describing the violated constraint.
+        message: String,
+    },
+
+    /// The event type is not known or not valid in this context.
+    UnknownEventType(String),
+
+    /// The event contained fields that aren't part of its type, found when parsing it with
+    /// [`StrictEvent`](struct.StrictEvent.html).
+    ///
+    /// The fields are given as paths of keys separated by dots, such as `content.foo`.
+    UnknownFields(Vec<String>),
+
+    /// Additional variants may be added in the future and will not be considered breaking changes
+    /// to ruma-events.
+    #[doc(hidden)]
+    __Nonexhaustive,
+}
+
+impl Display for BadEvent {
+    fn fmt(&self, f: &mut Formatter<'_>) -> FmtResult {
+        match self {
+            BadEvent::Deserialization(message) => write!(f, "{}", message),
+            BadEvent::Validation { field, message } => write!(f, "`{}` {}", field, message),
+            BadEvent::UnknownEventType(event_type) => {
+                write!(f, "unknown event type `{}`", event_type)
+            }
+            BadEvent::UnknownFields(fields) => {
+                let fields: Vec<String> =
+                    fields.iter().map(|field| format!("`{}`", field)).collect();
+                write!(f, "unknown fields: {}", fields.join(", "))
+            }
+            BadEvent::__Nonexhaustive => {
+                panic!("__Nonexhaustive enum variant is not intended for use.")
+            }
+        }
+    }
+}
+
+impl Error for BadEvent {}
+
+impl From<Infallible> for BadEvent {
+    fn from(never: Infallible) -> Self {
+        match never {}
+    }
+}
+
+impl From<serde_json::Error> for BadEvent {
+    fn from(error: serde_json::Error) -> Self {
+        BadEvent::Deserialization(error.to_string())
+    }
+}
+
 /// The result of deserializing an event, which may or may not be valid.
 ///
 /// When data is successfully deserialized and validated, this structure will contain the
@@ -261,6 +389,25 @@ pub enum EventResult<T: TryFromRaw> {
 }
 
 impl<T: TryFromRaw> EventResult<T> {
+    /// Deserializes and validates a `T` from a string of JSON.
+    ///
+    /// Unlike deserializing an `EventResult<T>` through `serde_json::from_str`, this never fails:
+    /// input that isn't valid JSON results in an `InvalidEvent` with a `json` of
+    /// `Value::Null`.
+    ///
+    /// Most event types also implement `FromStr` in terms of this function, so they can be parsed
+    /// with `str::parse`.
+    pub fn from_json_str(json: &str) -> Self {
+        match serde_json::from_str(json) {
+            Ok(event_result) => event_result,
+            Err(error) => EventResult::Err(InvalidEvent {
+                message: error.to_string(),
+                json: Value::Null,
+                kind: InvalidEventKind::Deserialization,
+            }),
+        }
+    }
+
     /// Convert `EventResult<T>` into the equivalent `std::result::Result<T, InvalidEvent>`.
     pub fn into_result(self) -> Result<T, InvalidEvent> {
         match self {
@@ -278,13 +425,16 @@ where
     where
         D: Deserializer<'de>,
     {
-        let json = serde_json::Value::deserialize(deserializer)?;
+        // The event is only buffered as raw JSON, and is only parsed into a `Value` for the
+        // `InvalidEvent` if it turns out to be invalid.
+        let json = Box::<RawValue>::deserialize(deserializer)?;
+        let json_value = || serde_json::from_str(json.get()).unwrap_or(Value::Null);
 
-        let raw_data: T::Raw = match serde_json::from_value(json.clone()) {
+        let raw_data: T::Raw = match serde_json::from_str(json.get()) {
             Ok(raw) => raw,
             Err(error) => {
                 return Ok(EventResult::Err(InvalidEvent {
-                    json,
+                    json: json_value(),
                     message: error.to_string(),
                     kind: InvalidEventKind::Deserialization,
                 }));
@@ -295,7 +445,7 @@ where
             Ok(value) => Ok(EventResult::Ok(value)),
             Err(err) => Ok(EventResult::Err(InvalidEvent {
                 message: err.to_string(),
-                json,
+                json: json_value(),
                 kind: InvalidEventKind::Validation,
             })),
         }
@@ -396,6 +546,43 @@ pub trait RoomEvent: Event {
 
     /// Additional key-value pairs not signed by the homeserver.
     fn unsigned(&self) -> &Map<String, Value>;
+
+    /// The time elapsed between `origin_server_ts` and `now`.
+    ///
+    /// Unlike `unsigned.age`, which is computed by the homeserver when the event is sent to the
+    /// client, this stays accurate for events that have been stored. Events with a timestamp after
+    /// `now`, e.g. due to clock skew, have an age of zero, as do events whose `origin_server_ts` is
+    /// too far in the future to be represented as a `SystemTime` on this platform.
+    fn age(&self, now: SystemTime) -> Duration {
+        UNIX_EPOCH
+            .checked_add(Duration::from_millis(self.origin_server_ts().into()))
+            .and_then(|sent| now.duration_since(sent).ok())
+            .unwrap_or_else(|| Duration::from_secs(0))
+    }
+
+    /// The relations of this event that the homeserver aggregated into `unsigned`.
+    ///
+    /// Returns `None` if there are none or if they are invalid.
+    fn bundled_relations(&self) -> Option<relations::BundledRelations> {
+        serde_json::from_value(self.unsigned().get("m.relations")?.clone()).ok()
+    }
+
+    /// Whether this event has been redacted, according to `unsigned`.
+    fn is_redacted(&self) -> bool {
+        self.unsigned().contains_key("redacted_because")
+    }
+
+    /// The event that redacted this event, from `unsigned`.
+    ///
+    /// Returns `None` if this event hasn't been redacted or if the redaction event is invalid.
+    fn redacted_because(&self) -> Option<room::redaction::RedactionEvent> {
+        let redaction = self.unsigned().get("redacted_because")?.clone();
+
+        serde_json::from_value::<EventResult<_>>(redaction)
+            .ok()?
+            .into_result()
+            .ok()
+    }
 }
 
 /// An event that describes persistent state about a room.
@@ -407,9 +594,44 @@ pub trait StateEvent: RoomEvent {
     fn state_key(&self) -> &str;
 }
 
+/// An object-safe view of an event.
+///
+/// `Event` and its subtraits have associated types and require `Sized`, so they can't be used as
+/// trait objects. This trait exposes the parts of an event that don't depend on its concrete type,
+/// which allows handling events of different types uniformly, e.g. through a
+/// `Vec<Box<dyn DynEvent>>`. It is implemented for every event type in this crate.
+///
+/// If both this trait and `Event` are in scope, calling `event_type` on a concrete event type is
+/// ambiguous; use `Event::event_type(&event)` or `DynEvent::event_type(&event)` in that case.
+pub trait DynEvent: Debug {
+    /// The type of the event.
+    fn event_type(&self) -> EventType;
+
+    /// The unique identifier for the user who sent this event.
+    ///
+    /// This is `None` for events that don't have a sender, like basic events.
+    fn sender(&self) -> Option<&UserId>;
+
+    /// The event's content, serialized to JSON.
+    fn content_json(&self) -> Result<Value, serde_json::Error>;
+}
+
+/// The content of an event type defined outside of this crate, e.g. by a bridge or bot.
+///
+/// Events of types that aren't known to this crate are deserialized into the custom variants of
+/// the collection enums, which retain their content as JSON. Implementing this trait allows
+/// parsing that content into a dedicated type with
+/// [`collections::all::Event::deserialize_custom`](collections/all/enum.Event.html#method.deserialize_custom).
+pub trait CustomEventContent: DeserializeOwned {
+    /// The type of the events with this content, e.g. `com.example.poll`.
+    const EVENT_TYPE: &'static str;
+}
+
 /// A basic custom event outside of the Matrix specification.
 mod custom {
-    use super::{Event, EventType};
+    use std::collections::BTreeMap;
+
+    use super::{DynEvent, Event, EventType};
 
     use ruma_events_macros::FromRaw;
     use serde::{Deserialize, Serialize};
@@ -423,11 +645,31 @@ mod custom {
         /// The custom type of the event.
         #[serde(rename = "type")]
         pub event_type: String,
+        /// Top-level fields of the event that are not covered by the fields above, which are kept
+        /// so that re-serializing the event doesn't lose them.
+        #[serde(flatten)]
+        pub extra: BTreeMap<String, Value>,
     }
 
     /// The payload for `CustomEvent`.
     pub type CustomEventContent = Value;
 
+    impl_from_str!(CustomEvent);
+
+    impl DynEvent for CustomEvent {
+        fn event_type(&self) -> EventType {
+            EventType::Custom(self.event_type.clone())
+        }
+
+        fn sender(&self) -> Option<&ruma_identifiers::UserId> {
+            None
+        }
+
+        fn content_json(&self) -> Result<Value, serde_json::Error> {
+            Ok(self.content.clone())
+        }
+    }
+
     impl Event for CustomEvent {
         /// The type of this event's `content` field.
         type Content = CustomEventContent;
@@ -453,12 +695,18 @@ mod custom {
             /// The custom type of the event.
             #[serde(rename = "type")]
             pub event_type: String,
+            /// Top-level fields of the event that are not covered by the fields above, which are kept
+            /// so that re-serializing the event doesn't lose them.
+            #[serde(flatten)]
+            pub extra: BTreeMap<String, Value>,
         }
     }
 }
 
 mod custom_room {
-    use super::{Event, EventType, RoomEvent};
+    use std::collections::BTreeMap;
+
+    use super::{DynEvent, Event, EventType, RoomEvent};
 
     use ruma_events_macros::FromRaw;
     use serde::{Deserialize, Serialize};
@@ -478,17 +726,38 @@ mod custom_room {
         /// event was sent.
         pub origin_server_ts: js_int::UInt,
         /// The unique identifier for the room associated with this event.
+        #[serde(skip_serializing_if = "Option::is_none")]
         pub room_id: Option<ruma_identifiers::RoomId>,
         /// The unique identifier for the user who sent this event.
         pub sender: ruma_identifiers::UserId,
         /// Additional key-value pairs not signed by the homeserver.
         #[serde(skip_serializing_if = "serde_json::Map::is_empty")]
         pub unsigned: Map<String, Value>,
+        /// Top-level fields of the event that are not covered by the fields above, which are kept
+        /// so that re-serializing the event doesn't lose them.
+        #[serde(flatten)]
+        pub extra: BTreeMap<String, Value>,
     }
 
     /// The payload for `CustomRoomEvent`.
     pub type CustomRoomEventContent = Value;
 
+    impl_from_str!(CustomRoomEvent);
+
+    impl DynEvent for CustomRoomEvent {
+        fn event_type(&self) -> EventType {
+            EventType::Custom(self.event_type.clone())
+        }
+
+        fn sender(&self) -> Option<&ruma_identifiers::UserId> {
+            Some(&self.sender)
+        }
+
+        fn content_json(&self) -> Result<Value, serde_json::Error> {
+            Ok(self.content.clone())
+        }
+    }
+
     impl Event for CustomRoomEvent {
         /// The type of this event's `content` field.
         type Content = CustomRoomEventContent;
@@ -544,6 +813,7 @@ mod custom_room {
             pub event_type: String,
             /// Timestamp (milliseconds since the UNIX epoch) on originating homeserver when this
             /// event was sent.
+            #[serde(deserialize_with = "crate::__private::uint_or_string")]
             pub origin_server_ts: js_int::UInt,
             /// The unique identifier for the room associated with this event.
             pub room_id: Option<ruma_identifiers::RoomId>,
@@ -552,12 +822,18 @@ mod custom_room {
             /// Additional key-value pairs not signed by the homeserver.
             #[serde(default)]
             pub unsigned: Map<String, Value>,
+            /// Top-level fields of the event that are not covered by the fields above, which are kept
+            /// so that re-serializing the event doesn't lose them.
+            #[serde(flatten)]
+            pub extra: BTreeMap<String, Value>,
         }
     }
 }
 
 mod custom_state {
-    use super::{Event, EventType, RoomEvent, StateEvent};
+    use std::collections::BTreeMap;
+
+    use super::{DynEvent, Event, EventType, RoomEvent, StateEvent};
 
     use ruma_events_macros::FromRaw;
     use serde::{Deserialize, Serialize};
@@ -577,8 +853,10 @@ mod custom_state {
         /// event was sent.
         pub origin_server_ts: js_int::UInt,
         /// The previous content for this state key, if any.
+        #[serde(skip_serializing_if = "Option::is_none")]
         pub prev_content: Option<CustomStateEventContent>,
         /// The unique identifier for the room associated with this event.
+        #[serde(skip_serializing_if = "Option::is_none")]
         pub room_id: Option<ruma_identifiers::RoomId>,
         /// The unique identifier for the user who sent this event.
         pub sender: ruma_identifiers::UserId,
@@ -587,11 +865,31 @@ mod custom_state {
         /// Additional key-value pairs not signed by the homeserver.
         #[serde(skip_serializing_if = "serde_json::Map::is_empty")]
         pub unsigned: Map<String, Value>,
+        /// Top-level fields of the event that are not covered by the fields above, which are kept
+        /// so that re-serializing the event doesn't lose them.
+        #[serde(flatten)]
+        pub extra: BTreeMap<String, Value>,
     }
 
     /// The payload for `CustomStateEvent`.
     pub type CustomStateEventContent = Value;
 
+    impl_from_str!(CustomStateEvent);
+
+    impl DynEvent for CustomStateEvent {
+        fn event_type(&self) -> EventType {
+            EventType::Custom(self.event_type.clone())
+        }
+
+        fn sender(&self) -> Option<&ruma_identifiers::UserId> {
+            Some(&self.sender)
+        }
+
+        fn content_json(&self) -> Result<Value, serde_json::Error> {
+            Ok(self.content.clone())
+        }
+    }
+
     impl Event for CustomStateEvent {
         /// The type of this event's `content` field.
         type Content = CustomStateEventContent;
@@ -658,6 +956,7 @@ mod custom_state {
             pub event_type: String,
             /// Timestamp (milliseconds since the UNIX epoch) on originating homeserver when this
             /// event was sent.
+            #[serde(deserialize_with = "crate::__private::uint_or_string")]
             pub origin_server_ts: js_int::UInt,
             /// The previous content for this state key, if any.
             pub prev_content: Option<CustomStateEventContent>,
@@ -670,6 +969,288 @@ mod custom_state {
             /// Additional key-value pairs not signed by the homeserver.
             #[serde(default)]
             pub unsigned: Map<String, Value>,
+            /// Top-level fields of the event that are not covered by the fields above, which are kept
+            /// so that re-serializing the event doesn't lose them.
+            #[serde(flatten)]
+            pub extra: BTreeMap<String, Value>,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::{
+        convert::TryFrom,
+        time::{Duration, UNIX_EPOCH},
+    };
+
+    use js_int::UInt;
+    use serde_json::{from_value as from_json_value, json, to_value as to_json_value, Map};
+
+    use super::{DynEvent, EventId, EventResult, EventType, RoomEvent, RoomId, UserId};
+    use crate::{
+        collections::all::Event,
+        room::{
+            member::{MemberEvent, MemberEventContent, MembershipState},
+            message::{MessageEvent, MessageEventContent, TextMessageEventContent},
+            topic::TopicEvent,
+        },
+        typing::{TypingEvent, TypingEventContent},
+    };
+
+    fn topic_event_json(room_id: &str) -> serde_json::Value {
+        json!({
+            "content": {
+                "topic": "Testing room"
+            },
+            "event_id": "$h29iv0s8:example.com",
+            "origin_server_ts": 1,
+            "room_id": room_id,
+            "sender": "@carl:example.com",
+            "state_key": "",
+            "type": "m.room.topic"
+        })
+    }
+
+    #[test]
+    fn envelope_with_valid_identifiers() {
+        let event =
+            from_json_value::<EventResult<TopicEvent>>(topic_event_json("!n8f893n9:example.com"))
+                .unwrap()
+                .into_result()
+                .unwrap();
+
+        assert_eq!(
+            event.event_id,
+            EventId::try_from("$h29iv0s8:example.com").unwrap()
+        );
+        assert_eq!(
+            event.room_id,
+            Some(RoomId::try_from("!n8f893n9:example.com").unwrap())
+        );
+        assert_eq!(event.sender, UserId::try_from("@carl:example.com").unwrap());
+    }
+
+    #[test]
+    fn envelope_with_malformed_room_id() {
+        assert!(
+            from_json_value::<EventResult<TopicEvent>>(topic_event_json("n8f893n9"))
+                .unwrap()
+                .into_result()
+                .unwrap_err()
+                .is_deserialization()
+        );
+    }
+
+    #[test]
+    fn dyn_event_trait_objects() {
+        let sender = UserId::try_from("@carl:example.com").unwrap();
+        let events: Vec<Box<dyn DynEvent>> = vec![
+            Box::new(MessageEvent {
+                content: MessageEventContent::Text(TextMessageEventContent::new_plain("Hello")),
+                event_id: EventId::try_from("$h29iv0s8:example.com").unwrap(),
+                origin_server_ts: UInt::new(1).unwrap(),
+                room_id: Some(RoomId::try_from("!n8f893n9:example.com").unwrap()),
+                sender: sender.clone(),
+                unsigned: Map::new(),
+            }),
+            Box::new(MemberEvent {
+                content: MemberEventContent {
+                    avatar_url: None,
+                    displayname: None,
+                    is_direct: None,
+                    membership: MembershipState::Join,
+                    join_authorized_via_users_server: None,
+                    reason: None,
+                    third_party_invite: None,
+                },
+                event_id: EventId::try_from("$h29iv0s9:example.com").unwrap(),
+                origin_server_ts: UInt::new(2).unwrap(),
+                prev_content: None,
+                room_id: Some(RoomId::try_from("!n8f893n9:example.com").unwrap()),
+                sender: sender.clone(),
+                state_key: "@carl:example.com".to_string(),
+                unsigned: Map::new(),
+            }),
+            Box::new(TypingEvent {
+                content: TypingEventContent {
+                    user_ids: vec![sender.clone()],
+                },
+                room_id: None,
+            }),
+        ];
+
+        let event_types: Vec<EventType> = events.iter().map(|event| event.event_type()).collect();
+        assert_eq!(
+            event_types,
+            vec![
+                EventType::RoomMessage,
+                EventType::RoomMember,
+                EventType::Typing
+            ]
+        );
+
+        assert_eq!(events[0].sender(), Some(&sender));
+        assert_eq!(events[1].sender(), Some(&sender));
+        assert_eq!(events[2].sender(), None);
+
+        assert_eq!(
+            events[1].content_json().unwrap(),
+            json!({ "membership": "join" })
+        );
+    }
+
+    #[test]
+    fn redacted_because() {
+        let mut json = json!({
+            "content": { "body": "Hello", "msgtype": "m.text" },
+            "event_id": "$h29iv0s8:example.com",
+            "origin_server_ts": 1,
+            "sender": "@carl:example.com",
+            "type": "m.room.message"
+        });
+
+        let event = from_json_value::<EventResult<MessageEvent>>(json.clone())
+            .unwrap()
+            .into_result()
+            .unwrap();
+        assert!(!event.is_redacted());
+        assert!(event.redacted_because().is_none());
+
+        json["unsigned"] = json!({
+            "redacted_because": {
+                "content": { "reason": "Spam" },
+                "event_id": "$redaction:example.com",
+                "origin_server_ts": 2,
+                "redacts": "$h29iv0s8:example.com",
+                "sender": "@admin:example.com",
+                "type": "m.room.redaction"
+            }
+        });
+        let event = from_json_value::<EventResult<MessageEvent>>(json)
+            .unwrap()
+            .into_result()
+            .unwrap();
+        assert!(event.is_redacted());
+
+        let redaction = event.redacted_because().unwrap();
+        assert_eq!(
+            redaction.redacts(),
+            &[EventId::try_from("$h29iv0s8:example.com").unwrap()]
+        );
+        assert_eq!(redaction.content.reason.unwrap(), "Spam");
+    }
+
+    #[test]
+    fn age() {
+        let event =
+            from_json_value::<EventResult<TopicEvent>>(topic_event_json("!n8f893n9:example.com"))
+                .unwrap()
+                .into_result()
+                .unwrap();
+        let sent = UNIX_EPOCH + Duration::from_millis(1);
+
+        assert_eq!(
+            event.age(sent + Duration::from_secs(60)),
+            Duration::from_secs(60)
+        );
+        assert_eq!(event.age(sent), Duration::from_secs(0));
+        assert_eq!(event.age(UNIX_EPOCH), Duration::from_secs(0));
+
+        let mut json = topic_event_json("!n8f893n9:example.com");
+        json["origin_server_ts"] = json!(9_007_199_254_740_991u64);
+        let future_event = from_json_value::<EventResult<TopicEvent>>(json)
+            .unwrap()
+            .into_result()
+            .unwrap();
+
+        assert_eq!(
+            future_event.age(sent + Duration::from_secs(60)),
+            Duration::from_secs(0)
+        );
+    }
+
+    #[test]
+    fn origin_server_ts_as_number() {
+        let event =
+            from_json_value::<EventResult<TopicEvent>>(topic_event_json("!n8f893n9:example.com"))
+                .unwrap()
+                .into_result()
+                .unwrap();
+
+        assert_eq!(event.origin_server_ts, UInt::from(1u32));
+    }
+
+    #[test]
+    fn origin_server_ts_as_string() {
+        let mut json = topic_event_json("!n8f893n9:example.com");
+        json["origin_server_ts"] = json!("1432735824653");
+
+        let event = from_json_value::<EventResult<TopicEvent>>(json)
+            .unwrap()
+            .into_result()
+            .unwrap();
+
+        assert_eq!(
+            event.origin_server_ts,
+            UInt::new(1_432_735_824_653).unwrap()
+        );
+        assert_eq!(
+            serde_json::to_value(&event).unwrap()["origin_server_ts"],
+            json!(1_432_735_824_653u64)
+        );
+
+        let message = from_json_value::<EventResult<MessageEvent>>(json!({
+            "content": { "body": "Hello", "msgtype": "m.text" },
+            "event_id": "$h29iv0s8:example.com",
+            "origin_server_ts": "1",
+            "sender": "@carl:example.com",
+            "type": "m.room.message"
+        }))
+        .unwrap()
+        .into_result()
+        .unwrap();
+
+        assert_eq!(message.origin_server_ts, UInt::from(1u32));
+    }
+
+    #[test]
+    fn origin_server_ts_as_non_numeric_string() {
+        let mut json = topic_event_json("!n8f893n9:example.com");
+        json["origin_server_ts"] = json!("yesterday");
+
+        let error = from_json_value::<EventResult<TopicEvent>>(json)
+            .unwrap()
+            .into_result()
+            .unwrap_err();
+
+        assert!(error.is_deserialization());
+    }
+
+    #[test]
+    fn custom_event_extra_fields() {
+        let json = json!({
+            "content": { "answer": 42 },
+            "event_id": "$h29iv0s8:example.com",
+            "origin_server_ts": 1,
+            "sender": "@carl:example.com",
+            "state_key": "",
+            "type": "com.example.question",
+            "com.example.origin": { "bridge": "irc" }
+        });
+
+        let event = from_json_value::<EventResult<Event>>(json.clone())
+            .unwrap()
+            .into_result()
+            .unwrap();
+
+        match &event {
+            Event::CustomState(event) => assert_eq!(
+                event.extra.get("com.example.origin"),
+                Some(&json!({ "bridge": "irc" }))
+            ),
+            other => panic!("expected a custom state event, found {:?}", other),
         }
+        assert_eq!(to_json_value(&event).unwrap(), json);
     }
 }
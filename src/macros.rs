@@ -24,8 +24,27 @@ macro_rules! impl_enum {
     }
 }
 
+macro_rules! impl_from_str {
+    ($name:ty) => {
+        impl ::std::str::FromStr for $name {
+            type Err = crate::InvalidEvent;
+
+            /// Deserializes and validates the event from a string of JSON.
+            fn from_str(s: &str) -> Result<Self, Self::Err> {
+                crate::EventResult::from_json_str(s).into_result()
+            }
+        }
+    };
+}
+
 macro_rules! impl_event {
     ($name:ident, $content_name:ident, $event_type:path) => {
+        impl_event!(@event $name, $content_name, $event_type);
+        impl_dyn_event!($name, $event_type, |_event| None);
+    };
+    (@event $name:ident, $content_name:ident, $event_type:path) => {
+        impl_from_str!($name);
+
         impl crate::Event for $name {
             /// The type of this event's `content` field.
             type Content = $content_name;
@@ -43,9 +62,29 @@ macro_rules! impl_event {
     };
 }
 
+macro_rules! impl_dyn_event {
+    ($name:ident, $event_type:path, $sender:expr) => {
+        impl crate::DynEvent for $name {
+            fn event_type(&self) -> crate::EventType {
+                $event_type
+            }
+
+            fn sender(&self) -> Option<&ruma_identifiers::UserId> {
+                let sender: fn(&$name) -> Option<&ruma_identifiers::UserId> = $sender;
+                sender(self)
+            }
+
+            fn content_json(&self) -> Result<serde_json::Value, serde_json::Error> {
+                serde_json::to_value(&self.content)
+            }
+        }
+    };
+}
+
 macro_rules! impl_room_event {
     ($name:ident, $content_name:ident, $event_type:path) => {
-        impl_event!($name, $content_name, $event_type);
+        impl_event!(@event $name, $content_name, $event_type);
+        impl_dyn_event!($name, $event_type, |event| Some(&event.sender));
 
         impl crate::RoomEvent for $name {
             /// The unique identifier for the event.
@@ -0,0 +1,242 @@
+//! Modules for events in the *m.policy.rule* namespace.
+//!
+//! This module also contains types shared by events in its child namespaces.
+
+use std::{
+    borrow::Cow,
+    fmt::{Display, Formatter, Result as FmtResult},
+};
+
+use serde::{de::Error as _, ser::SerializeMap, Deserialize, Deserializer, Serialize, Serializer};
+
+use crate::{util::glob_matches, FromRaw};
+
+pub mod room;
+pub mod server;
+pub mod user;
+
+/// The payload shared by all *m.policy.rule* events.
+///
+/// A rule is removed by sending an event with the same state key and empty content, which is
+/// represented by `Removed`.
+#[derive(Clone, Debug, PartialEq)]
+pub enum PolicyRuleEventContent {
+    /// An active rule.
+    Rule(PolicyRule),
+
+    /// A rule that was removed.
+    Removed,
+}
+
+/// An active moderation policy rule.
+#[derive(Clone, Debug, Deserialize, PartialEq, Serialize)]
+pub struct PolicyRule {
+    /// The entity affected by this rule.
+    ///
+    /// Glob characters `*` and `?` can be used to match zero or more characters and exactly one
+    /// character respectively.
+    pub entity: String,
+
+    /// The suggested action to take.
+    pub recommendation: Recommendation,
+
+    /// The human-readable description for the recommendation.
+    pub reason: String,
+}
+
+impl PolicyRuleEventContent {
+    /// The rule, unless it was removed.
+    pub fn rule(&self) -> Option<&PolicyRule> {
+        match self {
+            PolicyRuleEventContent::Rule(rule) => Some(rule),
+            PolicyRuleEventContent::Removed => None,
+        }
+    }
+
+    /// Whether `entity` is affected by this rule, taking glob characters into account.
+    ///
+    /// A removed rule doesn't affect any entity.
+    pub fn matches(&self, entity: &str) -> bool {
+        match self.rule() {
+            Some(rule) => rule.matches(entity),
+            None => false,
+        }
+    }
+}
+
+impl PolicyRule {
+    /// Whether `entity` is affected by this rule, taking glob characters into account.
+    pub fn matches(&self, entity: &str) -> bool {
+        glob_matches(&self.entity, entity)
+    }
+}
+
+impl FromRaw for PolicyRuleEventContent {
+    type Raw = Self;
+
+    fn from_raw(raw: Self) -> Self {
+        raw
+    }
+}
+
+impl Serialize for PolicyRuleEventContent {
+    fn serialize<S>(&self, serializer: S) -> Result<S::Ok, S::Error>
+    where
+        S: Serializer,
+    {
+        match self {
+            PolicyRuleEventContent::Rule(rule) => rule.serialize(serializer),
+            PolicyRuleEventContent::Removed => serializer.serialize_map(Some(0))?.end(),
+        }
+    }
+}
+
+impl<'de> Deserialize<'de> for PolicyRuleEventContent {
+    fn deserialize<D>(deserializer: D) -> Result<Self, D::Error>
+    where
+        D: Deserializer<'de>,
+    {
+        /// The fields of both an active and a removed rule.
+        #[derive(Deserialize)]
+        struct RawPolicyRule {
+            entity: Option<String>,
+            recommendation: Option<Recommendation>,
+            reason: Option<String>,
+        }
+
+        let raw = RawPolicyRule::deserialize(deserializer)?;
+
+        match (raw.entity, raw.recommendation, raw.reason) {
+            (None, None, None) => Ok(PolicyRuleEventContent::Removed),
+            (Some(entity), Some(recommendation), Some(reason)) => {
+                Ok(PolicyRuleEventContent::Rule(PolicyRule {
+                    entity,
+                    recommendation,
+                    reason,
+                }))
+            }
+            (None, _, _) => Err(D::Error::missing_field("entity")),
+            (_, None, _) => Err(D::Error::missing_field("recommendation")),
+            (_, _, None) => Err(D::Error::missing_field("reason")),
+        }
+    }
+}
+
+/// The recommended action of a policy rule.
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+// Cow<str> because deserialization sometimes needs to copy to unescape things
+#[serde(from = "Cow<'_, str>", into = "String")]
+pub enum Recommendation {
+    /// Entities affected by the rule should be banned from participation where possible.
+    Ban,
+
+    /// Any recommendation that is not part of the specification.
+    Custom(String),
+
+    /// Additional variants may be added in the future and will not be considered breaking changes
+    /// to ruma-events.
+    #[doc(hidden)]
+    __Nonexhaustive,
+}
+
+impl Display for Recommendation {
+    fn fmt(&self, f: &mut Formatter<'_>) -> FmtResult {
+        let recommendation_str = match *self {
+            Recommendation::Ban => "m.ban",
+            Recommendation::Custom(ref recommendation) => recommendation,
+            Recommendation::__Nonexhaustive => {
+                panic!("__Nonexhaustive enum variant is not intended for use.")
+            }
+        };
+
+        write!(f, "{}", recommendation_str)
+    }
+}
+
+impl From<Cow<'_, str>> for Recommendation {
+    fn from(s: Cow<'_, str>) -> Recommendation {
+        match &s as &str {
+            "m.ban" => Recommendation::Ban,
+            _ => Recommendation::Custom(s.into_owned()),
+        }
+    }
+}
+
+impl From<&str> for Recommendation {
+    fn from(s: &str) -> Recommendation {
+        Recommendation::from(Cow::Borrowed(s))
+    }
+}
+
+impl From<Recommendation> for String {
+    fn from(recommendation: Recommendation) -> String {
+        recommendation.to_string()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use serde_json::json;
+
+    use super::{PolicyRule, PolicyRuleEventContent, Recommendation};
+    use crate::util::serde_json_eq;
+
+    fn rule(entity: &str) -> PolicyRuleEventContent {
+        PolicyRuleEventContent::Rule(PolicyRule {
+            entity: entity.to_string(),
+            recommendation: Recommendation::Ban,
+            reason: "undesirable behaviour".to_string(),
+        })
+    }
+
+    #[test]
+    fn recommendation_serialize_and_deserialize() {
+        serde_json_eq(Recommendation::Ban, json!("m.ban"));
+        serde_json_eq(
+            Recommendation::Custom("io.ruma.test".to_string()),
+            json!("io.ruma.test"),
+        );
+    }
+
+    #[test]
+    fn matches_literal_entity() {
+        assert!(rule("@alice:example.org").matches("@alice:example.org"));
+        assert!(!rule("@alice:example.org").matches("@alice:example.org.evil"));
+    }
+
+    #[test]
+    fn matches_globs() {
+        assert!(rule("*.example.org").matches("matrix.example.org"));
+        assert!(!rule("*.example.org").matches("example.org"));
+        assert!(rule("*").matches(""));
+        assert!(rule("@bot?:example.org").matches("@bot1:example.org"));
+        assert!(!rule("@bot?:example.org").matches("@bot:example.org"));
+        assert!(rule("@*:*.evil").matches("@mallory:server.evil"));
+    }
+
+    #[test]
+    fn matches_pathological_glob() {
+        let entity = "a".repeat(64);
+
+        assert!(!rule("*a*a*a*a*a*a*a*a*b").matches(&entity));
+        assert!(rule("*a*a*a*a*a*a*a*a").matches(&entity));
+    }
+
+    #[test]
+    fn removed_rule() {
+        serde_json_eq(PolicyRuleEventContent::Removed, json!({}));
+        assert!(!PolicyRuleEventContent::Removed.matches("@alice:example.org"));
+        assert_eq!(PolicyRuleEventContent::Removed.rule(), None);
+    }
+
+    #[test]
+    fn incomplete_rule() {
+        let error = serde_json::from_value::<PolicyRuleEventContent>(json!({
+            "entity": "@alice:example.org",
+            "recommendation": "m.ban"
+        }))
+        .unwrap_err();
+
+        assert_eq!(error.to_string(), "missing field `reason`");
+    }
+}
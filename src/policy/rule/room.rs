@@ -0,0 +1,17 @@
+//! Types for the *m.policy.rule.room* event.
+
+use ruma_events_macros::ruma_event;
+
+use super::PolicyRuleEventContent;
+
+ruma_event! {
+    /// A moderation policy rule which affects rooms.
+    PolicyRuleRoomEvent {
+        kind: StateEvent,
+        event_type: "m.policy.rule.room",
+        content_type_alias: {
+            /// The payload for `PolicyRuleRoomEvent`.
+            PolicyRuleEventContent
+        },
+    }
+}
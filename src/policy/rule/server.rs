@@ -0,0 +1,47 @@
+//! Types for the *m.policy.rule.server* event.
+
+use ruma_events_macros::ruma_event;
+
+use super::PolicyRuleEventContent;
+
+ruma_event! {
+    /// A moderation policy rule which affects servers.
+    PolicyRuleServerEvent {
+        kind: StateEvent,
+        event_type: "m.policy.rule.server",
+        content_type_alias: {
+            /// The payload for `PolicyRuleServerEvent`.
+            PolicyRuleEventContent
+        },
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use serde_json::json;
+
+    use crate::{
+        policy::rule::{PolicyRule, PolicyRuleEventContent, Recommendation},
+        util::serde_json_eq_try_from_raw,
+    };
+
+    #[test]
+    fn globbed_server_rule_round_trip() {
+        let content = PolicyRuleEventContent::Rule(PolicyRule {
+            entity: "*.evil.example".to_string(),
+            recommendation: Recommendation::Ban,
+            reason: "spam".to_string(),
+        });
+
+        assert!(content.matches("matrix.evil.example"));
+        assert!(!content.matches("example.org"));
+        serde_json_eq_try_from_raw(
+            content,
+            json!({
+                "entity": "*.evil.example",
+                "recommendation": "m.ban",
+                "reason": "spam"
+            }),
+        );
+    }
+}
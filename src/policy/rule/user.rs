@@ -0,0 +1,87 @@
+//! Types for the *m.policy.rule.user* event.
+
+use ruma_events_macros::ruma_event;
+
+use super::PolicyRuleEventContent;
+
+ruma_event! {
+    /// A moderation policy rule which affects users.
+    PolicyRuleUserEvent {
+        kind: StateEvent,
+        event_type: "m.policy.rule.user",
+        content_type_alias: {
+            /// The payload for `PolicyRuleUserEvent`.
+            PolicyRuleEventContent
+        },
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use serde_json::{from_value as from_json_value, json};
+
+    use super::PolicyRuleUserEvent;
+    use crate::{
+        policy::rule::{PolicyRule, PolicyRuleEventContent, Recommendation},
+        util::serde_json_eq_try_from_raw,
+        EventResult,
+    };
+
+    #[test]
+    fn user_rule_round_trip() {
+        serde_json_eq_try_from_raw(
+            PolicyRuleEventContent::Rule(PolicyRule {
+                entity: "@alice*:example.org".to_string(),
+                recommendation: Recommendation::Ban,
+                reason: "undesirable behaviour".to_string(),
+            }),
+            json!({
+                "entity": "@alice*:example.org",
+                "recommendation": "m.ban",
+                "reason": "undesirable behaviour"
+            }),
+        );
+    }
+
+    #[test]
+    fn deserialize_user_rule_event() {
+        let event = from_json_value::<EventResult<PolicyRuleUserEvent>>(json!({
+            "content": {
+                "entity": "@alice*:example.org",
+                "recommendation": "m.ban",
+                "reason": "undesirable behaviour"
+            },
+            "event_id": "$143273582443PhrSn:example.org",
+            "origin_server_ts": 1_432_735_824_653_u64,
+            "room_id": "!jEsUZKDJdhlrceRyVU:example.org",
+            "sender": "@example:example.org",
+            "state_key": "rule:@alice*:example.org",
+            "type": "m.policy.rule.user"
+        }))
+        .unwrap()
+        .into_result()
+        .unwrap();
+
+        assert!(event.content.matches("@alice2:example.org"));
+        assert_eq!(event.state_key, "rule:@alice*:example.org");
+    }
+
+    #[test]
+    fn deserialize_removed_user_rule_event() {
+        let event = from_json_value::<EventResult<PolicyRuleUserEvent>>(json!({
+            "content": {},
+            "event_id": "$143273582443PhrSn:example.org",
+            "origin_server_ts": 1_432_735_824_653_u64,
+            "room_id": "!jEsUZKDJdhlrceRyVU:example.org",
+            "sender": "@example:example.org",
+            "state_key": "rule:@alice*:example.org",
+            "type": "m.policy.rule.user"
+        }))
+        .unwrap()
+        .into_result()
+        .unwrap();
+
+        assert_eq!(event.content, PolicyRuleEventContent::Removed);
+        assert!(!event.content.matches("@alice2:example.org"));
+    }
+}
@@ -80,7 +80,7 @@ mod tests {
     use serde_json::{from_value as from_json_value, json, to_value as to_json_value};
 
     use super::{PresenceEvent, PresenceEventContent, PresenceState};
-    use crate::EventResult;
+    use crate::{collections::all::Event, EventResult};
 
     #[test]
     fn serialization() {
@@ -145,4 +145,62 @@ mod tests {
             event
         );
     }
+
+    #[test]
+    fn round_trip_with_profile_through_collection() {
+        let json = json!({
+            "content": {
+                "avatar_url": "mxc://localhost:wefuiwegh8742w",
+                "displayname": "Alice Margatroid",
+                "presence": "unavailable"
+            },
+            "sender": "@alice:localhost",
+            "type": "m.presence"
+        });
+
+        let event = from_json_value::<EventResult<Event>>(json.clone())
+            .unwrap()
+            .into_result()
+            .unwrap();
+
+        match &event {
+            Event::Presence(presence) => {
+                assert_eq!(
+                    presence.content.avatar_url,
+                    Some("mxc://localhost:wefuiwegh8742w".to_string())
+                );
+                assert_eq!(
+                    presence.content.displayname,
+                    Some("Alice Margatroid".to_string())
+                );
+            }
+            _ => panic!("expected a presence event, got {:?}", event),
+        }
+        assert_eq!(to_json_value(&event).unwrap(), json);
+    }
+
+    #[test]
+    fn round_trip_without_profile_through_collection() {
+        let json = json!({
+            "content": {
+                "presence": "offline"
+            },
+            "sender": "@alice:localhost",
+            "type": "m.presence"
+        });
+
+        let event = from_json_value::<EventResult<Event>>(json.clone())
+            .unwrap()
+            .into_result()
+            .unwrap();
+
+        match &event {
+            Event::Presence(presence) => {
+                assert_eq!(presence.content.avatar_url, None);
+                assert_eq!(presence.content.displayname, None);
+            }
+            _ => panic!("expected a presence event, got {:?}", event),
+        }
+        assert_eq!(to_json_value(&event).unwrap(), json);
+    }
 }
@@ -27,11 +27,21 @@ ruma_event! {
     }
 }
 
+// `ruma_event!` doesn't support deriving additional traits for the content.
+#[allow(clippy::derivable_impls, unknown_lints)]
+impl Default for PushRulesEventContent {
+    fn default() -> Self {
+        Self {
+            global: Ruleset::default(),
+        }
+    }
+}
+
 /// A push ruleset scopes a set of rules according to some criteria.
 ///
 /// For example, some rules may only be applied for messages from a particular sender, a particular
 /// room, or by default. The push ruleset contains the entire set of scopes and rules.
-#[derive(Clone, Debug, Deserialize, PartialEq, Serialize)]
+#[derive(Clone, Debug, Default, Deserialize, PartialEq, Serialize)]
 pub struct Ruleset {
     /// These rules configure behaviour for (unencrypted) messages that match certain patterns.
     pub content: Vec<PatternedPushRule>,
@@ -348,7 +358,7 @@ impl<'de> Deserialize<'de> for PushCondition {
 
                 Ok(PushCondition::SenderNotificationPermission(condition))
             }
-            unknown_kind => Err(D::Error::custom(&format!(
+            unknown_kind => Err(D::Error::custom(format!(
                 "unknown condition kind `{}`",
                 unknown_kind
             ))),
@@ -437,8 +447,8 @@ mod tests {
     use serde_json::{from_value as from_json_value, json, to_value as to_json_value};
 
     use super::{
-        Action, EventMatchCondition, PushCondition, PushRulesEvent, RoomMemberCountCondition,
-        SenderNotificationPermissionCondition, Tweak,
+        Action, EventMatchCondition, PushCondition, PushRulesEvent, PushRulesEventContent,
+        RoomMemberCountCondition, SenderNotificationPermissionCondition, Tweak,
     };
     use crate::EventResult;
 
@@ -819,4 +829,20 @@ mod tests {
             .into_result()
             .is_ok());
     }
+
+    #[test]
+    fn default_is_empty() {
+        assert_eq!(
+            to_json_value(PushRulesEventContent::default()).unwrap(),
+            json!({
+                "global": {
+                    "content": [],
+                    "override": [],
+                    "room": [],
+                    "sender": [],
+                    "underride": []
+                }
+            })
+        );
+    }
 }
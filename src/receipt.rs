@@ -1,11 +1,18 @@
 //! Types for the *m.receipt* event.
 
-use std::collections::HashMap;
+use std::{
+    borrow::Cow,
+    collections::HashMap,
+    fmt::{Display, Formatter, Result as FmtResult},
+};
 
 use js_int::UInt;
 use ruma_events_macros::ruma_event;
 use ruma_identifiers::{EventId, RoomId, UserId};
-use serde::{Deserialize, Serialize};
+use serde::{Deserialize, Deserializer, Serialize, Serializer};
+use serde_json::Value;
+
+use crate::util::sorted_map;
 
 ruma_event! {
     /// Informs the client of new receipts.
@@ -17,6 +24,7 @@ ruma_event! {
             ///
             /// `None` if the room is known through other means (such as this even being part of an
             /// event list scoped to a room in a `/sync` response)
+            #[serde(skip_serializing_if = "Option::is_none")]
             pub room_id: Option<RoomId>,
         },
         content_type_alias: {
@@ -24,18 +32,167 @@ ruma_event! {
             ///
             /// A mapping of event ID to a collection of receipts for this event ID. The event ID is the ID of
             /// the event being acknowledged and *not* an ID for the receipt itself.
+            #[serde(serialize_with = "crate::util::sorted_map")]
             HashMap<EventId, Receipts>
         },
     }
 }
 
+impl ReceiptEvent {
+    /// The receipts of the given type for the event with the given ID.
+    ///
+    /// Returns `None` if no receipts of that type were sent for the event.
+    pub fn receipts_of_type(&self, kind: ReceiptType, event_id: &EventId) -> Option<&UserReceipts> {
+        self.content.get(event_id)?.of_type(&kind)
+    }
+}
+
 /// A collection of receipts.
 #[derive(Clone, Debug, Deserialize, PartialEq, Serialize)]
 pub struct Receipts {
     /// A collection of users who have sent *m.read* receipts for this event.
     #[serde(rename = "m.read")]
     #[serde(default)]
+    #[serde(skip_serializing_if = "Option::is_none")]
+    #[serde(serialize_with = "crate::util::sorted_optional_map")]
     pub read: Option<UserReceipts>,
+
+    /// A collection of users who have sent *m.read.private* receipts for this event.
+    ///
+    /// These are only visible to the user who sent them and must not be treated as public read
+    /// receipts.
+    #[serde(rename = "m.read.private")]
+    #[serde(default)]
+    #[serde(skip_serializing_if = "Option::is_none")]
+    #[serde(serialize_with = "crate::util::sorted_optional_map")]
+    pub read_private: Option<UserReceipts>,
+
+    /// Receipts of all other types, such as *m.fully_read*, keyed by receipt type.
+    ///
+    /// Entries that aren't a valid collection of receipts are skipped during deserialization.
+    #[serde(flatten)]
+    #[serde(deserialize_with = "deserialize_other_receipts")]
+    #[serde(serialize_with = "serialize_other_receipts")]
+    pub other: HashMap<String, UserReceipts>,
+}
+
+/// Deserializes the receipts of other types, skipping entries that aren't receipts.
+///
+/// Unknown receipt types can be sent by anyone, so one that doesn't fit shouldn't make the whole
+/// event invalid.
+fn deserialize_other_receipts<'de, D>(
+    deserializer: D,
+) -> Result<HashMap<String, UserReceipts>, D::Error>
+where
+    D: Deserializer<'de>,
+{
+    let other = HashMap::<String, Value>::deserialize(deserializer)?;
+
+    Ok(other
+        .into_iter()
+        .filter_map(|(kind, receipts)| Some((kind, serde_json::from_value(receipts).ok()?)))
+        .collect())
+}
+
+/// Serializes the receipts of other types sorted by receipt type and user ID.
+fn serialize_other_receipts<S>(
+    other: &HashMap<String, UserReceipts>,
+    serializer: S,
+) -> Result<S::Ok, S::Error>
+where
+    S: Serializer,
+{
+    struct SortedUserReceipts<'a>(&'a UserReceipts);
+
+    impl Serialize for SortedUserReceipts<'_> {
+        fn serialize<S>(&self, serializer: S) -> Result<S::Ok, S::Error>
+        where
+            S: Serializer,
+        {
+            sorted_map(self.0, serializer)
+        }
+    }
+
+    let mut entries: Vec<_> = other
+        .iter()
+        .map(|(kind, receipts)| (kind, SortedUserReceipts(receipts)))
+        .collect();
+    entries.sort_by_key(|(kind, _)| *kind);
+
+    serializer.collect_map(entries)
+}
+
+impl Receipts {
+    /// The receipts of the given type.
+    pub fn of_type(&self, kind: &ReceiptType) -> Option<&UserReceipts> {
+        match kind {
+            ReceiptType::Read => self.read.as_ref(),
+            ReceiptType::ReadPrivate => self.read_private.as_ref(),
+            _ => self.other.get(&kind.to_string()),
+        }
+    }
+}
+
+/// The type of a receipt.
+#[derive(Clone, Debug, PartialEq, Eq, Hash, Serialize, Deserialize)]
+// Cow<str> because deserialization sometimes needs to copy to unescape things
+#[serde(from = "Cow<'_, str>", into = "String")]
+pub enum ReceiptType {
+    /// m.read
+    Read,
+
+    /// m.read.private
+    ReadPrivate,
+
+    /// m.fully_read
+    FullyRead,
+
+    /// Any receipt type that is not part of the specification.
+    Custom(String),
+
+    /// Additional variants may be added in the future and will not be considered breaking changes
+    /// to ruma-events.
+    #[doc(hidden)]
+    __Nonexhaustive,
+}
+
+impl Display for ReceiptType {
+    fn fmt(&self, f: &mut Formatter<'_>) -> FmtResult {
+        let receipt_type_str = match *self {
+            ReceiptType::Read => "m.read",
+            ReceiptType::ReadPrivate => "m.read.private",
+            ReceiptType::FullyRead => "m.fully_read",
+            ReceiptType::Custom(ref receipt_type) => receipt_type,
+            ReceiptType::__Nonexhaustive => {
+                panic!("__Nonexhaustive enum variant is not intended for use.")
+            }
+        };
+
+        write!(f, "{}", receipt_type_str)
+    }
+}
+
+impl From<Cow<'_, str>> for ReceiptType {
+    fn from(s: Cow<'_, str>) -> ReceiptType {
+        match &s as &str {
+            "m.read" => ReceiptType::Read,
+            "m.read.private" => ReceiptType::ReadPrivate,
+            "m.fully_read" => ReceiptType::FullyRead,
+            _ => ReceiptType::Custom(s.into_owned()),
+        }
+    }
+}
+
+impl From<&str> for ReceiptType {
+    fn from(s: &str) -> ReceiptType {
+        ReceiptType::from(Cow::Borrowed(s))
+    }
+}
+
+impl From<ReceiptType> for String {
+    fn from(receipt_type: ReceiptType) -> String {
+        receipt_type.to_string()
+    }
 }
 
 /// A mapping of user ID to receipt.
@@ -47,5 +204,134 @@ pub type UserReceipts = HashMap<UserId, Receipt>;
 #[derive(Clone, Debug, Deserialize, PartialEq, Serialize)]
 pub struct Receipt {
     /// The timestamp (milliseconds since the Unix epoch) when the receipt was sent.
+    #[serde(skip_serializing_if = "Option::is_none")]
     pub ts: Option<UInt>,
 }
+
+#[cfg(test)]
+mod tests {
+    use std::convert::TryFrom;
+
+    use js_int::UInt;
+    use ruma_identifiers::{EventId, UserId};
+    use serde_json::{from_value as from_json_value, json};
+
+    use super::{ReceiptEvent, ReceiptType};
+    use crate::{
+        util::{assert_deterministic_serialization, serde_json_eq, try_from_raw_round_trip},
+        EventResult,
+    };
+
+    #[test]
+    fn receipt_type_serialize_and_deserialize() {
+        serde_json_eq(ReceiptType::Read, json!("m.read"));
+        serde_json_eq(ReceiptType::ReadPrivate, json!("m.read.private"));
+        serde_json_eq(ReceiptType::FullyRead, json!("m.fully_read"));
+        serde_json_eq(
+            ReceiptType::Custom("io.ruma.receipt".to_string()),
+            json!("io.ruma.receipt"),
+        );
+    }
+
+    #[test]
+    fn mixed_public_and_private_reads() {
+        let json = json!({
+            "content": {
+                "$event:example.com": {
+                    "m.read": {
+                        "@alice:example.com": { "ts": 1 }
+                    },
+                    "m.read.private": {
+                        "@bob:example.com": { "ts": 2 }
+                    },
+                    "io.ruma.receipt": {
+                        "@carl:example.com": {}
+                    }
+                }
+            },
+            "type": "m.receipt"
+        });
+
+        let event = from_json_value::<EventResult<ReceiptEvent>>(json.clone())
+            .unwrap()
+            .into_result()
+            .unwrap();
+        try_from_raw_round_trip::<ReceiptEvent>(json);
+
+        let event_id = EventId::try_from("$event:example.com").unwrap();
+        let alice = UserId::try_from("@alice:example.com").unwrap();
+        let bob = UserId::try_from("@bob:example.com").unwrap();
+
+        let public = event
+            .receipts_of_type(ReceiptType::Read, &event_id)
+            .unwrap();
+        assert_eq!(public.len(), 1);
+        assert_eq!(public[&alice].ts, Some(UInt::from(1u32)));
+
+        let private = event
+            .receipts_of_type(ReceiptType::ReadPrivate, &event_id)
+            .unwrap();
+        assert_eq!(private.len(), 1);
+        assert_eq!(private[&bob].ts, Some(UInt::from(2u32)));
+
+        assert!(event
+            .receipts_of_type(ReceiptType::from("io.ruma.receipt"), &event_id)
+            .is_some());
+        assert!(event
+            .receipts_of_type(ReceiptType::FullyRead, &event_id)
+            .is_none());
+    }
+
+    #[test]
+    fn deterministic_serialization() {
+        let receipts = json!({
+            "@alice:example.com": { "ts": 1 },
+            "@bob:example.com": { "ts": 2 },
+            "@carl:example.com": { "ts": 3 },
+            "@dana:example.com": { "ts": 4 }
+        });
+
+        assert_deterministic_serialization::<ReceiptEvent>(json!({
+            "content": {
+                "$a:example.com": {
+                    "m.read": receipts,
+                    "m.read.private": receipts,
+                    "m.fully_read": receipts,
+                    "org.example.seen": receipts
+                },
+                "$b:example.com": { "m.read": receipts },
+                "$c:example.com": { "m.read": receipts },
+                "$d:example.com": { "m.read": receipts }
+            },
+            "room_id": "!room:example.com",
+            "type": "m.receipt"
+        }));
+    }
+
+    #[test]
+    fn skip_malformed_receipts_of_other_types() {
+        let event = from_json_value::<EventResult<ReceiptEvent>>(json!({
+            "content": {
+                "$event:example.com": {
+                    "m.read": {
+                        "@alice:example.com": { "ts": 1 }
+                    },
+                    "m.fully_read": {
+                        "@alice:example.com": { "ts": 1 }
+                    },
+                    "org.example.flag": true,
+                    "org.example.list": ["@bob:example.com"]
+                }
+            },
+            "type": "m.receipt"
+        }))
+        .unwrap()
+        .into_result()
+        .unwrap();
+
+        let receipts = &event.content[&EventId::try_from("$event:example.com").unwrap()];
+        assert!(receipts.read.is_some());
+        assert_eq!(receipts.other.len(), 1);
+        assert!(receipts.of_type(&ReceiptType::FullyRead).is_some());
+    }
+}
@@ -0,0 +1,377 @@
+//! Types for relations between events, as found in the `m.relates_to` field of event contents.
+
+use js_int::UInt;
+use ruma_identifiers::{EventId, UserId};
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+
+use crate::{room::message::InReplyTo, unstable};
+
+/// A relation of an event to another event, independent of the content type it was found in.
+#[derive(Clone, Debug, PartialEq)]
+pub enum Relation {
+    /// A [rich reply](https://matrix.org/docs/spec/client_server/r0.6.0#rich-replies) to another
+    /// event.
+    Reply {
+        /// The event being replied to.
+        in_reply_to: EventId,
+    },
+
+    /// A replacement of the content of another event, i.e. an edit.
+    Replacement {
+        /// The event being replaced.
+        event_id: EventId,
+    },
+
+    /// An annotation of another event, such as a reaction.
+    Annotation {
+        /// The event being annotated.
+        event_id: EventId,
+
+        /// The annotation, e.g. the reaction emoji.
+        key: String,
+    },
+
+    /// A message in a thread.
+    Thread {
+        /// The root event of the thread.
+        event_id: EventId,
+
+        /// The event in the thread this message is replying to, if any.
+        in_reply_to: Option<EventId>,
+    },
+}
+
+/// The fields of all supported shapes of `m.relates_to`.
+#[derive(Deserialize)]
+struct RawRelatesTo {
+    rel_type: Option<String>,
+    event_id: Option<EventId>,
+    key: Option<String>,
+    #[serde(rename = "m.in_reply_to")]
+    in_reply_to: Option<InReplyTo>,
+}
+
+/// Extracts the relation from the content of any event.
+///
+/// Returns `None` if `content` has no `m.relates_to` field, or if it is malformed or uses a
+/// relation type that isn't supported. Threads using the unstable `io.element.thread` relation
+/// type are recognized as well.
+// TODO: Switch to `Option::as_deref` and remove this attribute once MSRV is >= 1.40
+#[allow(clippy::option_as_ref_deref, unknown_lints)]
+pub fn extract_relation(content: &Value) -> Option<Relation> {
+    let raw: RawRelatesTo = serde_json::from_value(content.get("m.relates_to")?.clone()).ok()?;
+    let in_reply_to = raw.in_reply_to.map(|in_reply_to| in_reply_to.event_id);
+
+    let relation = match raw.rel_type.as_ref().map(String::as_str) {
+        None => Relation::Reply {
+            in_reply_to: in_reply_to?,
+        },
+        Some("m.replace") => Relation::Replacement {
+            event_id: raw.event_id?,
+        },
+        Some("m.annotation") => Relation::Annotation {
+            event_id: raw.event_id?,
+            key: raw.key?,
+        },
+        Some("m.thread") | Some(unstable::THREAD_REL_TYPE) => Relation::Thread {
+            event_id: raw.event_id?,
+            in_reply_to,
+        },
+        Some(_) => return None,
+    };
+
+    Some(relation)
+}
+
+/// Relations of an event that the homeserver aggregated into its `unsigned.m.relations`.
+///
+/// Relation types that aren't supported are ignored.
+#[derive(Clone, Debug, Default, Deserialize, PartialEq, Serialize)]
+pub struct BundledRelations {
+    /// The annotations of the event, grouped by type and key.
+    #[serde(rename = "m.annotation", skip_serializing_if = "Option::is_none")]
+    pub annotation: Option<AnnotationChunk>,
+
+    /// The most recent replacement of the event.
+    #[serde(rename = "m.replace", skip_serializing_if = "Option::is_none")]
+    pub replace: Option<BundledReplacement>,
+}
+
+impl BundledRelations {
+    /// The aggregated *m.reaction* annotations of the event.
+    pub fn reactions(&self) -> Vec<&BundledAnnotation> {
+        match &self.annotation {
+            Some(annotation) => annotation
+                .chunk
+                .iter()
+                .filter(|annotation| annotation.annotation_type == "m.reaction")
+                .collect(),
+            None => Vec::new(),
+        }
+    }
+
+    /// The most recent edit of the event, if any.
+    pub fn latest_edit(&self) -> Option<&BundledReplacement> {
+        self.replace.as_ref()
+    }
+}
+
+/// The aggregated annotations of an event.
+#[derive(Clone, Debug, Deserialize, PartialEq, Serialize)]
+pub struct AnnotationChunk {
+    /// The annotations, one per combination of event type and key.
+    pub chunk: Vec<BundledAnnotation>,
+}
+
+/// The number of annotations of an event with the same event type and key.
+#[derive(Clone, Debug, Deserialize, PartialEq, Serialize)]
+pub struct BundledAnnotation {
+    /// The type of the annotating events, usually *m.reaction*.
+    #[serde(rename = "type")]
+    pub annotation_type: String,
+
+    /// The annotation, e.g. the reaction emoji.
+    pub key: String,
+
+    /// The number of events with this annotation.
+    pub count: UInt,
+}
+
+/// A reference to the most recent replacement of an event.
+#[derive(Clone, Debug, Deserialize, PartialEq, Serialize)]
+pub struct BundledReplacement {
+    /// The ID of the replacing event.
+    pub event_id: EventId,
+
+    /// Timestamp (milliseconds since the UNIX epoch) on originating homeserver when the replacing
+    /// event was sent.
+    pub origin_server_ts: UInt,
+
+    /// The user who sent the replacing event.
+    pub sender: UserId,
+}
+
+/// Normalizes the `key` of an annotation, so that keys that render the same are aggregated
+/// together.
+///
+/// Reactions are usually single emoji, which can be sent with or without a variation selector
+/// (e.g. `❤` and `❤️`). This strips the text and emoji presentation selectors (U+FE0E and
+/// U+FE0F), leaving all other characters untouched.
+pub fn normalize_annotation_key(key: &str) -> String {
+    key.chars()
+        .filter(|&c| c != '\u{FE0E}' && c != '\u{FE0F}')
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use std::convert::TryFrom;
+
+    use js_int::UInt;
+    use ruma_identifiers::{EventId, UserId};
+    use serde_json::{from_value as from_json_value, json, to_value as to_json_value};
+
+    use super::{extract_relation, normalize_annotation_key, BundledRelations, Relation};
+    use crate::{room::message::MessageEvent, EventResult, RoomEvent};
+
+    fn event_id(id: &str) -> EventId {
+        EventId::try_from(id).unwrap()
+    }
+
+    #[test]
+    fn reply() {
+        let content = json!({
+            "body": "> <@alice:example.com> hi\n\nhello",
+            "msgtype": "m.text",
+            "m.relates_to": {
+                "m.in_reply_to": { "event_id": "$original:example.com" }
+            }
+        });
+
+        assert_eq!(
+            extract_relation(&content),
+            Some(Relation::Reply {
+                in_reply_to: event_id("$original:example.com"),
+            })
+        );
+    }
+
+    #[test]
+    fn replacement() {
+        let content = json!({
+            "body": "* hello",
+            "msgtype": "m.text",
+            "m.new_content": { "body": "hello", "msgtype": "m.text" },
+            "m.relates_to": {
+                "rel_type": "m.replace",
+                "event_id": "$original:example.com"
+            }
+        });
+
+        assert_eq!(
+            extract_relation(&content),
+            Some(Relation::Replacement {
+                event_id: event_id("$original:example.com"),
+            })
+        );
+    }
+
+    #[test]
+    fn annotation() {
+        let content = json!({
+            "m.relates_to": {
+                "rel_type": "m.annotation",
+                "event_id": "$original:example.com",
+                "key": "👍"
+            }
+        });
+
+        assert_eq!(
+            extract_relation(&content),
+            Some(Relation::Annotation {
+                event_id: event_id("$original:example.com"),
+                key: "👍".to_string(),
+            })
+        );
+    }
+
+    #[test]
+    fn thread() {
+        let content = json!({
+            "body": "in a thread",
+            "msgtype": "m.text",
+            "m.relates_to": {
+                "rel_type": "m.thread",
+                "event_id": "$root:example.com",
+                "m.in_reply_to": { "event_id": "$latest:example.com" }
+            }
+        });
+
+        assert_eq!(
+            extract_relation(&content),
+            Some(Relation::Thread {
+                event_id: event_id("$root:example.com"),
+                in_reply_to: Some(event_id("$latest:example.com")),
+            })
+        );
+    }
+
+    #[test]
+    fn unstable_thread() {
+        let content = json!({
+            "body": "in a thread",
+            "msgtype": "m.text",
+            "m.relates_to": {
+                "rel_type": "io.element.thread",
+                "event_id": "$root:example.com"
+            }
+        });
+
+        assert_eq!(
+            extract_relation(&content),
+            Some(Relation::Thread {
+                event_id: event_id("$root:example.com"),
+                in_reply_to: None,
+            })
+        );
+    }
+
+    #[test]
+    fn no_relation() {
+        assert_eq!(
+            extract_relation(&json!({ "body": "hello", "msgtype": "m.text" })),
+            None
+        );
+        assert_eq!(
+            extract_relation(&json!({
+                "m.relates_to": {
+                    "rel_type": "m.reference",
+                    "event_id": "$original:example.com"
+                }
+            })),
+            None
+        );
+        assert_eq!(
+            extract_relation(&json!({ "m.relates_to": { "rel_type": "m.replace" } })),
+            None
+        );
+    }
+
+    #[test]
+    fn normalized_heart_keys() {
+        let text_heart = "\u{2764}";
+        let emoji_heart = "\u{2764}\u{FE0F}";
+
+        assert_ne!(text_heart, emoji_heart);
+        assert_eq!(
+            normalize_annotation_key(text_heart),
+            normalize_annotation_key(emoji_heart)
+        );
+        assert_eq!(normalize_annotation_key("👍🏽"), "👍🏽");
+        assert_eq!(normalize_annotation_key("+1"), "+1");
+    }
+
+    fn bundled_relations(relations: serde_json::Value) -> BundledRelations {
+        let event = from_json_value::<EventResult<MessageEvent>>(json!({
+            "content": { "body": "hello", "msgtype": "m.text" },
+            "event_id": "$original:example.com",
+            "origin_server_ts": 1,
+            "sender": "@alice:example.com",
+            "type": "m.room.message",
+            "unsigned": { "m.relations": relations.clone() }
+        }))
+        .unwrap()
+        .into_result()
+        .unwrap();
+
+        let bundled = event.bundled_relations().unwrap();
+        assert_eq!(to_json_value(&bundled).unwrap(), relations);
+
+        bundled
+    }
+
+    #[test]
+    fn bundled_reactions() {
+        let bundled = bundled_relations(json!({
+            "m.annotation": {
+                "chunk": [
+                    { "type": "m.reaction", "key": "👍", "count": 3 },
+                    { "type": "org.example.vote", "key": "yes", "count": 1 }
+                ]
+            }
+        }));
+        let reactions = bundled.reactions();
+
+        assert_eq!(reactions.len(), 1);
+        assert_eq!(reactions[0].key, "👍");
+        assert_eq!(reactions[0].count, UInt::from(3u32));
+        assert_eq!(bundled.latest_edit(), None);
+    }
+
+    #[test]
+    fn bundled_edit() {
+        let bundled = bundled_relations(json!({
+            "m.replace": {
+                "event_id": "$edit:example.com",
+                "origin_server_ts": 2,
+                "sender": "@alice:example.com"
+            }
+        }));
+        let edit = bundled.latest_edit().unwrap();
+
+        assert_eq!(edit.event_id, event_id("$edit:example.com"));
+        assert_eq!(edit.sender, UserId::try_from("@alice:example.com").unwrap());
+        assert!(bundled.reactions().is_empty());
+    }
+
+    #[test]
+    fn unknown_bundled_relations() {
+        let bundled = from_json_value::<BundledRelations>(json!({
+            "m.reference": { "chunk": [{ "event_id": "$reference:example.com" }] }
+        }))
+        .unwrap();
+
+        assert_eq!(bundled, BundledRelations::default());
+    }
+}
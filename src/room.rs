@@ -2,11 +2,17 @@
 //!
 //! This module also contains types shared by events in its child namespaces.
 
-use std::collections::HashMap;
+use std::{
+    collections::HashMap,
+    fmt::{Debug, Formatter, Result as FmtResult},
+};
 
 use js_int::UInt;
 use serde::{Deserialize, Serialize};
 
+use crate::util::Redacted;
+
+pub mod access;
 pub mod aliases;
 pub mod avatar;
 pub mod canonical_alias;
@@ -97,6 +103,7 @@ pub struct EncryptedFile {
 
     /// A map from an algorithm name to a hash of the ciphertext, encoded as unpadded base64.
     /// Clients should support the SHA-256 hash, which uses the key sha256.
+    #[serde(serialize_with = "crate::util::sorted_map")]
     pub hashes: HashMap<String, String>,
 
     /// Version of the encrypted attachments protocol. Must be `v2`.
@@ -104,7 +111,9 @@ pub struct EncryptedFile {
 }
 
 /// A [JSON Web Key](https://tools.ietf.org/html/rfc7517#appendix-A.3) object.
-#[derive(Clone, Debug, Deserialize, PartialEq, Serialize)]
+///
+/// The `Debug` output doesn't contain the key itself.
+#[derive(Clone, Deserialize, PartialEq, Serialize)]
 pub struct JsonWebKey {
     /// Key type. Must be `oct`.
     pub kty: String,
@@ -122,3 +131,15 @@ pub struct JsonWebKey {
     /// [W3C extension](https://w3c.github.io/webcrypto/#iana-section-jwk).
     pub ext: bool,
 }
+
+impl Debug for JsonWebKey {
+    fn fmt(&self, f: &mut Formatter<'_>) -> FmtResult {
+        f.debug_struct("JsonWebKey")
+            .field("kty", &self.kty)
+            .field("key_ops", &self.key_ops)
+            .field("alg", &self.alg)
+            .field("k", &Redacted)
+            .field("ext", &self.ext)
+            .finish()
+    }
+}
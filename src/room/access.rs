@@ -0,0 +1,94 @@
+//! A summary of the access policy of a room, as set by its *m.room.join_rules*,
+//! *m.room.guest_access* and *m.room.history_visibility* events.
+
+use super::{
+    guest_access::{GuestAccess, GuestAccessEventContent},
+    history_visibility::{HistoryVisibility, HistoryVisibilityEventContent},
+    join_rules::{JoinRule, JoinRulesEventContent},
+};
+
+/// Who can join and read a room, derived from its access-related state.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct RoomAccessSummary {
+    /// Whether anyone can join the room without an invite.
+    pub publicly_joinable: bool,
+
+    /// Whether guest users can join the room.
+    ///
+    /// Guests can't be invited, so this requires the room to be publicly joinable as well.
+    pub guests_allowed: bool,
+
+    /// Whether the room's history can be read without joining it.
+    pub previewable: bool,
+}
+
+/// Combines the content of a room's access-related state events into a `RoomAccessSummary`.
+///
+/// Rooms without an *m.room.guest_access* or *m.room.history_visibility* event should pass the
+/// `Default` contents, which match how servers treat the missing events.
+pub fn summarize_access(
+    join_rules: &JoinRulesEventContent,
+    guest_access: &GuestAccessEventContent,
+    history_visibility: &HistoryVisibilityEventContent,
+) -> RoomAccessSummary {
+    let publicly_joinable = join_rules.join_rule == JoinRule::Public;
+
+    RoomAccessSummary {
+        publicly_joinable,
+        guests_allowed: publicly_joinable && guest_access.guest_access == GuestAccess::CanJoin,
+        previewable: history_visibility.history_visibility == HistoryVisibility::WorldReadable,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{summarize_access, RoomAccessSummary};
+    use crate::room::{
+        guest_access::{GuestAccess, GuestAccessEventContent},
+        history_visibility::{HistoryVisibility, HistoryVisibilityEventContent},
+        join_rules::{JoinRule, JoinRulesEventContent},
+    };
+
+    fn join_rules(join_rule: JoinRule) -> JoinRulesEventContent {
+        JoinRulesEventContent {
+            join_rule,
+            allow: Vec::new(),
+        }
+    }
+
+    #[test]
+    fn public_world_readable_room() {
+        let summary = summarize_access(
+            &join_rules(JoinRule::Public),
+            &GuestAccessEventContent::new(GuestAccess::CanJoin),
+            &HistoryVisibilityEventContent::new(HistoryVisibility::WorldReadable),
+        );
+
+        assert_eq!(
+            summary,
+            RoomAccessSummary {
+                publicly_joinable: true,
+                guests_allowed: true,
+                previewable: true,
+            }
+        );
+    }
+
+    #[test]
+    fn private_invite_only_room() {
+        let summary = summarize_access(
+            &join_rules(JoinRule::Invite),
+            &GuestAccessEventContent::new(GuestAccess::CanJoin),
+            &HistoryVisibilityEventContent::default(),
+        );
+
+        assert_eq!(
+            summary,
+            RoomAccessSummary {
+                publicly_joinable: false,
+                guests_allowed: false,
+                previewable: false,
+            }
+        );
+    }
+}
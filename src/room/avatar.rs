@@ -18,7 +18,44 @@ ruma_event! {
 
             /// Information about the avatar thumbnail image.
             /// URL of the avatar image.
+            ///
+            /// Empty if the room has no avatar, in which case the field is omitted when
+            /// serializing.
+            #[serde(default, skip_serializing_if = "String::is_empty")]
             pub url: String,
         },
     }
 }
+
+impl AvatarEventContent {
+    /// Creates an `AvatarEventContent` that removes the avatar of the room.
+    pub fn clear() -> Self {
+        Self {
+            info: None,
+            url: String::new(),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use serde_json::{from_value as from_json_value, json, to_value as to_json_value};
+
+    use super::AvatarEventContent;
+    use crate::EventResult;
+
+    #[test]
+    fn cleared_avatar_serialization() {
+        assert_eq!(
+            to_json_value(AvatarEventContent::clear()).unwrap(),
+            json!({})
+        );
+        assert_eq!(
+            from_json_value::<EventResult<AvatarEventContent>>(json!({}))
+                .unwrap()
+                .into_result()
+                .unwrap(),
+            AvatarEventContent::clear()
+        );
+    }
+}
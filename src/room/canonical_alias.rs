@@ -43,6 +43,10 @@ pub struct CanonicalAliasEventContent {
     ///
     /// Rooms with `alias: None` should be treated the same as a room with no canonical alias.
     pub alias: Option<RoomAliasId>,
+
+    /// Alternative aliases the room advertises.
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    pub alt_aliases: Vec<RoomAliasId>,
 }
 
 impl FromRaw for CanonicalAliasEvent {
@@ -66,7 +70,10 @@ impl FromRaw for CanonicalAliasEventContent {
     type Raw = raw::CanonicalAliasEventContent;
 
     fn from_raw(raw: raw::CanonicalAliasEventContent) -> Self {
-        Self { alias: raw.alias }
+        Self {
+            alias: raw.alias,
+            alt_aliases: raw.alt_aliases,
+        }
     }
 }
 
@@ -121,6 +128,22 @@ impl_state_event!(
     EventType::RoomCanonicalAlias
 );
 
+/// Whether the canonical alias and all alternative aliases of `canonical` are among
+/// `known_aliases`.
+///
+/// Servers should reject *m.room.canonical_alias* events for which this returns `false`, since they
+/// point to aliases that don't exist.
+pub fn canonical_alias_is_valid(
+    canonical: &CanonicalAliasEventContent,
+    known_aliases: &[RoomAliasId],
+) -> bool {
+    canonical
+        .alias
+        .iter()
+        .chain(&canonical.alt_aliases)
+        .all(|alias| known_aliases.contains(alias))
+}
+
 pub(crate) mod raw {
     use super::*;
 
@@ -135,6 +158,7 @@ pub(crate) mod raw {
 
         /// Timestamp (milliseconds since the UNIX epoch) on originating homeserver when this
         /// event was sent.
+        #[serde(deserialize_with = "crate::__private::uint_or_string")]
         pub origin_server_ts: UInt,
 
         /// The previous content for this state key, if any.
@@ -165,6 +189,10 @@ pub(crate) mod raw {
         #[serde(default)]
         #[serde(deserialize_with = "empty_string_as_none")]
         pub alias: Option<RoomAliasId>,
+
+        /// Alternative aliases the room advertises.
+        #[serde(default)]
+        pub alt_aliases: Vec<RoomAliasId>,
     }
 }
 
@@ -176,7 +204,7 @@ mod tests {
     use ruma_identifiers::{EventId, RoomAliasId, UserId};
     use serde_json::{from_value as from_json_value, json, to_value as to_json_value, Map};
 
-    use super::{CanonicalAliasEvent, CanonicalAliasEventContent};
+    use super::{canonical_alias_is_valid, CanonicalAliasEvent, CanonicalAliasEventContent};
     use crate::EventResult;
 
     #[test]
@@ -184,6 +212,7 @@ mod tests {
         let canonical_alias_event = CanonicalAliasEvent {
             content: CanonicalAliasEventContent {
                 alias: Some(RoomAliasId::try_from("#somewhere:localhost").unwrap()),
+                alt_aliases: Vec::new(),
             },
             event_id: EventId::try_from("$h29iv0s8:example.com").unwrap(),
             origin_server_ts: UInt::try_from(1).unwrap(),
@@ -300,4 +329,55 @@ mod tests {
             alias
         );
     }
+
+    #[test]
+    fn alt_aliases() {
+        let json_data = json!({
+            "alias": "#somewhere:localhost",
+            "alt_aliases": ["#elsewhere:localhost"]
+        });
+        let content = from_json_value::<EventResult<CanonicalAliasEventContent>>(json_data.clone())
+            .unwrap()
+            .into_result()
+            .unwrap();
+
+        assert_eq!(
+            content.alt_aliases,
+            vec![RoomAliasId::try_from("#elsewhere:localhost").unwrap()]
+        );
+        assert_eq!(to_json_value(&content).unwrap(), json_data);
+    }
+
+    #[test]
+    fn valid_canonical_alias() {
+        let known_aliases = vec![
+            RoomAliasId::try_from("#somewhere:localhost").unwrap(),
+            RoomAliasId::try_from("#elsewhere:localhost").unwrap(),
+        ];
+        let content = CanonicalAliasEventContent {
+            alias: Some(known_aliases[0].clone()),
+            alt_aliases: vec![known_aliases[1].clone()],
+        };
+
+        assert!(canonical_alias_is_valid(&content, &known_aliases));
+        assert!(canonical_alias_is_valid(
+            &CanonicalAliasEventContent {
+                alias: None,
+                alt_aliases: Vec::new(),
+            },
+            &[]
+        ));
+    }
+
+    #[test]
+    fn invalid_canonical_alias() {
+        let known_aliases = vec![RoomAliasId::try_from("#somewhere:localhost").unwrap()];
+        let content = CanonicalAliasEventContent {
+            alias: Some(known_aliases[0].clone()),
+            alt_aliases: vec![RoomAliasId::try_from("#nowhere:localhost").unwrap()],
+        };
+
+        assert!(!canonical_alias_is_valid(&content, &known_aliases));
+        assert!(!canonical_alias_is_valid(&content, &[]));
+    }
 }
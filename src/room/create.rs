@@ -30,10 +30,32 @@ ruma_event! {
             /// A reference to the room this room replaces, if the previous room was upgraded.
             #[serde(skip_serializing_if = "Option::is_none")]
             pub predecessor: Option<PreviousRoom>,
+
+            /// The type of the room, e.g. `m.space` for [spaces](../../space/index.html).
+            ///
+            /// `None` for regular rooms.
+            #[serde(rename = "type")]
+            #[serde(skip_serializing_if = "Option::is_none")]
+            pub room_type: Option<String>,
         },
     }
 }
 
+impl CreateEventContent {
+    /// Whether users on other homeservers are able to join this room.
+    pub fn federates(&self) -> bool {
+        self.federate
+    }
+
+    /// Whether this room is a space.
+    pub fn is_space(&self) -> bool {
+        match &self.room_type {
+            Some(room_type) => room_type == "m.space",
+            None => false,
+        }
+    }
+}
+
 /// A reference to an old room replaced during a room version upgrade.
 #[derive(Clone, Debug, Deserialize, PartialEq, Serialize)]
 pub struct PreviousRoom {
@@ -66,6 +88,7 @@ mod tests {
             federate: true,
             room_version: RoomVersionId::version_4(),
             predecessor: None,
+            room_type: None,
         };
 
         let json = json!({
@@ -84,6 +107,7 @@ mod tests {
             federate: true,
             room_version: RoomVersionId::version_4(),
             predecessor: None,
+            room_type: None,
         };
 
         let json = json!({
@@ -100,4 +124,80 @@ mod tests {
             content
         );
     }
+
+    #[test]
+    fn regular_room_round_trip() {
+        let json = json!({
+            "creator": "@carl:example.com",
+            "m.federate": false,
+            "room_version": "5"
+        });
+
+        let content = from_json_value::<EventResult<CreateEventContent>>(json.clone())
+            .unwrap()
+            .into_result()
+            .unwrap();
+
+        assert_eq!(content.room_type, None);
+        assert!(!content.is_space());
+        assert_eq!(to_json_value(&content).unwrap(), json);
+    }
+
+    #[test]
+    fn space_round_trip() {
+        let json = json!({
+            "creator": "@carl:example.com",
+            "m.federate": true,
+            "room_version": "6",
+            "type": "m.space"
+        });
+
+        let content = from_json_value::<EventResult<CreateEventContent>>(json.clone())
+            .unwrap()
+            .into_result()
+            .unwrap();
+
+        assert_eq!(content.room_type.as_ref().unwrap(), "m.space");
+        assert!(content.is_space());
+        assert_eq!(to_json_value(&content).unwrap(), json);
+    }
+
+    fn federates(json: serde_json::Value) -> bool {
+        let content = from_json_value::<EventResult<CreateEventContent>>(json.clone())
+            .unwrap()
+            .into_result()
+            .unwrap();
+
+        let mut expected = json;
+        expected["m.federate"] = content.federates().into();
+        assert_eq!(to_json_value(&content).unwrap(), expected);
+
+        content.federates()
+    }
+
+    #[test]
+    fn explicitly_not_federated() {
+        assert!(!federates(json!({
+            "creator": "@carl:example.com",
+            "m.federate": false,
+            "room_version": "5"
+        })));
+    }
+
+    #[test]
+    fn explicitly_federated() {
+        assert!(federates(json!({
+            "creator": "@carl:example.com",
+            "m.federate": true,
+            "room_version": "5"
+        })));
+    }
+
+    #[test]
+    fn federated_by_default() {
+        assert!(federates(json!({
+            "creator": "@carl:example.com",
+            "room_version": "5"
+        })));
+    }
 }
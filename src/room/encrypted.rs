@@ -1,12 +1,18 @@
 //! Types for the *m.room.encrypted* event.
-use std::collections::HashMap;
+use std::{
+    collections::HashMap,
+    fmt::{Debug, Formatter, Result as FmtResult},
+};
 
 use js_int::UInt;
 use ruma_identifiers::{DeviceId, EventId, RoomId, UserId};
 use serde::{Deserialize, Deserializer, Serialize, Serializer};
 use serde_json::{from_value, Map, Value};
 
-use crate::{Algorithm, EventType, FromRaw};
+use crate::{
+    util::{decode_base64, Redacted},
+    BadEvent, EventEncryptionAlgorithm, EventType, TryFromRaw,
+};
 
 /// This event type is used when sending encrypted events.
 ///
@@ -38,7 +44,9 @@ pub struct EncryptedEvent {
 }
 
 /// The payload for `EncryptedEvent`.
-#[derive(Clone, Debug, PartialEq)]
+///
+/// The `Debug` output doesn't contain the ciphertext.
+#[derive(Clone, PartialEq)]
 pub enum EncryptedEventContent {
     /// An event encrypted with *m.olm.v1.curve25519-aes-sha2*.
     OlmV1Curve25519AesSha2(OlmV1Curve25519AesSha2Content),
@@ -46,45 +54,92 @@ pub enum EncryptedEventContent {
     /// An event encrypted with *m.megolm.v1.aes-sha2*.
     MegolmV1AesSha2(MegolmV1AesSha2Content),
 
+    /// An event encrypted with an algorithm that ruma-events doesn't know about.
+    ///
+    /// The content is kept as-is, so it can be passed on untouched or decrypted by code that does
+    /// know about the algorithm.
+    Unknown {
+        /// The encryption algorithm used to encrypt this event.
+        algorithm: String,
+
+        /// The complete content of the event, including the `algorithm` field.
+        content: Value,
+    },
+
     /// Additional variants may be added in the future and will not be considered breaking changes
     /// to ruma-events.
     #[doc(hidden)]
     __Nonexhaustive,
 }
 
-impl FromRaw for EncryptedEvent {
+impl TryFromRaw for EncryptedEvent {
     type Raw = raw::EncryptedEvent;
+    type Err = BadEvent;
 
-    fn from_raw(raw: raw::EncryptedEvent) -> Self {
-        Self {
-            content: FromRaw::from_raw(raw.content),
+    fn try_from_raw(raw: raw::EncryptedEvent) -> Result<Self, Self::Err> {
+        Ok(Self {
+            content: TryFromRaw::try_from_raw(raw.content)?,
             event_id: raw.event_id,
             origin_server_ts: raw.origin_server_ts,
             room_id: raw.room_id,
             sender: raw.sender,
             unsigned: raw.unsigned,
-        }
+        })
     }
 }
 
-impl FromRaw for EncryptedEventContent {
+impl TryFromRaw for EncryptedEventContent {
     type Raw = raw::EncryptedEventContent;
+    type Err = BadEvent;
 
-    fn from_raw(raw: raw::EncryptedEventContent) -> Self {
+    fn try_from_raw(raw: raw::EncryptedEventContent) -> Result<Self, Self::Err> {
         use raw::EncryptedEventContent::*;
 
-        match raw {
+        let content = match raw {
             OlmV1Curve25519AesSha2(content) => {
                 EncryptedEventContent::OlmV1Curve25519AesSha2(content)
             }
-            MegolmV1AesSha2(content) => EncryptedEventContent::MegolmV1AesSha2(content),
+            MegolmV1AesSha2(content) => {
+                if !is_base64(&content.sender_key) {
+                    return Err(BadEvent::Validation {
+                        field: "sender_key",
+                        message: "must be a non-empty Base64 string".to_string(),
+                    });
+                }
+
+                EncryptedEventContent::MegolmV1AesSha2(content)
+            }
+            Unknown { algorithm, content } => EncryptedEventContent::Unknown { algorithm, content },
             __Nonexhaustive => {
                 unreachable!("__Nonexhaustive variant should be impossible to obtain.")
             }
-        }
+        };
+
+        Ok(content)
     }
 }
 
+/// Whether `key` is a non-empty Base64 string.
+///
+/// Padding is tolerated, as by `util::decode_base64`, and so is the URL-safe alphabet.
+fn is_base64(key: &str) -> bool {
+    !key.is_empty() && decode_sender_key(key).is_some()
+}
+
+/// Decodes a sender key, translating the URL-safe Base64 alphabet to the standard one.
+fn decode_sender_key(key: &str) -> Option<Vec<u8>> {
+    let key: String = key
+        .chars()
+        .map(|c| match c {
+            '-' => '+',
+            '_' => '/',
+            c => c,
+        })
+        .collect();
+
+    decode_base64(&key)
+}
+
 impl_room_event!(
     EncryptedEvent,
     EncryptedEventContent,
@@ -101,6 +156,7 @@ impl Serialize for EncryptedEventContent {
                 content.serialize(serializer)
             }
             EncryptedEventContent::MegolmV1AesSha2(ref content) => content.serialize(serializer),
+            EncryptedEventContent::Unknown { ref content, .. } => content.serialize(serializer),
             _ => panic!("Attempted to serialize __Nonexhaustive variant."),
         }
     }
@@ -123,6 +179,7 @@ pub(crate) mod raw {
 
         /// Timestamp (milliseconds since the UNIX epoch) on originating homeserver when this
         /// event was sent.
+        #[serde(deserialize_with = "crate::__private::uint_or_string")]
         pub origin_server_ts: UInt,
 
         /// The unique identifier for the room associated with this event.
@@ -145,6 +202,15 @@ pub(crate) mod raw {
         /// An event encrypted with *m.megolm.v1.aes-sha2*.
         MegolmV1AesSha2(MegolmV1AesSha2Content),
 
+        /// An event encrypted with an algorithm that ruma-events doesn't know about.
+        Unknown {
+            /// The encryption algorithm used to encrypt this event.
+            algorithm: String,
+
+            /// The complete content of the event.
+            content: Value,
+        },
+
         /// Additional variants may be added in the future and will not be considered breaking
         /// changes to ruma-events.
         #[doc(hidden)]
@@ -165,13 +231,13 @@ pub(crate) mod raw {
                 None => return Err(D::Error::missing_field("algorithm")),
             };
 
-            let method = match from_value::<Algorithm>(method_value) {
+            let method = match from_value::<EventEncryptionAlgorithm>(method_value) {
                 Ok(method) => method,
                 Err(error) => return Err(D::Error::custom(error.to_string())),
             };
 
             match method {
-                Algorithm::OlmV1Curve25519AesSha2 => {
+                EventEncryptionAlgorithm::OlmV1Curve25519AesSha2 => {
                     let content = match from_value::<OlmV1Curve25519AesSha2Content>(value) {
                         Ok(content) => content,
                         Err(error) => return Err(D::Error::custom(error.to_string())),
@@ -179,7 +245,7 @@ pub(crate) mod raw {
 
                     Ok(EncryptedEventContent::OlmV1Curve25519AesSha2(content))
                 }
-                Algorithm::MegolmV1AesSha2 => {
+                EventEncryptionAlgorithm::MegolmV1AesSha2 => {
                     let content = match from_value::<MegolmV1AesSha2Content>(value) {
                         Ok(content) => content,
                         Err(error) => return Err(D::Error::custom(error.to_string())),
@@ -187,10 +253,11 @@ pub(crate) mod raw {
 
                     Ok(EncryptedEventContent::MegolmV1AesSha2(content))
                 }
-                Algorithm::Custom(_) => Err(D::Error::custom(
-                    "Custom algorithms are not supported by `EncryptedEventContent`.",
-                )),
-                Algorithm::__Nonexhaustive => Err(D::Error::custom(
+                EventEncryptionAlgorithm::Custom(algorithm) => Ok(EncryptedEventContent::Unknown {
+                    algorithm,
+                    content: value,
+                }),
+                EventEncryptionAlgorithm::__Nonexhaustive => Err(D::Error::custom(
                     "Attempted to deserialize __Nonexhaustive variant.",
                 )),
             }
@@ -202,9 +269,10 @@ pub(crate) mod raw {
 #[derive(Clone, Debug, Serialize, PartialEq, Deserialize)]
 pub struct OlmV1Curve25519AesSha2Content {
     /// The encryption algorithm used to encrypt this event.
-    pub algorithm: Algorithm,
+    pub algorithm: EventEncryptionAlgorithm,
 
     /// A map from the recipient Curve25519 identity key to ciphertext information.
+    #[serde(serialize_with = "crate::util::sorted_map")]
     pub ciphertext: HashMap<String, CiphertextInfo>,
 
     /// The Curve25519 key of the sender.
@@ -214,7 +282,9 @@ pub struct OlmV1Curve25519AesSha2Content {
 /// Ciphertext information holding the ciphertext and message type.
 ///
 /// Used for messages encrypted with the *m.olm.v1.curve25519-aes-sha2* algorithm.
-#[derive(Clone, Debug, Deserialize, PartialEq, Serialize)]
+///
+/// The `Debug` output doesn't contain the ciphertext.
+#[derive(Clone, Deserialize, PartialEq, Serialize)]
 pub struct CiphertextInfo {
     /// The encrypted payload.
     pub body: String,
@@ -225,10 +295,12 @@ pub struct CiphertextInfo {
 }
 
 /// The payload for `EncryptedEvent` using the *m.megolm.v1.aes-sha2* algorithm.
-#[derive(Clone, Debug, Serialize, PartialEq, Deserialize)]
+///
+/// The `Debug` output doesn't contain the ciphertext.
+#[derive(Clone, Serialize, PartialEq, Deserialize)]
 pub struct MegolmV1AesSha2Content {
     /// The encryption algorithm used to encrypt this event.
-    pub algorithm: Algorithm,
+    pub algorithm: EventEncryptionAlgorithm,
 
     /// The encrypted content of the event.
     pub ciphertext: String,
@@ -243,18 +315,75 @@ pub struct MegolmV1AesSha2Content {
     pub session_id: String,
 }
 
+impl MegolmV1AesSha2Content {
+    /// Whether `sender_key` decodes to 32 bytes, the length of a Curve25519 public key.
+    ///
+    /// Deserialization only checks that `sender_key` is non-empty Base64, so this can be used as an
+    /// additional, stricter check.
+    pub fn has_curve25519_sender_key(&self) -> bool {
+        match decode_sender_key(&self.sender_key) {
+            Some(key) => key.len() == 32,
+            None => false,
+        }
+    }
+}
+
+impl Debug for EncryptedEventContent {
+    fn fmt(&self, f: &mut Formatter<'_>) -> FmtResult {
+        match self {
+            EncryptedEventContent::OlmV1Curve25519AesSha2(content) => f
+                .debug_tuple("OlmV1Curve25519AesSha2")
+                .field(content)
+                .finish(),
+            EncryptedEventContent::MegolmV1AesSha2(content) => {
+                f.debug_tuple("MegolmV1AesSha2").field(content).finish()
+            }
+            EncryptedEventContent::Unknown { algorithm, .. } => f
+                .debug_struct("Unknown")
+                .field("algorithm", algorithm)
+                .field("content", &Redacted)
+                .finish(),
+            EncryptedEventContent::__Nonexhaustive => f.write_str("__Nonexhaustive"),
+        }
+    }
+}
+
+impl Debug for CiphertextInfo {
+    fn fmt(&self, f: &mut Formatter<'_>) -> FmtResult {
+        f.debug_struct("CiphertextInfo")
+            .field("body", &Redacted)
+            .field("message_type", &self.message_type)
+            .finish()
+    }
+}
+
+impl Debug for MegolmV1AesSha2Content {
+    fn fmt(&self, f: &mut Formatter<'_>) -> FmtResult {
+        f.debug_struct("MegolmV1AesSha2Content")
+            .field("algorithm", &self.algorithm)
+            .field("ciphertext", &Redacted)
+            .field("sender_key", &self.sender_key)
+            .field("device_id", &self.device_id)
+            .field("session_id", &self.session_id)
+            .finish()
+    }
+}
+
 #[cfg(test)]
 mod tests {
-    use serde_json::{from_value as from_json_value, json, to_value as to_json_value};
+    use serde_json::{
+        from_value as from_json_value, json, to_string as to_json_string,
+        to_value as to_json_value, Map, Value,
+    };
 
-    use super::{Algorithm, EncryptedEventContent, MegolmV1AesSha2Content};
-    use crate::EventResult;
+    use super::{EncryptedEventContent, EventEncryptionAlgorithm, MegolmV1AesSha2Content};
+    use crate::{util::assert_deterministic_serialization, EventResult};
 
     #[test]
     fn serializtion() {
         let key_verification_start_content =
             EncryptedEventContent::MegolmV1AesSha2(MegolmV1AesSha2Content {
-                algorithm: Algorithm::MegolmV1AesSha2,
+                algorithm: EventEncryptionAlgorithm::MegolmV1AesSha2,
                 ciphertext: "ciphertext".to_string(),
                 sender_key: "sender_key".to_string(),
                 device_id: "device_id".to_string(),
@@ -279,7 +408,7 @@ mod tests {
     fn deserialization() {
         let key_verification_start_content =
             EncryptedEventContent::MegolmV1AesSha2(MegolmV1AesSha2Content {
-                algorithm: Algorithm::MegolmV1AesSha2,
+                algorithm: EventEncryptionAlgorithm::MegolmV1AesSha2,
                 ciphertext: "ciphertext".to_string(),
                 sender_key: "sender_key".to_string(),
                 device_id: "device_id".to_string(),
@@ -322,7 +451,10 @@ mod tests {
 
         match content {
             EncryptedEventContent::OlmV1Curve25519AesSha2(c) => {
-                assert_eq!(c.algorithm, Algorithm::OlmV1Curve25519AesSha2);
+                assert_eq!(
+                    c.algorithm,
+                    EventEncryptionAlgorithm::OlmV1Curve25519AesSha2
+                );
                 assert_eq!(c.sender_key, "test_key");
                 assert_eq!(c.ciphertext.len(), 1);
                 assert_eq!(c.ciphertext["test_curve_key"].body, "encrypted_body");
@@ -341,4 +473,138 @@ mod tests {
         .into_result()
         .is_err());
     }
+
+    #[test]
+    fn unknown_algorithm_round_trip() {
+        let json = r#"{"algorithm":"com.example.future-algo","ciphertext":{"body":"c2VjcmV0","nonce":"bm9uY2U"},"session":{"epoch":3,"id":"abc"}}"#;
+
+        let content = serde_json::from_str::<EventResult<EncryptedEventContent>>(json)
+            .unwrap()
+            .into_result()
+            .unwrap();
+
+        match &content {
+            EncryptedEventContent::Unknown { algorithm, .. } => {
+                assert_eq!(algorithm, "com.example.future-algo")
+            }
+            _ => panic!("expected content with an unknown algorithm"),
+        }
+        assert_eq!(to_json_string(&content).unwrap(), json);
+    }
+
+    #[test]
+    fn deterministic_olm_serialization() {
+        let ciphertext: Map<String, Value> = (0..8)
+            .map(|i| {
+                let info = json!({ "body": format!("ciphertext{}", i), "type": 0 });
+                (format!("recipient_key_{}", i), info)
+            })
+            .collect();
+
+        assert_deterministic_serialization::<EncryptedEventContent>(json!({
+            "algorithm": "m.olm.v1.curve25519-aes-sha2",
+            "ciphertext": ciphertext,
+            "sender_key": "sender_key"
+        }));
+    }
+
+    #[test]
+    fn debug_hides_ciphertext() {
+        let megolm = EncryptedEventContent::MegolmV1AesSha2(MegolmV1AesSha2Content {
+            algorithm: EventEncryptionAlgorithm::MegolmV1AesSha2,
+            ciphertext: "secret_ciphertext".to_string(),
+            sender_key: "sender_key".to_string(),
+            device_id: "device_id".to_string(),
+            session_id: "session_id".to_string(),
+        });
+        let debug = format!("{:?}", megolm);
+
+        assert!(debug.contains("session_id"));
+        assert!(!debug.contains("secret_ciphertext"));
+
+        let olm = from_json_value::<EventResult<EncryptedEventContent>>(json!({
+            "algorithm": "m.olm.v1.curve25519-aes-sha2",
+            "ciphertext": {
+                "recipient_key": { "body": "secret_ciphertext", "type": 0 }
+            },
+            "sender_key": "sender_key"
+        }))
+        .unwrap()
+        .into_result()
+        .unwrap();
+        let debug = format!("{:?}", olm);
+
+        assert!(debug.contains("recipient_key"));
+        assert!(!debug.contains("secret_ciphertext"));
+
+        let unknown = from_json_value::<EventResult<EncryptedEventContent>>(json!({
+            "algorithm": "org.example.custom",
+            "ciphertext": "secret_ciphertext"
+        }))
+        .unwrap()
+        .into_result()
+        .unwrap();
+        let debug = format!("{:?}", unknown);
+
+        assert!(debug.contains("org.example.custom"));
+        assert!(!debug.contains("secret_ciphertext"));
+    }
+
+    fn megolm_with_sender_key(sender_key: &str) -> EventResult<EncryptedEventContent> {
+        from_json_value(json!({
+            "algorithm": "m.megolm.v1.aes-sha2",
+            "ciphertext": "ciphertext",
+            "sender_key": sender_key,
+            "device_id": "device_id",
+            "session_id": "session_id"
+        }))
+        .unwrap()
+    }
+
+    #[test]
+    fn well_formed_sender_key() {
+        for sender_key in &[
+            "RF3s+E7RkTQTGF2d8Deol0FkQvgII2aJDf3/Jp5mxVU",
+            "RF3s+E7RkTQTGF2d8Deol0FkQvgII2aJDf3/Jp5mxVU=",
+            "RF3s-E7RkTQTGF2d8Deol0FkQvgII2aJDf3_Jp5mxVU",
+            "RF3s+E7RkTQTGF2d8Deol0Fk",
+        ] {
+            assert!(megolm_with_sender_key(sender_key).into_result().is_ok());
+        }
+    }
+
+    #[test]
+    fn malformed_sender_key() {
+        for sender_key in &["", "not base64!", "RF3s+"] {
+            let error = megolm_with_sender_key(sender_key)
+                .into_result()
+                .unwrap_err();
+
+            assert!(error.is_validation());
+            assert_eq!(
+                error.message(),
+                "`sender_key` must be a non-empty Base64 string"
+            );
+        }
+    }
+
+    #[test]
+    fn curve25519_sender_key() {
+        let sender_key_is_curve25519 = |sender_key| match megolm_with_sender_key(sender_key)
+            .into_result()
+            .unwrap()
+        {
+            EncryptedEventContent::MegolmV1AesSha2(content) => content.has_curve25519_sender_key(),
+            other => panic!("unexpected encrypted content: {:?}", other),
+        };
+
+        assert!(sender_key_is_curve25519(
+            "RF3s+E7RkTQTGF2d8Deol0FkQvgII2aJDf3/Jp5mxVU"
+        ));
+        assert!(sender_key_is_curve25519(
+            "RF3s-E7RkTQTGF2d8Deol0FkQvgII2aJDf3_Jp5mxVU="
+        ));
+        assert!(!sender_key_is_curve25519("RF3s+E7RkTQTGF2d8Deol0Fk"));
+        assert!(!sender_key_is_curve25519("sender_key"));
+    }
 }
@@ -1,9 +1,11 @@
 //! Types for the *m.room.encryption* event.
 
+use std::time::Duration;
+
 use js_int::UInt;
 use ruma_events_macros::ruma_event;
 
-use crate::Algorithm;
+use crate::EventEncryptionAlgorithm;
 
 ruma_event! {
     /// Defines how messages sent in this room should be encrypted.
@@ -14,17 +16,120 @@ ruma_event! {
             /// The encryption algorithm to be used to encrypt messages sent in this room.
             ///
             /// Must be `m.megolm.v1.aes-sha2`.
-            pub algorithm: Algorithm,
+            pub algorithm: EventEncryptionAlgorithm,
 
             /// How long the session should be used before changing it.
             ///
             /// 604800000 (a week) is the recommended default.
+            #[serde(skip_serializing_if = "Option::is_none")]
             pub rotation_period_ms: Option<UInt>,
 
             /// How many messages should be sent before changing the session.
             ///
             /// 100 is the recommended default.
+            #[serde(skip_serializing_if = "Option::is_none")]
             pub rotation_period_msgs: Option<UInt>,
         },
     }
 }
+
+impl EncryptionEventContent {
+    /// Whether the encryption algorithm is one that is defined by the specification.
+    pub fn is_supported(&self) -> bool {
+        match self.algorithm {
+            EventEncryptionAlgorithm::OlmV1Curve25519AesSha2
+            | EventEncryptionAlgorithm::MegolmV1AesSha2 => true,
+            _ => false,
+        }
+    }
+
+    /// How long a session should be used before changing it, defaulting to a week.
+    pub fn rotation_period(&self) -> Duration {
+        let millis = self
+            .rotation_period_ms
+            .map_or(DEFAULT_ROTATION_PERIOD_MS, u64::from);
+
+        Duration::from_millis(millis)
+    }
+
+    /// How many messages should be sent before changing the session, defaulting to 100.
+    pub fn rotation_messages(&self) -> UInt {
+        self.rotation_period_msgs
+            .unwrap_or_else(|| UInt::from(DEFAULT_ROTATION_PERIOD_MSGS))
+    }
+}
+
+/// The recommended default of `rotation_period_ms`, a week.
+const DEFAULT_ROTATION_PERIOD_MS: u64 = 604_800_000;
+
+/// The recommended default of `rotation_period_msgs`.
+const DEFAULT_ROTATION_PERIOD_MSGS: u32 = 100;
+
+#[cfg(test)]
+mod tests {
+    use std::{convert::TryFrom, time::Duration};
+
+    use js_int::UInt;
+    use serde_json::json;
+
+    use super::EncryptionEventContent;
+    use crate::{util::serde_json_eq_try_from_raw, EventEncryptionAlgorithm};
+
+    #[test]
+    fn megolm_round_trip() {
+        let content = EncryptionEventContent {
+            algorithm: EventEncryptionAlgorithm::MegolmV1AesSha2,
+            rotation_period_ms: Some(UInt::try_from(604_800_000).unwrap()),
+            rotation_period_msgs: Some(UInt::try_from(100).unwrap()),
+        };
+
+        assert!(content.is_supported());
+        serde_json_eq_try_from_raw(
+            content,
+            json!({
+                "algorithm": "m.megolm.v1.aes-sha2",
+                "rotation_period_ms": 604_800_000,
+                "rotation_period_msgs": 100
+            }),
+        );
+    }
+
+    #[test]
+    fn unknown_algorithm_round_trip() {
+        let content = EncryptionEventContent {
+            algorithm: EventEncryptionAlgorithm::Custom("io.ruma.test".to_string()),
+            rotation_period_ms: None,
+            rotation_period_msgs: None,
+        };
+
+        assert!(!content.is_supported());
+        serde_json_eq_try_from_raw(content, json!({ "algorithm": "io.ruma.test" }));
+    }
+
+    #[test]
+    fn explicit_rotation_policy() {
+        let content = EncryptionEventContent {
+            algorithm: EventEncryptionAlgorithm::MegolmV1AesSha2,
+            rotation_period_ms: Some(UInt::from(3_600_000u32)),
+            rotation_period_msgs: Some(UInt::from(10u32)),
+        };
+
+        assert_eq!(content.rotation_period(), Duration::from_secs(3600));
+        assert_eq!(content.rotation_messages(), UInt::from(10u32));
+    }
+
+    #[test]
+    fn default_rotation_policy() {
+        let content = EncryptionEventContent {
+            algorithm: EventEncryptionAlgorithm::MegolmV1AesSha2,
+            rotation_period_ms: None,
+            rotation_period_msgs: None,
+        };
+
+        assert_eq!(
+            content.rotation_period(),
+            Duration::from_secs(7 * 24 * 60 * 60)
+        );
+        assert_eq!(content.rotation_messages(), UInt::from(100u32));
+    }
+}
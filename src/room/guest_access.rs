@@ -42,3 +42,48 @@ impl_enum! {
         Forbidden => "forbidden",
     }
 }
+
+impl Default for GuestAccess {
+    /// Servers act as if guests are forbidden if there is no *m.room.guest_access* event.
+    fn default() -> Self {
+        GuestAccess::Forbidden
+    }
+}
+
+impl GuestAccessEventContent {
+    /// Creates a new `GuestAccessEventContent` with the given policy.
+    pub fn new(guest_access: GuestAccess) -> Self {
+        Self { guest_access }
+    }
+}
+
+impl Default for GuestAccessEventContent {
+    fn default() -> Self {
+        Self::new(GuestAccess::default())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use serde_json::json;
+
+    use super::{GuestAccess, GuestAccessEventContent};
+    use crate::util::serde_json_eq_try_from_raw;
+
+    #[test]
+    fn default_is_forbidden() {
+        assert_eq!(GuestAccess::default(), GuestAccess::Forbidden);
+        serde_json_eq_try_from_raw(
+            GuestAccessEventContent::default(),
+            json!({ "guest_access": "forbidden" }),
+        );
+    }
+
+    #[test]
+    fn new() {
+        assert_eq!(
+            GuestAccessEventContent::new(GuestAccess::CanJoin).guest_access,
+            GuestAccess::CanJoin
+        );
+    }
+}
@@ -3,6 +3,8 @@
 use ruma_events_macros::ruma_event;
 use serde::{Deserialize, Serialize};
 
+use crate::collections::all::StateEvent;
+
 ruma_event! {
     /// This event controls whether a member of a room can see the events that happened in a room
     /// from before they joined.
@@ -56,3 +58,182 @@ impl_enum! {
         WorldReadable => "world_readable",
     }
 }
+
+impl Default for HistoryVisibility {
+    /// Clients and servers act as if the history is shared if there is no
+    /// *m.room.history_visibility* event.
+    fn default() -> Self {
+        HistoryVisibility::Shared
+    }
+}
+
+/// A preset of a `POST /createRoom` request, which determines the initial state of the new room.
+#[derive(Clone, Copy, Debug, Deserialize, PartialEq, Serialize)]
+pub enum RoomPreset {
+    /// Only invited users can join, and guests are allowed.
+    #[serde(rename = "private_chat")]
+    PrivateChat,
+
+    /// Like `PrivateChat`, but all invited users get the same power level as the creator.
+    #[serde(rename = "trusted_private_chat")]
+    TrustedPrivateChat,
+
+    /// Anyone can join, and guests are forbidden.
+    #[serde(rename = "public_chat")]
+    PublicChat,
+
+    /// Additional variants may be added in the future and will not be considered breaking changes
+    /// to ruma-events.
+    #[doc(hidden)]
+    #[serde(skip)]
+    __Nonexhaustive,
+}
+
+impl_enum! {
+    RoomPreset {
+        PrivateChat => "private_chat",
+        TrustedPrivateChat => "trusted_private_chat",
+        PublicChat => "public_chat",
+    }
+}
+
+impl HistoryVisibilityEventContent {
+    /// Creates a new `HistoryVisibilityEventContent` with the given visibility.
+    pub fn new(history_visibility: HistoryVisibility) -> Self {
+        Self { history_visibility }
+    }
+
+    /// Creates the `HistoryVisibilityEventContent` a homeserver sets for rooms created with the
+    /// given preset.
+    ///
+    /// All presets of the specification use `HistoryVisibility::Shared`.
+    pub fn for_preset(preset: RoomPreset) -> Self {
+        match preset {
+            RoomPreset::PrivateChat | RoomPreset::TrustedPrivateChat | RoomPreset::PublicChat => {
+                Self::new(HistoryVisibility::Shared)
+            }
+            RoomPreset::__Nonexhaustive => {
+                panic!("__Nonexhaustive enum variant is not intended for use.")
+            }
+        }
+    }
+}
+
+impl Default for HistoryVisibilityEventContent {
+    fn default() -> Self {
+        Self::new(HistoryVisibility::default())
+    }
+}
+
+/// Whether the given room state makes the room's history readable by anyone.
+///
+/// This scans `events` for the *m.room.history_visibility* event and checks whether it is set to
+/// `HistoryVisibility::WorldReadable`. A room without such an event is not world readable.
+pub fn room_is_world_readable(events: &[StateEvent]) -> bool {
+    events.iter().any(|event| match event {
+        StateEvent::RoomHistoryVisibility(event) => {
+            event.state_key.is_empty()
+                && event.content.history_visibility == HistoryVisibility::WorldReadable
+        }
+        _ => false,
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use serde_json::{from_value as from_json_value, json, Value};
+
+    use super::{
+        room_is_world_readable, HistoryVisibility, HistoryVisibilityEventContent, RoomPreset,
+    };
+    use crate::{collections::all::StateEvent, util::serde_json_eq_try_from_raw, EventResult};
+
+    fn state(history_visibility: &str) -> Vec<StateEvent> {
+        let events: Vec<Value> = vec![
+            json!({
+                "content": { "topic": "Testing room" },
+                "event_id": "$h29iv0s8:example.com",
+                "origin_server_ts": 1,
+                "sender": "@carl:example.com",
+                "state_key": "",
+                "type": "m.room.topic"
+            }),
+            json!({
+                "content": { "history_visibility": history_visibility },
+                "event_id": "$h29iv0s9:example.com",
+                "origin_server_ts": 1,
+                "sender": "@carl:example.com",
+                "state_key": "",
+                "type": "m.room.history_visibility"
+            }),
+        ];
+
+        events
+            .into_iter()
+            .map(|event| {
+                from_json_value::<EventResult<StateEvent>>(event)
+                    .unwrap()
+                    .into_result()
+                    .unwrap()
+            })
+            .collect()
+    }
+
+    #[test]
+    fn world_readable_room() {
+        assert!(room_is_world_readable(&state("world_readable")));
+    }
+
+    #[test]
+    fn shared_history_room() {
+        assert!(!room_is_world_readable(&state("shared")));
+    }
+
+    #[test]
+    fn room_without_history_visibility() {
+        assert!(!room_is_world_readable(&[]));
+    }
+
+    #[test]
+    fn default_is_shared() {
+        assert_eq!(HistoryVisibility::default(), HistoryVisibility::Shared);
+        serde_json_eq_try_from_raw(
+            HistoryVisibilityEventContent::default(),
+            json!({ "history_visibility": "shared" }),
+        );
+    }
+
+    #[test]
+    fn new() {
+        assert_eq!(
+            HistoryVisibilityEventContent::new(HistoryVisibility::Joined).history_visibility,
+            HistoryVisibility::Joined
+        );
+    }
+
+    #[test]
+    fn private_chat_preset() {
+        assert_eq!(
+            HistoryVisibilityEventContent::for_preset(RoomPreset::PrivateChat).history_visibility,
+            HistoryVisibility::Shared
+        );
+    }
+
+    #[test]
+    fn trusted_private_chat_preset() {
+        assert_eq!(
+            HistoryVisibilityEventContent::for_preset(RoomPreset::TrustedPrivateChat)
+                .history_visibility,
+            HistoryVisibility::Shared
+        );
+    }
+
+    #[test]
+    fn public_chat_preset() {
+        assert_eq!(
+            HistoryVisibilityEventContent::for_preset(RoomPreset::PublicChat).history_visibility,
+            HistoryVisibility::Shared
+        );
+        assert_eq!(RoomPreset::PublicChat.to_string(), "public_chat");
+    }
+}
@@ -1,7 +1,12 @@
 //! Types for the *m.room.join_rules* event.
 
 use ruma_events_macros::ruma_event;
-use serde::{Deserialize, Serialize};
+use ruma_identifiers::RoomId;
+use serde::{
+    ser::{Error as _, SerializeStruct},
+    Deserialize, Deserializer, Serialize, Serializer,
+};
+use serde_json::Value;
 
 ruma_event! {
     /// Describes how users are allowed to join the room.
@@ -11,6 +16,11 @@ ruma_event! {
         content: {
             /// The type of rules used for users wishing to join this room.
             pub join_rule: JoinRule,
+
+            /// The conditions under which users may join a `restricted` room.
+            #[serde(default)]
+            #[serde(skip_serializing_if = "Vec::is_empty")]
+            pub allow: Vec<AllowCondition>,
         },
     }
 }
@@ -35,6 +45,11 @@ pub enum JoinRule {
     #[serde(rename = "public")]
     Public,
 
+    /// Users can join the room if they fulfill one of the conditions in `allow`, or if they are
+    /// invited.
+    #[serde(rename = "restricted")]
+    Restricted,
+
     /// Additional variants may be added in the future and will not be considered breaking changes
     /// to ruma-events.
     #[doc(hidden)]
@@ -48,5 +63,175 @@ impl_enum! {
         Knock => "knock",
         Private => "private",
         Public => "public",
+        Restricted => "restricted",
+    }
+}
+
+/// A condition under which users may join a `restricted` room.
+#[derive(Clone, Debug, PartialEq)]
+pub enum AllowCondition {
+    /// Users may join if they are a member of the given room.
+    RoomMembership {
+        /// The room whose members may join.
+        room_id: RoomId,
+    },
+
+    /// A condition of a type that is not part of the specification, or a malformed
+    /// `m.room_membership` condition, as JSON.
+    Custom(Value),
+
+    /// Additional variants may be added in the future and will not be considered breaking changes
+    /// to ruma-events.
+    #[doc(hidden)]
+    __Nonexhaustive,
+}
+
+impl JoinRulesEventContent {
+    /// The rooms whose members may join this room through one of the `m.room_membership`
+    /// conditions in `allow`.
+    ///
+    /// Conditions of other types are ignored.
+    pub fn allowed_via_rooms(&self) -> Vec<&RoomId> {
+        self.allow
+            .iter()
+            .filter_map(|condition| match condition {
+                AllowCondition::RoomMembership { room_id } => Some(room_id),
+                AllowCondition::Custom(_) | AllowCondition::__Nonexhaustive => None,
+            })
+            .collect()
+    }
+}
+
+impl Serialize for AllowCondition {
+    fn serialize<S>(&self, serializer: S) -> Result<S::Ok, S::Error>
+    where
+        S: Serializer,
+    {
+        match self {
+            AllowCondition::RoomMembership { room_id } => {
+                let mut state = serializer.serialize_struct("AllowCondition", 2)?;
+
+                state.serialize_field("type", "m.room_membership")?;
+                state.serialize_field("room_id", room_id)?;
+
+                state.end()
+            }
+            AllowCondition::Custom(condition) => condition.serialize(serializer),
+            AllowCondition::__Nonexhaustive => Err(S::Error::custom(
+                "Attempted to serialize __Nonexhaustive variant.",
+            )),
+        }
+    }
+}
+
+impl<'de> Deserialize<'de> for AllowCondition {
+    fn deserialize<D>(deserializer: D) -> Result<Self, D::Error>
+    where
+        D: Deserializer<'de>,
+    {
+        #[derive(Deserialize)]
+        struct RoomMembership {
+            room_id: RoomId,
+        }
+
+        let value = Value::deserialize(deserializer)?;
+
+        if value.get("type").and_then(Value::as_str) != Some("m.room_membership") {
+            return Ok(AllowCondition::Custom(value));
+        }
+
+        // A malformed condition is kept as is rather than failing the whole event.
+        match RoomMembership::deserialize(&value) {
+            Ok(RoomMembership { room_id }) => Ok(AllowCondition::RoomMembership { room_id }),
+            Err(_) => Ok(AllowCondition::Custom(value)),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::convert::TryFrom;
+
+    use ruma_identifiers::RoomId;
+    use serde_json::{from_value as from_json_value, json};
+
+    use super::{AllowCondition, JoinRule, JoinRulesEventContent};
+    use crate::{util::try_from_raw_round_trip, EventResult};
+
+    #[test]
+    fn restricted_with_two_allow_rooms() {
+        let json = json!({
+            "join_rule": "restricted",
+            "allow": [
+                { "type": "m.room_membership", "room_id": "!first:example.com" },
+                { "type": "io.ruma.custom", "room_id": "!ignored:example.com" },
+                { "type": "m.room_membership", "room_id": "!second:example.com" }
+            ]
+        });
+
+        let content = from_json_value::<EventResult<JoinRulesEventContent>>(json.clone())
+            .unwrap()
+            .into_result()
+            .unwrap();
+
+        assert_eq!(content.join_rule, JoinRule::Restricted);
+        assert_eq!(
+            content.allowed_via_rooms(),
+            vec![
+                &RoomId::try_from("!first:example.com").unwrap(),
+                &RoomId::try_from("!second:example.com").unwrap(),
+            ]
+        );
+        assert!(match content.allow[1] {
+            AllowCondition::Custom(_) => true,
+            _ => false,
+        });
+        try_from_raw_round_trip::<JoinRulesEventContent>(json);
+    }
+
+    #[test]
+    fn malformed_room_membership_is_kept() {
+        let json = json!({
+            "join_rule": "restricted",
+            "allow": [
+                { "type": "m.room_membership" },
+                { "type": "m.room_membership", "room_id": "not a room ID" },
+                { "type": "m.room_membership", "room_id": "!room:example.com" }
+            ]
+        });
+
+        let content = from_json_value::<EventResult<JoinRulesEventContent>>(json.clone())
+            .unwrap()
+            .into_result()
+            .unwrap();
+
+        assert_eq!(
+            content.allow[0],
+            AllowCondition::Custom(json!({ "type": "m.room_membership" }))
+        );
+        assert_eq!(
+            content.allow[1],
+            AllowCondition::Custom(
+                json!({ "type": "m.room_membership", "room_id": "not a room ID" })
+            )
+        );
+        assert_eq!(
+            content.allowed_via_rooms(),
+            vec![&RoomId::try_from("!room:example.com").unwrap()]
+        );
+        try_from_raw_round_trip::<JoinRulesEventContent>(json);
+    }
+
+    #[test]
+    fn public_has_no_allow_rooms() {
+        let json = json!({ "join_rule": "public" });
+
+        let content = from_json_value::<EventResult<JoinRulesEventContent>>(json.clone())
+            .unwrap()
+            .into_result()
+            .unwrap();
+
+        assert!(content.allowed_via_rooms().is_empty());
+        try_from_raw_round_trip::<JoinRulesEventContent>(json);
     }
 }
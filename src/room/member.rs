@@ -1,10 +1,10 @@
 //! Types for the *m.room.member* event.
 
-use std::collections::HashMap;
+use std::collections::{BTreeMap, HashMap};
 
 use ruma_events_macros::ruma_event;
 use ruma_identifiers::UserId;
-use serde::{Deserialize, Serialize};
+use serde::{Deserialize, Serialize, Serializer};
 
 ruma_event! {
     /// The current membership state of a user in the room.
@@ -52,6 +52,18 @@ ruma_event! {
             /// The membership state of this user.
             pub membership: MembershipState,
 
+            /// The user that authorised this join on the joining user's homeserver, for joins of
+            /// rooms with the *restricted* join rule.
+            #[serde(
+                rename = "join_authorised_via_users_server",
+                skip_serializing_if = "Option::is_none"
+            )]
+            pub join_authorized_via_users_server: Option<UserId>,
+
+            /// The reason for the membership change, if any. Typically set on kicks and bans.
+            #[serde(skip_serializing_if = "Option::is_none")]
+            pub reason: Option<String>,
+
             /// If this member event is the successor to a third party invitation, this field will
             /// contain information about that invitation.
             #[serde(skip_serializing_if = "Option::is_none")]
@@ -123,12 +135,29 @@ pub struct SignedContent {
 
     /// A single signature from the verifying server, in the format specified by the Signing Events
     /// section of the server-server API.
+    #[serde(serialize_with = "serialize_signatures")]
     pub signatures: HashMap<String, HashMap<String, String>>,
 
     /// The token property of the containing third_party_invite object.
     pub token: String,
 }
 
+/// Serializes the signatures of a `SignedContent` sorted by server name and key ID.
+fn serialize_signatures<S>(
+    signatures: &HashMap<String, HashMap<String, String>>,
+    serializer: S,
+) -> Result<S::Ok, S::Error>
+where
+    S: Serializer,
+{
+    let sorted: BTreeMap<&String, BTreeMap<&String, &String>> = signatures
+        .iter()
+        .map(|(server_name, keys)| (server_name, keys.iter().collect()))
+        .collect();
+
+    sorted.serialize(serializer)
+}
+
 /// Translation of the membership change in `m.room.member` event.
 #[derive(Clone, Copy, Debug, Deserialize, PartialEq, Serialize)]
 pub enum MembershipChange {
@@ -177,6 +206,126 @@ pub enum MembershipChange {
     __Nonexhaustive,
 }
 
+impl MemberEventContent {
+    /// A convenience constructor to create the content of a ban, with an optional reason.
+    pub fn ban(reason: Option<String>) -> Self {
+        Self::with_membership(MembershipState::Ban, reason)
+    }
+
+    /// A convenience constructor to create the content of a leave or kick, with an optional
+    /// reason.
+    pub fn leave(reason: Option<String>) -> Self {
+        Self::with_membership(MembershipState::Leave, reason)
+    }
+
+    /// Whether the room was created with the intention of being a direct chat.
+    ///
+    /// Defaults to `false` if `is_direct` is not set.
+    pub fn is_direct(&self) -> bool {
+        self.is_direct.unwrap_or(false)
+    }
+
+    /// The display name with control, format (including bidirectional and zero-width) and other
+    /// invisible characters removed, as it should be rendered.
+    ///
+    /// These characters can be used to make a display name look like that of another user. The
+    /// `displayname` itself is left untouched. Returns `None` if there is no display name or
+    /// nothing is left of it.
+    pub fn sanitized_display_name(&self) -> Option<String> {
+        let sanitized: String = self
+            .displayname
+            .as_ref()?
+            .chars()
+            .filter(|&c| !is_spoofing_char(c))
+            .collect();
+
+        if sanitized.is_empty() {
+            None
+        } else {
+            Some(sanitized)
+        }
+    }
+
+    fn with_membership(membership: MembershipState, reason: Option<String>) -> Self {
+        Self {
+            avatar_url: None,
+            displayname: None,
+            is_direct: None,
+            membership,
+            join_authorized_via_users_server: None,
+            reason,
+            third_party_invite: None,
+        }
+    }
+}
+
+/// Whether `c` is invisible or changes the direction of the text around it.
+///
+/// This covers control characters, all format characters (general category Cf), the line and
+/// paragraph separators and the Hangul fillers, which render as blank space.
+fn is_spoofing_char(c: char) -> bool {
+    match c {
+        // Format characters: soft hyphen, prepended number signs, bidirectional marks,
+        // embeddings, overrides and isolates, zero-width characters, invisible operators,
+        // deprecated format characters, the byte order mark, interlinear annotation characters,
+        // shorthand format controls, musical symbol formatting and the tag characters.
+        '\u{00AD}'
+        | '\u{0600}'..='\u{0605}'
+        | '\u{061C}'
+        | '\u{06DD}'
+        | '\u{070F}'
+        | '\u{0890}'..='\u{0891}'
+        | '\u{08E2}'
+        | '\u{180E}'
+        | '\u{200B}'..='\u{200F}'
+        | '\u{202A}'..='\u{202E}'
+        | '\u{2060}'..='\u{2064}'
+        | '\u{2066}'..='\u{206F}'
+        | '\u{FEFF}'
+        | '\u{FFF9}'..='\u{FFFB}'
+        | '\u{110BD}'
+        | '\u{110CD}'
+        | '\u{13430}'..='\u{1343F}'
+        | '\u{1BCA0}'..='\u{1BCA3}'
+        | '\u{1D173}'..='\u{1D17A}'
+        | '\u{E0000}'..='\u{E007F}' => true,
+        // Line and paragraph separators.
+        '\u{2028}' | '\u{2029}' => true,
+        // Hangul choseong and jungseong fillers, Hangul filler and halfwidth Hangul filler.
+        '\u{115F}' | '\u{1160}' | '\u{3164}' | '\u{FFA0}' => true,
+        c => c.is_control(),
+    }
+}
+
+/// Returns the name to display for `user`, disambiguated against the other members of the room.
+///
+/// This is the plain display name if no other joined or invited member in `all_members` uses the
+/// same one, and the display name followed by the user ID in parentheses otherwise. If `member`
+/// has no display name, the user ID itself is returned.
+pub fn disambiguated_display_name(
+    member: &MemberEventContent,
+    user: &UserId,
+    all_members: &HashMap<UserId, MemberEventContent>,
+) -> String {
+    let displayname = match &member.displayname {
+        Some(displayname) => displayname,
+        None => return user.to_string(),
+    };
+
+    let collides = all_members.iter().any(|(other_user, other)| {
+        other_user != user
+            && (other.membership == MembershipState::Join
+                || other.membership == MembershipState::Invite)
+            && other.displayname.as_ref() == Some(displayname)
+    });
+
+    if collides {
+        format!("{} ({})", displayname, user)
+    } else {
+        displayname.clone()
+    }
+}
+
 impl MemberEvent {
     /// Helper function for memebership change. Check [the specification][spec] for details.
     ///
@@ -217,6 +366,42 @@ impl MemberEvent {
             }
         }
     }
+
+    /// Like `membership_change`, but also returns the reason given for the change, if any.
+    ///
+    /// No reason is returned for `MembershipChange::None` and `MembershipChange::ProfileChanged`,
+    /// since those aren't membership changes a reason could apply to.
+    // TODO: Switch to `Option::as_deref` and remove this attribute once MSRV is >= 1.40
+    #[allow(clippy::option_as_ref_deref, unknown_lints)]
+    pub fn membership_change_with_reason(&self) -> (MembershipChange, Option<&str>) {
+        let change = self.membership_change();
+        let reason = match change {
+            MembershipChange::None | MembershipChange::ProfileChanged => None,
+            _ => self.content.reason.as_ref().map(String::as_str),
+        };
+
+        (change, reason)
+    }
+
+    /// Whether this event changes the member's display name.
+    ///
+    /// Always `false` if there is no `prev_content` to compare to.
+    pub fn display_name_changed(&self) -> bool {
+        match &self.prev_content {
+            Some(prev_content) => prev_content.displayname != self.content.displayname,
+            None => false,
+        }
+    }
+
+    /// Whether this event changes the member's avatar.
+    ///
+    /// Always `false` if there is no `prev_content` to compare to.
+    pub fn avatar_changed(&self) -> bool {
+        match &self.prev_content {
+            Some(prev_content) => prev_content.avatar_url != self.content.avatar_url,
+            None => false,
+        }
+    }
 }
 
 #[cfg(test)]
@@ -225,10 +410,10 @@ mod tests {
 
     use js_int::UInt;
     use ruma_identifiers::{EventId, RoomId, UserId};
-    use serde_json::{json, Map};
+    use serde_json::{json, Map, Value};
 
     use super::*;
-    use crate::util::serde_json_eq_try_from_raw;
+    use crate::util::{assert_deterministic_serialization, serde_json_eq_try_from_raw};
 
     #[test]
     fn serde_with_no_prev_content() {
@@ -238,6 +423,8 @@ mod tests {
                 displayname: None,
                 is_direct: None,
                 membership: MembershipState::Join,
+                join_authorized_via_users_server: None,
+                reason: None,
                 third_party_invite: None,
             },
             event_id: EventId::try_from("$h29iv0s8:example.com").unwrap(),
@@ -270,6 +457,8 @@ mod tests {
                 displayname: None,
                 is_direct: None,
                 membership: MembershipState::Join,
+                join_authorized_via_users_server: None,
+                reason: None,
                 third_party_invite: None,
             },
             event_id: EventId::try_from("$h29iv0s8:example.com").unwrap(),
@@ -283,6 +472,8 @@ mod tests {
                 displayname: None,
                 is_direct: None,
                 membership: MembershipState::Join,
+                join_authorized_via_users_server: None,
+                reason: None,
                 third_party_invite: None,
             }),
         };
@@ -319,6 +510,8 @@ mod tests {
                 displayname: Some("Alice Margatroid".to_owned()),
                 is_direct: Some(true),
                 membership: MembershipState::Invite,
+                join_authorized_via_users_server: None,
+                reason: None,
                 third_party_invite: Some(ThirdPartyInvite {
                     display_name: "alice".to_owned(),
                     signed: SignedContent {
@@ -381,6 +574,8 @@ mod tests {
                 displayname: None,
                 is_direct: None,
                 membership: MembershipState::Join,
+                join_authorized_via_users_server: None,
+                reason: None,
                 third_party_invite: None,
             },
             event_id: EventId::try_from("$143273582443PhrSn:example.org").unwrap(),
@@ -394,6 +589,8 @@ mod tests {
                 displayname: Some("Alice Margatroid".to_owned()),
                 is_direct: Some(true),
                 membership: MembershipState::Invite,
+                join_authorized_via_users_server: None,
+                reason: None,
                 third_party_invite: Some(ThirdPartyInvite {
                     display_name: "alice".to_owned(),
                     signed: SignedContent {
@@ -435,4 +632,371 @@ mod tests {
         });
         serde_json_eq_try_from_raw(event, json);
     }
+
+    #[test]
+    fn serde_ban_with_reason() {
+        let event = MemberEvent {
+            content: MemberEventContent::ban(Some("Spamming".to_owned())),
+            event_id: EventId::try_from("$h29iv0s8:example.com").unwrap(),
+            origin_server_ts: UInt::new(1).unwrap(),
+            room_id: Some(RoomId::try_from("!n8f893n9:example.com").unwrap()),
+            sender: UserId::try_from("@carl:example.com").unwrap(),
+            state_key: "@alice:example.com".to_string(),
+            unsigned: Map::new(),
+            prev_content: Some(MemberEventContent {
+                avatar_url: None,
+                displayname: None,
+                is_direct: None,
+                membership: MembershipState::Join,
+                join_authorized_via_users_server: None,
+                reason: None,
+                third_party_invite: None,
+            }),
+        };
+        let json = json!({
+            "type": "m.room.member",
+            "content": {
+                "membership": "ban",
+                "reason": "Spamming"
+            },
+            "event_id": "$h29iv0s8:example.com",
+            "origin_server_ts": 1,
+            "prev_content": {
+                "membership": "join"
+            },
+            "room_id": "!n8f893n9:example.com",
+            "sender": "@carl:example.com",
+            "state_key": "@alice:example.com"
+        });
+
+        assert_eq!(
+            event.membership_change_with_reason(),
+            (MembershipChange::KickedAndBanned, Some("Spamming"))
+        );
+        serde_json_eq_try_from_raw(event, json);
+    }
+
+    #[test]
+    fn serde_join_without_reason() {
+        let event = MemberEvent {
+            content: MemberEventContent {
+                avatar_url: None,
+                displayname: None,
+                is_direct: None,
+                membership: MembershipState::Join,
+                join_authorized_via_users_server: None,
+                reason: None,
+                third_party_invite: None,
+            },
+            event_id: EventId::try_from("$h29iv0s8:example.com").unwrap(),
+            origin_server_ts: UInt::new(1).unwrap(),
+            room_id: Some(RoomId::try_from("!n8f893n9:example.com").unwrap()),
+            sender: UserId::try_from("@alice:example.com").unwrap(),
+            state_key: "@alice:example.com".to_string(),
+            unsigned: Map::new(),
+            prev_content: None,
+        };
+        let json = json!({
+            "type": "m.room.member",
+            "content": {
+                "membership": "join"
+            },
+            "event_id": "$h29iv0s8:example.com",
+            "origin_server_ts": 1,
+            "room_id": "!n8f893n9:example.com",
+            "sender": "@alice:example.com",
+            "state_key": "@alice:example.com"
+        });
+
+        assert_eq!(
+            event.membership_change_with_reason(),
+            (MembershipChange::Joined, None)
+        );
+        serde_json_eq_try_from_raw(event, json);
+    }
+
+    #[test]
+    fn leave_with_reason() {
+        let content = MemberEventContent::leave(Some("Bye".to_owned()));
+
+        assert_eq!(content.membership, MembershipState::Leave);
+        assert_eq!(content.reason, Some("Bye".to_owned()));
+    }
+
+    #[test]
+    fn serde_direct_invite() {
+        let content = MemberEventContent {
+            avatar_url: None,
+            displayname: None,
+            is_direct: Some(true),
+            membership: MembershipState::Invite,
+            join_authorized_via_users_server: None,
+            reason: None,
+            third_party_invite: None,
+        };
+        let json = json!({
+            "is_direct": true,
+            "membership": "invite"
+        });
+
+        assert!(content.is_direct());
+        assert!(!MemberEventContent::leave(None).is_direct());
+        serde_json_eq_try_from_raw(content, json);
+    }
+
+    #[test]
+    fn serde_third_party_invite() {
+        let signatures = vec![(
+            "magic.forest".to_owned(),
+            vec![("ed25519:3".to_owned(), "foobar".to_owned())]
+                .into_iter()
+                .collect(),
+        )]
+        .into_iter()
+        .collect();
+        let content = MemberEventContent {
+            avatar_url: None,
+            displayname: None,
+            is_direct: None,
+            membership: MembershipState::Invite,
+            join_authorized_via_users_server: None,
+            reason: None,
+            third_party_invite: Some(ThirdPartyInvite {
+                display_name: "alice".to_owned(),
+                signed: SignedContent {
+                    mxid: UserId::try_from("@alice:example.org").unwrap(),
+                    signatures,
+                    token: "abc123".to_owned(),
+                },
+            }),
+        };
+        let json = json!({
+            "membership": "invite",
+            "third_party_invite": {
+                "display_name": "alice",
+                "signed": {
+                    "mxid": "@alice:example.org",
+                    "signatures": {
+                        "magic.forest": {
+                            "ed25519:3": "foobar"
+                        }
+                    },
+                    "token": "abc123"
+                }
+            }
+        });
+
+        assert!(!content.is_direct());
+        serde_json_eq_try_from_raw(content, json);
+    }
+
+    fn joined_as(displayname: Option<&str>) -> MemberEventContent {
+        MemberEventContent {
+            avatar_url: None,
+            displayname: displayname.map(ToOwned::to_owned),
+            is_direct: None,
+            membership: MembershipState::Join,
+            join_authorized_via_users_server: None,
+            reason: None,
+            third_party_invite: None,
+        }
+    }
+
+    #[test]
+    fn sanitized_display_name_with_rtl_override() {
+        let content = joined_as(Some("Alice\u{202E}moc.elpmaxe\u{200B}\n"));
+
+        assert_eq!(
+            content.sanitized_display_name(),
+            Some("Alicemoc.elpmaxe".to_owned())
+        );
+        assert_eq!(
+            content.displayname,
+            Some("Alice\u{202E}moc.elpmaxe\u{200B}\n".to_owned())
+        );
+        assert_eq!(joined_as(Some("\u{202E}")).sanitized_display_name(), None);
+        assert_eq!(joined_as(None).sanitized_display_name(), None);
+    }
+
+    #[test]
+    fn sanitized_display_name_with_line_separator() {
+        assert_eq!(
+            joined_as(Some("Alice\u{2028}(@alice:example.com)")).sanitized_display_name(),
+            Some("Alice(@alice:example.com)".to_owned())
+        );
+    }
+
+    #[test]
+    fn sanitized_display_name_with_other_invisible_characters() {
+        let content = joined_as(Some(
+            "A\u{00AD}l\u{180E}i\u{2061}c\u{E0041}e\u{3164}\u{115F}\u{1160}\u{2029}",
+        ));
+
+        assert_eq!(content.sanitized_display_name(), Some("Alice".to_owned()));
+        assert_eq!(joined_as(Some("\u{3164}")).sanitized_display_name(), None);
+    }
+
+    #[test]
+    fn sanitized_display_name_plain() {
+        assert_eq!(
+            joined_as(Some("Alice Margatroid")).sanitized_display_name(),
+            Some("Alice Margatroid".to_owned())
+        );
+        assert_eq!(
+            joined_as(Some("Åsa 李")).sanitized_display_name(),
+            Some("Åsa 李".to_owned())
+        );
+    }
+
+    #[test]
+    fn disambiguated_display_name_unique() {
+        let alice = UserId::try_from("@alice:example.com").unwrap();
+        let bob = UserId::try_from("@bob:example.com").unwrap();
+        let members = vec![
+            (alice.clone(), joined_as(Some("Alice"))),
+            (bob, joined_as(Some("Bob"))),
+        ]
+        .into_iter()
+        .collect();
+
+        assert_eq!(
+            disambiguated_display_name(&joined_as(Some("Alice")), &alice, &members),
+            "Alice"
+        );
+    }
+
+    #[test]
+    fn disambiguated_display_name_colliding() {
+        let alice = UserId::try_from("@alice:example.com").unwrap();
+        let impostor = UserId::try_from("@impostor:example.com").unwrap();
+        let members = vec![
+            (alice.clone(), joined_as(Some("Alice"))),
+            (impostor.clone(), joined_as(Some("Alice"))),
+        ]
+        .into_iter()
+        .collect();
+
+        assert_eq!(
+            disambiguated_display_name(&joined_as(Some("Alice")), &alice, &members),
+            "Alice (@alice:example.com)"
+        );
+        assert_eq!(
+            disambiguated_display_name(&joined_as(Some("Alice")), &impostor, &members),
+            "Alice (@impostor:example.com)"
+        );
+    }
+
+    #[test]
+    fn disambiguated_display_name_ignores_departed_members() {
+        let alice = UserId::try_from("@alice:example.com").unwrap();
+        let mut left = MemberEventContent::leave(None);
+        left.displayname = Some("Alice".to_owned());
+        let members = vec![
+            (alice.clone(), joined_as(Some("Alice"))),
+            (UserId::try_from("@former:example.com").unwrap(), left),
+        ]
+        .into_iter()
+        .collect();
+
+        assert_eq!(
+            disambiguated_display_name(&joined_as(Some("Alice")), &alice, &members),
+            "Alice"
+        );
+    }
+
+    #[test]
+    fn disambiguated_display_name_missing() {
+        let alice = UserId::try_from("@alice:example.com").unwrap();
+        let members = vec![(alice.clone(), joined_as(None))].into_iter().collect();
+
+        assert_eq!(
+            disambiguated_display_name(&joined_as(None), &alice, &members),
+            "@alice:example.com"
+        );
+    }
+
+    fn member_event(
+        content: MemberEventContent,
+        prev_content: Option<MemberEventContent>,
+    ) -> MemberEvent {
+        MemberEvent {
+            content,
+            event_id: EventId::try_from("$h29iv0s8:example.com").unwrap(),
+            origin_server_ts: UInt::new(1).unwrap(),
+            room_id: Some(RoomId::try_from("!n8f893n9:example.com").unwrap()),
+            sender: UserId::try_from("@alice:example.com").unwrap(),
+            state_key: "@alice:example.com".to_string(),
+            unsigned: Map::new(),
+            prev_content,
+        }
+    }
+
+    #[test]
+    fn display_name_change() {
+        let event = member_event(joined_as(Some("Alice")), Some(joined_as(Some("alice"))));
+
+        assert_eq!(event.membership_change(), MembershipChange::ProfileChanged);
+        assert!(event.display_name_changed());
+        assert!(!event.avatar_changed());
+    }
+
+    #[test]
+    fn avatar_change() {
+        let mut content = joined_as(Some("Alice"));
+        content.avatar_url = Some("mxc://example.com/avatar".to_owned());
+        let event = member_event(content, Some(joined_as(Some("Alice"))));
+
+        assert_eq!(event.membership_change(), MembershipChange::ProfileChanged);
+        assert!(!event.display_name_changed());
+        assert!(event.avatar_changed());
+    }
+
+    #[test]
+    fn pure_join() {
+        let event = member_event(joined_as(Some("Alice")), None);
+
+        assert_eq!(event.membership_change(), MembershipChange::Joined);
+        assert!(!event.display_name_changed());
+        assert!(!event.avatar_changed());
+    }
+
+    #[test]
+    fn deterministic_signature_serialization() {
+        let signatures: Map<String, Value> = (0..8)
+            .map(|i| {
+                let keys: Map<String, Value> = (0..4)
+                    .map(|j| (format!("ed25519:{}", j), json!(format!("signature{}", j))))
+                    .collect();
+                (format!("server{}.example.com", i), Value::Object(keys))
+            })
+            .collect();
+
+        assert_deterministic_serialization::<MemberEventContent>(json!({
+            "membership": "invite",
+            "third_party_invite": {
+                "display_name": "alice",
+                "signed": {
+                    "mxid": "@alice:example.org",
+                    "signatures": signatures,
+                    "token": "abc123"
+                }
+            }
+        }));
+    }
+
+    #[test]
+    fn restricted_join_round_trip() {
+        let content = MemberEventContent {
+            join_authorized_via_users_server: Some(UserId::try_from("@admin:example.org").unwrap()),
+            ..MemberEventContent::with_membership(MembershipState::Join, None)
+        };
+
+        serde_json_eq_try_from_raw(
+            content,
+            json!({
+                "join_authorised_via_users_server": "@admin:example.org",
+                "membership": "join"
+            }),
+        );
+    }
 }
@@ -1,12 +1,18 @@
 //! Types for the *m.room.message* event.
 
 use js_int::UInt;
-use ruma_identifiers::{EventId, RoomId, UserId};
-use serde::{ser::SerializeStruct, Deserialize, Deserializer, Serialize, Serializer};
+use ruma_identifiers::{DeviceId, EventId, RoomId, UserId};
+use serde::{
+    ser::{SerializeMap, SerializeStruct},
+    Deserialize, Deserializer, Serialize, Serializer,
+};
 use serde_json::{from_value, Map, Value};
 
-use super::{EncryptedFile, ImageInfo, ThumbnailInfo, encrypted::MegolmV1AesSha2Content};
-use crate::{EventType, FromRaw};
+use super::{encrypted::MegolmV1AesSha2Content, EncryptedFile, ImageInfo, ThumbnailInfo};
+use crate::{
+    key::verification::VerificationMethod, unstable, util::invalid_as_none, BadEvent, EventType,
+    TryFromRaw,
+};
 
 pub mod feedback;
 
@@ -52,6 +58,9 @@ pub enum MessageEventContent {
     /// An image message.
     Image(ImageMessageEventContent),
 
+    /// A request to verify the devices of another user in the room.
+    KeyVerificationRequest(KeyVerificationRequestEventContent),
+
     /// A location message.
     Location(LocationMessageEventContent),
 
@@ -76,47 +85,160 @@ pub enum MessageEventContent {
     __Nonexhaustive,
 }
 
-impl FromRaw for MessageEvent {
+impl TryFromRaw for MessageEvent {
     type Raw = raw::MessageEvent;
+    type Err = BadEvent;
 
-    fn from_raw(raw: raw::MessageEvent) -> Self {
-        Self {
-            content: FromRaw::from_raw(raw.content),
+    fn try_from_raw(raw: raw::MessageEvent) -> Result<Self, Self::Err> {
+        Ok(Self {
+            content: TryFromRaw::try_from_raw(raw.content)?,
             event_id: raw.event_id,
             origin_server_ts: raw.origin_server_ts,
             room_id: raw.room_id,
             sender: raw.sender,
             unsigned: raw.unsigned,
-        }
+        })
     }
 }
 
-impl FromRaw for MessageEventContent {
+impl TryFromRaw for MessageEventContent {
     type Raw = raw::MessageEventContent;
+    type Err = BadEvent;
 
-    fn from_raw(raw: raw::MessageEventContent) -> Self {
+    fn try_from_raw(raw: raw::MessageEventContent) -> Result<Self, Self::Err> {
         use raw::MessageEventContent::*;
 
-        match raw {
-            Audio(content) => MessageEventContent::Audio(content),
+        let content = match raw {
+            Audio(content) => {
+                validate_media_source(&content.url, &content.file)?;
+                MessageEventContent::Audio(content)
+            }
             Emote(content) => MessageEventContent::Emote(content),
-            File(content) => MessageEventContent::File(content),
-            Image(content) => MessageEventContent::Image(content),
+            File(content) => {
+                validate_media_source(&content.url, &content.file)?;
+                MessageEventContent::File(content)
+            }
+            Image(content) => {
+                validate_media_source(&content.url, &content.file)?;
+                MessageEventContent::Image(content)
+            }
+            KeyVerificationRequest(content) => MessageEventContent::KeyVerificationRequest(content),
             Location(content) => MessageEventContent::Location(content),
             Notice(content) => MessageEventContent::Notice(content),
             ServerNotice(content) => MessageEventContent::ServerNotice(content),
             Text(content) => MessageEventContent::Text(content),
-            Video(content) => MessageEventContent::Video(content),
+            Video(content) => {
+                validate_media_source(&content.url, &content.file)?;
+                MessageEventContent::Video(content)
+            }
             Encrypted(content) => MessageEventContent::Encrypted(content),
             __Nonexhaustive => {
                 unreachable!("It should be impossible to obtain a __Nonexhaustive variant.")
             }
-        }
+        };
+
+        Ok(content)
+    }
+}
+
+/// Checks that a media message has exactly one of a plaintext `url` and an encrypted `file`.
+fn validate_media_source(
+    url: &Option<String>,
+    file: &Option<EncryptedFile>,
+) -> Result<(), BadEvent> {
+    match (url, file) {
+        (Some(_), None) | (None, Some(_)) => Ok(()),
+        (Some(_), Some(_)) => Err(BadEvent::Validation {
+            field: "url",
+            message: "must not be present if `file` is".to_string(),
+        }),
+        (None, None) => Err(BadEvent::Validation {
+            field: "url",
+            message: "or `file` is required".to_string(),
+        }),
     }
 }
 
 impl_room_event!(MessageEvent, MessageEventContent, EventType::RoomMessage);
 
+impl_from_for_enum!(MessageEventContent, AudioMessageEventContent, Audio);
+impl_from_for_enum!(MessageEventContent, EmoteMessageEventContent, Emote);
+impl_from_for_enum!(MessageEventContent, FileMessageEventContent, File);
+impl_from_for_enum!(MessageEventContent, ImageMessageEventContent, Image);
+impl_from_for_enum!(
+    MessageEventContent,
+    KeyVerificationRequestEventContent,
+    KeyVerificationRequest
+);
+impl_from_for_enum!(MessageEventContent, LocationMessageEventContent, Location);
+impl_from_for_enum!(MessageEventContent, NoticeMessageEventContent, Notice);
+impl_from_for_enum!(
+    MessageEventContent,
+    ServerNoticeMessageEventContent,
+    ServerNotice
+);
+impl_from_for_enum!(MessageEventContent, TextMessageEventContent, Text);
+impl_from_for_enum!(MessageEventContent, VideoMessageEventContent, Video);
+impl_from_for_enum!(MessageEventContent, MegolmV1AesSha2Content, Encrypted);
+
+impl MessageEvent {
+    /// The transaction ID the client used when sending this event, from `unsigned`.
+    ///
+    /// This is only present if the event was sent by the client receiving it, which can use it to
+    /// match the event with its local echo.
+    pub fn transaction_id(&self) -> Option<&str> {
+        self.unsigned.get("transaction_id").and_then(Value::as_str)
+    }
+}
+
+impl MessageEventContent {
+    /// The length of the message's `body` in characters.
+    ///
+    /// Encrypted messages have no `body`, their length is 0.
+    pub fn body_len(&self) -> usize {
+        let body = match self {
+            MessageEventContent::Audio(content) => &content.body,
+            MessageEventContent::Emote(content) => &content.body,
+            MessageEventContent::File(content) => &content.body,
+            MessageEventContent::Image(content) => &content.body,
+            MessageEventContent::KeyVerificationRequest(content) => &content.body,
+            MessageEventContent::Location(content) => &content.body,
+            MessageEventContent::Notice(content) => &content.body,
+            MessageEventContent::ServerNotice(content) => &content.body,
+            MessageEventContent::Text(content) => &content.body,
+            MessageEventContent::Video(content) => &content.body,
+            MessageEventContent::Encrypted(_) | MessageEventContent::__Nonexhaustive => return 0,
+        };
+
+        body.chars().count()
+    }
+
+    /// The number of users mentioned in the message, counted as the matrix.to user links ("pills")
+    /// in its `formatted_body`.
+    ///
+    /// Only `href` attributes are looked at, so matrix.to URLs in the text itself or in other
+    /// attributes aren't counted. Links in a rich reply fallback aren't counted. Only text and emote messages have a
+    /// `formatted_body`, all other messages have no mentions.
+    pub fn mention_count(&self) -> usize {
+        let formatted_body = match self {
+            MessageEventContent::Emote(content) => content.formatted_body_without_reply_fallback(),
+            MessageEventContent::Text(content) => content.formatted_body_without_reply_fallback(),
+            _ => None,
+        };
+
+        formatted_body.map_or(0, |formatted_body| {
+            href_values(formatted_body)
+                .into_iter()
+                // The `@` of the user ID may or may not be percent-encoded.
+                .filter(|href| {
+                    href.starts_with("https://matrix.to/#/@")
+                        || href.starts_with("https://matrix.to/#/%40")
+                })
+                .count()
+        })
+    }
+}
+
 impl Serialize for MessageEventContent {
     fn serialize<S>(&self, serializer: S) -> Result<S::Ok, S::Error>
     where
@@ -129,6 +251,9 @@ impl Serialize for MessageEventContent {
             MessageEventContent::Emote(ref content) => content.serialize(serializer),
             MessageEventContent::File(ref content) => content.serialize(serializer),
             MessageEventContent::Image(ref content) => content.serialize(serializer),
+            MessageEventContent::KeyVerificationRequest(ref content) => {
+                content.serialize(serializer)
+            }
             MessageEventContent::Location(ref content) => content.serialize(serializer),
             MessageEventContent::Notice(ref content) => content.serialize(serializer),
             MessageEventContent::ServerNotice(ref content) => content.serialize(serializer),
@@ -156,6 +281,7 @@ pub(crate) mod raw {
 
         /// Timestamp (milliseconds since the UNIX epoch) on originating homeserver when this
         /// event was sent.
+        #[serde(deserialize_with = "crate::__private::uint_or_string")]
         pub origin_server_ts: UInt,
 
         /// The unique identifier for the room associated with this event.
@@ -185,6 +311,9 @@ pub(crate) mod raw {
         /// An image message.
         Image(ImageMessageEventContent),
 
+        /// A request to verify the devices of another user in the room.
+        KeyVerificationRequest(KeyVerificationRequestEventContent),
+
         /// A location message.
         Location(LocationMessageEventContent),
 
@@ -216,10 +345,18 @@ pub(crate) mod raw {
         {
             use serde::de::Error as _;
 
-            let value: Value = Deserialize::deserialize(deserializer)?;
+            let mut value: Value = Deserialize::deserialize(deserializer)?;
+            move_legacy_in_reply_to(&mut value);
 
             let message_type_value = match value.get("msgtype") {
                 Some(value) => value.clone(),
+                None if value.get("m.text").is_some()
+                    || value.get(unstable::EXTENSIBLE_TEXT).is_some() =>
+                {
+                    return TextMessageEventContent::from_extensible(value)
+                        .map(MessageEventContent::Text)
+                        .map_err(D::Error::custom);
+                }
                 None => return Err(D::Error::missing_field("msgtype")),
             };
 
@@ -261,6 +398,14 @@ pub(crate) mod raw {
 
                     Ok(MessageEventContent::Image(content))
                 }
+                MessageType::KeyVerificationRequest => {
+                    let content = match from_value::<KeyVerificationRequestEventContent>(value) {
+                        Ok(content) => content,
+                        Err(error) => return Err(D::Error::custom(error.to_string())),
+                    };
+
+                    Ok(MessageEventContent::KeyVerificationRequest(content))
+                }
                 MessageType::Location => {
                     let content = match from_value::<LocationMessageEventContent>(value) {
                         Ok(content) => content,
@@ -286,10 +431,13 @@ pub(crate) mod raw {
                     Ok(MessageEventContent::ServerNotice(content))
                 }
                 MessageType::Text => {
-                    let content = match from_value::<TextMessageEventContent>(value) {
+                    let extensible_blocks =
+                        TextMessageEventContent::mixed_extensible_blocks(&value);
+                    let mut content = match from_value::<TextMessageEventContent>(value) {
                         Ok(content) => content,
                         Err(error) => return Err(D::Error::custom(error.to_string())),
                     };
+                    content.extensible_blocks = extensible_blocks;
 
                     Ok(MessageEventContent::Text(content))
                 }
@@ -328,6 +476,14 @@ pub enum MessageType {
     #[serde(rename = "m.image")]
     Image,
 
+    /// A request to verify the devices of another user.
+    ///
+    /// This is the msgtype of an in-room verification request, which is an *m.room.message*
+    /// event. It is unrelated to the *m.key.verification.request* to-device event, which shares
+    /// its name.
+    #[serde(rename = "m.key.verification.request")]
+    KeyVerificationRequest,
+
     /// A location message.
     #[serde(rename = "m.location")]
     Location,
@@ -362,6 +518,9 @@ pub struct AudioMessageEventContent {
     pub body: String,
 
     /// Metadata for the audio clip referred to in `url`.
+    ///
+    /// Malformed metadata is ignored rather than making the whole message invalid.
+    #[serde(default, deserialize_with = "invalid_as_none")]
     #[serde(skip_serializing_if = "Option::is_none")]
     pub info: Option<AudioInfo>,
 
@@ -405,6 +564,12 @@ pub struct EmoteMessageEventContent {
     /// The formatted version of the `body`. This is required if `format` is specified.
     #[serde(skip_serializing_if = "Option::is_none")]
     pub formatted_body: Option<String>,
+
+    /// Information about related messages for
+    /// [rich replies](https://matrix.org/docs/spec/client_server/r0.5.0#rich-replies).
+    #[serde(rename = "m.relates_to")]
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub relates_to: Option<RelatesTo>,
 }
 
 /// The payload for a file message.
@@ -419,6 +584,9 @@ pub struct FileMessageEventContent {
     pub filename: Option<String>,
 
     /// Metadata about the file referred to in `url`.
+    ///
+    /// Malformed metadata is ignored rather than making the whole message invalid.
+    #[serde(default, deserialize_with = "invalid_as_none")]
     #[serde(skip_serializing_if = "Option::is_none")]
     pub info: Option<FileInfo>,
 
@@ -436,9 +604,11 @@ pub struct FileMessageEventContent {
 #[derive(Clone, Debug, Deserialize, PartialEq, Serialize)]
 pub struct FileInfo {
     /// The mimetype of the file, e.g. "application/msword."
+    #[serde(skip_serializing_if = "Option::is_none")]
     pub mimetype: Option<String>,
 
     /// The size of the file in bytes.
+    #[serde(skip_serializing_if = "Option::is_none")]
     pub size: Option<UInt>,
 
     /// Metadata about the image referred to in `thumbnail_url`.
@@ -462,11 +632,15 @@ pub struct ImageMessageEventContent {
     pub body: String,
 
     /// Metadata about the image referred to in `url`.
+    ///
+    /// Malformed metadata is ignored rather than making the whole message invalid.
+    #[serde(default, deserialize_with = "invalid_as_none")]
     #[serde(skip_serializing_if = "Option::is_none")]
     pub info: Option<ImageInfo>,
 
     /// The URL to the image.  Required if the file is unencrypted. The URL (typically
     /// [MXC URI](https://matrix.org/docs/spec/client_server/r0.5.0#mxc-uri)) to the image.
+    #[serde(skip_serializing_if = "Option::is_none")]
     pub url: Option<String>,
 
     /// Required if image is encrypted. Information on the encrypted image.
@@ -507,6 +681,27 @@ pub struct LocationInfo {
     pub thumbnail_file: Option<EncryptedFile>,
 }
 
+/// The payload for a key verification request message.
+///
+/// This starts a key verification in a room, usually a direct chat with the other user, instead of
+/// through to-device messages.
+#[derive(Clone, Debug, Deserialize, PartialEq)]
+pub struct KeyVerificationRequestEventContent {
+    /// A fallback message for clients that don't support in-room key verification.
+    pub body: String,
+
+    /// The verification methods supported by the sender.
+    pub methods: Vec<VerificationMethod>,
+
+    /// The device ID which is initiating the request.
+    pub from_device: DeviceId,
+
+    /// The user ID which should receive the request.
+    ///
+    /// Users should only respond to verification requests if they are named in this field.
+    pub to: UserId,
+}
+
 /// The payload for a notice message.
 #[derive(Clone, Debug, Deserialize, PartialEq)]
 pub struct NoticeMessageEventContent {
@@ -518,6 +713,14 @@ pub struct NoticeMessageEventContent {
     #[serde(rename = "m.relates_to")]
     #[serde(skip_serializing_if = "Option::is_none")]
     pub relates_to: Option<RelatesTo>,
+
+    /// The new content of the message being replaced, if this message is an edit.
+    ///
+    /// This content carries its own `m.relates_to`, independent of the relation of the edit
+    /// itself.
+    #[serde(rename = "m.new_content")]
+    #[serde(default, deserialize_with = "deserialize_new_content")]
+    pub new_content: Option<Box<MessageEventContent>>,
 }
 
 /// The payload for a server notice message.
@@ -591,6 +794,52 @@ pub struct TextMessageEventContent {
     #[serde(rename = "m.relates_to")]
     #[serde(skip_serializing_if = "Option::is_none")]
     pub relates_to: Option<RelatesTo>,
+
+    /// The new content of the message being replaced, if this message is an edit.
+    ///
+    /// This content carries its own `m.relates_to`, independent of the relation of the edit
+    /// itself.
+    #[serde(rename = "m.new_content")]
+    #[serde(default, deserialize_with = "deserialize_new_content")]
+    pub new_content: Option<Box<MessageEventContent>>,
+
+    /// The content blocks other than `m.text` of a message in the
+    /// [extensible events](https://github.com/matrix-org/matrix-doc/pull/1767) format, keyed by
+    /// their type.
+    ///
+    /// This is `Some` if the message was received with an `m.text` block instead of a `msgtype`,
+    /// in which case it is also serialized in that form.
+    ///
+    /// Messages with both a `msgtype` and an `m.text` block, as sent by clients during the
+    /// transition to extensible events, keep all fields other than the ones of the legacy format
+    /// here, including `m.text`. As long as that block is present, the message is serialized with
+    /// both the legacy fields and these blocks.
+    #[serde(skip)]
+    pub extensible_blocks: Option<Map<String, Value>>,
+}
+
+/// The fields of a text message in the legacy format, which aren't extensible event blocks.
+const LEGACY_TEXT_FIELDS: &[&str] = &[
+    "body",
+    "format",
+    "formatted_body",
+    "msgtype",
+    "m.relates_to",
+    "m.new_content",
+];
+
+/// Whether `blocks` contain an `m.text` block, under its stable or unstable name.
+fn has_text_block(blocks: &Map<String, Value>) -> bool {
+    blocks.contains_key("m.text") || blocks.contains_key(unstable::EXTENSIBLE_TEXT)
+}
+
+/// One representation of the text in an extensible event's `m.text` block.
+#[derive(Clone, Debug, Deserialize, Serialize)]
+struct TextRepresentation {
+    body: String,
+
+    #[serde(skip_serializing_if = "Option::is_none")]
+    mimetype: Option<String>,
 }
 
 /// The payload for a video message.
@@ -601,11 +850,15 @@ pub struct VideoMessageEventContent {
     pub body: String,
 
     /// Metadata about the video clip referred to in `url`.
+    ///
+    /// Malformed metadata is ignored rather than making the whole message invalid.
+    #[serde(default, deserialize_with = "invalid_as_none")]
     #[serde(skip_serializing_if = "Option::is_none")]
     pub info: Option<VideoInfo>,
 
     /// The URL to the video clip.  Required if the file is unencrypted. The URL (typically
     /// [MXC URI](https://matrix.org/docs/spec/client_server/r0.5.0#mxc-uri)) to the video clip.
+    #[serde(skip_serializing_if = "Option::is_none")]
     pub url: Option<String>,
 
     /// Required if video clip is encrypted. Information on the encrypted video clip.
@@ -653,12 +906,30 @@ pub struct VideoInfo {
 }
 
 /// Information about related messages for
-/// [rich replies](https://matrix.org/docs/spec/client_server/r0.5.0#rich-replies).
-#[derive(Clone, Debug, Deserialize, PartialEq, Serialize)]
+/// [rich replies](https://matrix.org/docs/spec/client_server/r0.5.0#rich-replies) and edits.
+///
+/// All relations are serialized into a single, flat `m.relates_to` object.
+#[derive(Clone, Debug, PartialEq)]
 pub struct RelatesTo {
     /// Information about another message being replied to.
-    #[serde(rename = "m.in_reply_to")]
-    pub in_reply_to: InReplyTo,
+    pub in_reply_to: Option<InReplyTo>,
+
+    /// Information about another message being replaced, i.e. edited.
+    pub replacement: Option<Replacement>,
+
+    /// The fields of `m.relates_to` that aren't modeled here, such as those of threads or of
+    /// relation types that aren't known, so that they are serialized again.
+    ///
+    /// [`relations::extract_relation`](../../relations/fn.extract_relation.html) understands more
+    /// relation types.
+    pub extra: Map<String, Value>,
+}
+
+/// Information about the event an edit is replacing.
+#[derive(Clone, Debug, Deserialize, PartialEq, Serialize)]
+pub struct Replacement {
+    /// The event being replaced.
+    pub event_id: EventId,
 }
 
 /// Information about the event a "rich reply" is replying to.
@@ -674,6 +945,7 @@ impl_enum! {
         Emote => "m.emote",
         File => "m.file",
         Image => "m.image",
+        KeyVerificationRequest => "m.key.verification.request",
         Location => "m.location",
         Notice => "m.notice",
         ServerNotice => "m.server_notice",
@@ -682,6 +954,122 @@ impl_enum! {
     }
 }
 
+impl Serialize for RelatesTo {
+    fn serialize<S>(&self, serializer: S) -> Result<S::Ok, S::Error>
+    where
+        S: Serializer,
+    {
+        let mut state = serializer.serialize_map(None)?;
+
+        if let Some(in_reply_to) = &self.in_reply_to {
+            state.serialize_entry("m.in_reply_to", in_reply_to)?;
+        }
+
+        if let Some(replacement) = &self.replacement {
+            state.serialize_entry("rel_type", "m.replace")?;
+            state.serialize_entry("event_id", &replacement.event_id)?;
+        }
+
+        for (key, value) in &self.extra {
+            let modeled = match key.as_str() {
+                "m.in_reply_to" => self.in_reply_to.is_some(),
+                "rel_type" | "event_id" => self.replacement.is_some(),
+                _ => false,
+            };
+
+            if !modeled {
+                state.serialize_entry(key, value)?;
+            }
+        }
+
+        state.end()
+    }
+}
+
+impl<'de> Deserialize<'de> for RelatesTo {
+    fn deserialize<D>(deserializer: D) -> Result<Self, D::Error>
+    where
+        D: Deserializer<'de>,
+    {
+        use serde::de::Error as _;
+
+        let mut extra = Map::deserialize(deserializer)?;
+
+        // Some clients used to send the reply relation without the `m.` prefix.
+        let in_reply_to = match extra
+            .remove("m.in_reply_to")
+            .or_else(|| extra.remove("in_reply_to"))
+        {
+            Some(in_reply_to) => Some(from_value(in_reply_to).map_err(D::Error::custom)?),
+            None => None,
+        };
+
+        // Relation types other than replacements aren't modeled here and are kept in `extra`.
+        let is_replacement = match extra.get("rel_type") {
+            Some(Value::String(rel_type)) => rel_type == "m.replace",
+            _ => false,
+        };
+        let replacement = if is_replacement {
+            extra.remove("rel_type");
+
+            match extra.remove("event_id") {
+                Some(event_id) => Some(Replacement {
+                    event_id: from_value(event_id).map_err(D::Error::custom)?,
+                }),
+                None => return Err(D::Error::missing_field("event_id")),
+            }
+        } else {
+            None
+        };
+
+        Ok(Self {
+            in_reply_to,
+            replacement,
+            extra,
+        })
+    }
+}
+
+/// Moves an `m.in_reply_to` found at the root of a message content, where some clients used to put
+/// it, into `m.relates_to`.
+///
+/// An `m.in_reply_to` already present in `m.relates_to` takes precedence.
+fn move_legacy_in_reply_to(content: &mut Value) {
+    let content = match content {
+        Value::Object(content) => content,
+        _ => return,
+    };
+
+    let in_reply_to = match content.remove("m.in_reply_to") {
+        Some(in_reply_to) => in_reply_to,
+        None => return,
+    };
+
+    if let Value::Object(relates_to) = content
+        .entry("m.relates_to")
+        .or_insert_with(|| Value::Object(Map::new()))
+    {
+        relates_to.entry("m.in_reply_to").or_insert(in_reply_to);
+    }
+}
+
+/// Deserializes the `m.new_content` of an edit, which is a full message content of its own.
+fn deserialize_new_content<'de, D>(
+    deserializer: D,
+) -> Result<Option<Box<MessageEventContent>>, D::Error>
+where
+    D: Deserializer<'de>,
+{
+    use serde::de::Error as _;
+
+    match Option::<raw::MessageEventContent>::deserialize(deserializer)? {
+        Some(content) => MessageEventContent::try_from_raw(content)
+            .map(|content| Some(Box::new(content)))
+            .map_err(D::Error::custom),
+        None => Ok(None),
+    }
+}
+
 impl TextMessageEventContent {
     /// A convenience constructor to create a plain text message
     pub fn new_plain(body: impl Into<String>) -> TextMessageEventContent {
@@ -690,10 +1078,279 @@ impl TextMessageEventContent {
             format: None,
             formatted_body: None,
             relates_to: None,
+            new_content: None,
+            extensible_blocks: None,
+        }
+    }
+
+    /// The `body` without the quoted message of a rich reply fallback.
+    ///
+    /// The `body` is returned as is if this message isn't a reply.
+    pub fn body_without_reply_fallback(&self) -> &str {
+        if is_reply(&self.relates_to) {
+            strip_plain_reply_fallback(&self.body)
+        } else {
+            &self.body
+        }
+    }
+
+    /// The `formatted_body` without the `<mx-reply>` element of a rich reply fallback.
+    ///
+    /// The `formatted_body` is returned as is if this message isn't a reply.
+    pub fn formatted_body_without_reply_fallback(&self) -> Option<&str> {
+        let formatted_body = self.formatted_body.as_ref()?;
+
+        if is_reply(&self.relates_to) {
+            Some(strip_html_reply_fallback(formatted_body))
+        } else {
+            Some(formatted_body)
+        }
+    }
+
+    /// The fields other than the legacy ones of a message that has both a `msgtype` and an `m.text`
+    /// block.
+    ///
+    /// Returns `None` if `content` has no `m.text` block.
+    fn mixed_extensible_blocks(content: &Value) -> Option<Map<String, Value>> {
+        let content = content.as_object()?;
+
+        if !has_text_block(content) {
+            return None;
+        }
+
+        let blocks = content
+            .iter()
+            .filter(|(key, _)| !LEGACY_TEXT_FIELDS.contains(&key.as_str()))
+            .map(|(key, block)| (key.clone(), block.clone()))
+            .collect();
+
+        Some(blocks)
+    }
+
+    /// Parses the content of a message in the extensible events format.
+    ///
+    /// The plain text and HTML representations in the `m.text` block (or its unstable
+    /// `org.matrix.msc1767.text` name) are mapped onto `body` and `formatted_body`, other
+    /// representations are dropped. All other blocks are kept in
+    /// `extensible_blocks`.
+    // TODO: Switch to `Option::as_deref` and remove this attribute once MSRV is >= 1.40
+    #[allow(clippy::option_as_ref_deref, unknown_lints)]
+    fn from_extensible(value: Value) -> Result<Self, serde_json::Error> {
+        use serde::de::Error as _;
+
+        let mut blocks = match value {
+            Value::Object(blocks) => blocks,
+            _ => return Err(serde_json::Error::custom("content must be an object")),
+        };
+
+        let unstable_text = blocks.remove(unstable::EXTENSIBLE_TEXT);
+        let text = blocks.remove("m.text").or(unstable_text);
+        let representations: Vec<TextRepresentation> = match text {
+            Some(text) => from_value(text)?,
+            None => return Err(serde_json::Error::missing_field("m.text")),
+        };
+        let relates_to = match blocks.remove("m.relates_to") {
+            Some(relates_to) => Some(from_value(relates_to)?),
+            None => None,
+        };
+
+        let mut body = None;
+        let mut formatted_body = None;
+
+        for representation in representations {
+            match representation.mimetype.as_ref().map(String::as_str) {
+                None | Some("text/plain") if body.is_none() => body = Some(representation.body),
+                Some("text/html") if formatted_body.is_none() => {
+                    formatted_body = Some(representation.body)
+                }
+                _ => {}
+            }
+        }
+
+        let body = match body {
+            Some(body) => body,
+            None => {
+                return Err(serde_json::Error::custom(
+                    "`m.text` must contain a plain text representation",
+                ))
+            }
+        };
+
+        Ok(Self {
+            body,
+            format: formatted_body
+                .as_ref()
+                .map(|_| "org.matrix.custom.html".to_string()),
+            formatted_body,
+            relates_to,
+            new_content: None,
+            extensible_blocks: Some(blocks),
+        })
+    }
+}
+
+macro_rules! impl_media_info_accessors {
+    ($content_ty:ident) => {
+        impl $content_ty {
+            /// The MIME type of the media, from `info`.
+            pub fn mimetype(&self) -> Option<&str> {
+                self.info
+                    .as_ref()
+                    .and_then(|info| info.mimetype.as_ref())
+                    .map(String::as_str)
+            }
+
+            /// The size of the media in bytes, from `info`.
+            pub fn file_size(&self) -> Option<UInt> {
+                self.info.as_ref().and_then(|info| info.size)
+            }
+        }
+    };
+}
+
+impl_media_info_accessors!(AudioMessageEventContent);
+impl_media_info_accessors!(FileMessageEventContent);
+impl_media_info_accessors!(ImageMessageEventContent);
+impl_media_info_accessors!(VideoMessageEventContent);
+
+impl EmoteMessageEventContent {
+    /// The `body` without the quoted message of a rich reply fallback.
+    ///
+    /// The `body` is returned as is if this message isn't a reply.
+    pub fn body_without_reply_fallback(&self) -> &str {
+        if is_reply(&self.relates_to) {
+            strip_plain_reply_fallback(&self.body)
+        } else {
+            &self.body
+        }
+    }
+
+    /// The `formatted_body` without the `<mx-reply>` element of a rich reply fallback.
+    ///
+    /// The `formatted_body` is returned as is if this message isn't a reply.
+    pub fn formatted_body_without_reply_fallback(&self) -> Option<&str> {
+        let formatted_body = self.formatted_body.as_ref()?;
+
+        if is_reply(&self.relates_to) {
+            Some(strip_html_reply_fallback(formatted_body))
+        } else {
+            Some(formatted_body)
+        }
+    }
+}
+
+impl KeyVerificationRequestEventContent {
+    /// Creates a request from the device `from_device` to verify the devices of the user `to`,
+    /// with a default fallback `body`.
+    pub fn new(from_device: DeviceId, methods: Vec<VerificationMethod>, to: UserId) -> Self {
+        Self {
+            body: "This user is requesting to verify your key, but your client does not support \
+                   in-chat key verification. You will need to use legacy key verification to \
+                   verify keys."
+                .to_string(),
+            methods,
+            from_device,
+            to,
+        }
+    }
+}
+
+impl NoticeMessageEventContent {
+    /// The `body` without the quoted message of a rich reply fallback.
+    ///
+    /// The `body` is returned as is if this message isn't a reply.
+    pub fn body_without_reply_fallback(&self) -> &str {
+        if is_reply(&self.relates_to) {
+            strip_plain_reply_fallback(&self.body)
+        } else {
+            &self.body
         }
     }
 }
 
+/// Whether a message with the given `m.relates_to` is a rich reply, and may have a reply fallback.
+fn is_reply(relates_to: &Option<RelatesTo>) -> bool {
+    match relates_to {
+        Some(relates_to) => relates_to.in_reply_to.is_some(),
+        None => false,
+    }
+}
+
+/// Strips the lines quoting the original message, and the empty line following them, from the
+/// start of a plain text rich reply.
+// TODO: Switch to `str::strip_prefix` and remove this attribute once MSRV is >= 1.45
+#[allow(clippy::manual_strip, unknown_lints)]
+fn strip_plain_reply_fallback(body: &str) -> &str {
+    if !body.starts_with('>') {
+        return body;
+    }
+
+    let mut rest = body;
+    while rest.starts_with('>') {
+        rest = match rest.find('\n') {
+            Some(index) => &rest[index + 1..],
+            None => return "",
+        };
+    }
+
+    if rest.starts_with('\n') {
+        &rest[1..]
+    } else {
+        rest
+    }
+}
+
+/// Strips the `<mx-reply>` element from the start of an HTML rich reply.
+fn strip_html_reply_fallback(formatted_body: &str) -> &str {
+    const END_TAG: &str = "</mx-reply>";
+
+    if !formatted_body.starts_with("<mx-reply>") {
+        return formatted_body;
+    }
+
+    match formatted_body.find(END_TAG) {
+        Some(index) => &formatted_body[index + END_TAG.len()..],
+        None => formatted_body,
+    }
+}
+
+/// The values of the `href` attributes in `html`, whether they are quoted or not.
+fn href_values(html: &str) -> Vec<&str> {
+    // ASCII lowercasing keeps the byte offsets the same.
+    let lowercase = html.to_ascii_lowercase();
+
+    lowercase
+        .match_indices("href")
+        .filter_map(|(index, _)| {
+            // Only a whole attribute name counts, not e.g. text that happens to contain "href".
+            if index == 0 || !html.as_bytes()[index - 1].is_ascii_whitespace() {
+                return None;
+            }
+
+            let rest = html[index + 4..].trim_start();
+            if !rest.starts_with('=') {
+                return None;
+            }
+            let rest = rest[1..].trim_start();
+
+            let value = match rest.chars().next() {
+                Some(quote) if quote == '"' || quote == '\'' => {
+                    let rest = &rest[1..];
+                    &rest[..rest.find(quote)?]
+                }
+                _ => {
+                    let end = rest
+                        .find(|c: char| c.is_ascii_whitespace() || c == '>')
+                        .unwrap_or(rest.len());
+                    &rest[..end]
+                }
+            };
+
+            Some(value)
+        })
+        .collect()
+}
+
 impl Serialize for AudioMessageEventContent {
     fn serialize<S>(&self, serializer: S) -> Result<S::Ok, S::Error>
     where
@@ -750,6 +1407,10 @@ impl Serialize for EmoteMessageEventContent {
             len += 1;
         }
 
+        if self.relates_to.is_some() {
+            len += 1;
+        }
+
         let mut state = serializer.serialize_struct("EmoteMessageEventContent", len)?;
 
         state.serialize_field("body", &self.body)?;
@@ -764,6 +1425,10 @@ impl Serialize for EmoteMessageEventContent {
 
         state.serialize_field("msgtype", "m.emote")?;
 
+        if self.relates_to.is_some() {
+            state.serialize_field("m.relates_to", &self.relates_to)?;
+        }
+
         state.end()
     }
 }
@@ -882,6 +1547,23 @@ impl Serialize for LocationMessageEventContent {
     }
 }
 
+impl Serialize for KeyVerificationRequestEventContent {
+    fn serialize<S>(&self, serializer: S) -> Result<S::Ok, S::Error>
+    where
+        S: Serializer,
+    {
+        let mut state = serializer.serialize_struct("KeyVerificationRequestEventContent", 5)?;
+
+        state.serialize_field("body", &self.body)?;
+        state.serialize_field("from_device", &self.from_device)?;
+        state.serialize_field("methods", &self.methods)?;
+        state.serialize_field("msgtype", "m.key.verification.request")?;
+        state.serialize_field("to", &self.to)?;
+
+        state.end()
+    }
+}
+
 impl Serialize for NoticeMessageEventContent {
     fn serialize<S>(&self, serializer: S) -> Result<S::Ok, S::Error>
     where
@@ -893,6 +1575,10 @@ impl Serialize for NoticeMessageEventContent {
             len += 1;
         }
 
+        if self.new_content.is_some() {
+            len += 1;
+        }
+
         let mut state = serializer.serialize_struct("NoticeMessageEventContent", len)?;
 
         state.serialize_field("body", &self.body)?;
@@ -902,6 +1588,10 @@ impl Serialize for NoticeMessageEventContent {
             state.serialize_field("m.relates_to", &self.relates_to)?;
         }
 
+        if self.new_content.is_some() {
+            state.serialize_field("m.new_content", &self.new_content)?;
+        }
+
         state.end()
     }
 }
@@ -944,40 +1634,82 @@ impl Serialize for TextMessageEventContent {
     where
         S: Serializer,
     {
-        let mut len = 2;
+        let mixed_blocks = match &self.extensible_blocks {
+            Some(blocks) if has_text_block(blocks) => Some(blocks),
+            Some(blocks) => return serialize_extensible_text(self, blocks, serializer),
+            None => None,
+        };
+
+        let mut state = serializer.serialize_map(None)?;
+
+        state.serialize_entry("body", &self.body)?;
 
         if self.format.is_some() {
-            len += 1;
+            state.serialize_entry("format", &self.format)?;
         }
 
         if self.formatted_body.is_some() {
-            len += 1;
+            state.serialize_entry("formatted_body", &self.formatted_body)?;
         }
 
+        state.serialize_entry("msgtype", "m.text")?;
+
         if self.relates_to.is_some() {
-            len += 1;
+            state.serialize_entry("m.relates_to", &self.relates_to)?;
         }
 
-        let mut state = serializer.serialize_struct("TextMessageEventContent", len)?;
-
-        state.serialize_field("body", &self.body)?;
-
-        if self.format.is_some() {
-            state.serialize_field("format", &self.format)?;
+        if self.new_content.is_some() {
+            state.serialize_entry("m.new_content", &self.new_content)?;
         }
 
-        if self.formatted_body.is_some() {
-            state.serialize_field("formatted_body", &self.formatted_body)?;
+        if let Some(blocks) = mixed_blocks {
+            for (block_type, block) in blocks {
+                if !LEGACY_TEXT_FIELDS.contains(&block_type.as_str()) {
+                    state.serialize_entry(block_type, block)?;
+                }
+            }
         }
 
-        state.serialize_field("msgtype", "m.text")?;
+        state.end()
+    }
+}
 
-        if self.relates_to.is_some() {
-            state.serialize_field("m.relates_to", &self.relates_to)?;
-        }
+/// Serializes a text message that was received in the extensible events format in that format.
+fn serialize_extensible_text<S>(
+    content: &TextMessageEventContent,
+    blocks: &Map<String, Value>,
+    serializer: S,
+) -> Result<S::Ok, S::Error>
+where
+    S: Serializer,
+{
+    let mut text = Vec::with_capacity(2);
+
+    if let Some(formatted_body) = &content.formatted_body {
+        text.push(TextRepresentation {
+            body: formatted_body.clone(),
+            mimetype: Some("text/html".to_string()),
+        });
+    }
 
-        state.end()
+    text.push(TextRepresentation {
+        body: content.body.clone(),
+        mimetype: None,
+    });
+
+    let mut state = serializer.serialize_map(None)?;
+
+    state.serialize_entry("m.text", &text)?;
+
+    if content.relates_to.is_some() {
+        state.serialize_entry("m.relates_to", &content.relates_to)?;
+    }
+
+    for (block_type, block) in blocks {
+        state.serialize_entry(block_type, block)?;
     }
+
+    state.end()
 }
 
 impl Serialize for VideoMessageEventContent {
@@ -1022,12 +1754,20 @@ impl Serialize for VideoMessageEventContent {
 
 #[cfg(test)]
 mod tests {
-    use serde_json::{from_value as from_json_value, json, to_value as to_json_value};
-
-    use super::{AudioMessageEventContent, MessageEventContent};
-    use crate::room::message::{InReplyTo, RelatesTo, TextMessageEventContent};
-    use crate::EventResult;
-    use ruma_identifiers::EventId;
+    use js_int::UInt;
+    use serde_json::{from_value as from_json_value, json, to_value as to_json_value, Map};
+
+    use super::{
+        AudioMessageEventContent, EmoteMessageEventContent, FileInfo, FileMessageEventContent,
+        ImageMessageEventContent, KeyVerificationRequestEventContent, LocationMessageEventContent,
+        MessageEvent, MessageEventContent, NoticeMessageEventContent, VideoMessageEventContent,
+    };
+    use crate::key::verification::VerificationMethod;
+    use crate::room::message::{InReplyTo, RelatesTo, Replacement, TextMessageEventContent};
+    use crate::{
+        collections::all::RoomEvent, util::assert_deterministic_serialization, EventResult,
+    };
+    use ruma_identifiers::{EventId, UserId};
     use std::convert::TryFrom;
 
     #[test]
@@ -1065,16 +1805,88 @@ mod tests {
     }
 
     #[test]
-    fn relates_to_serialization() {
-        let message_event_content = MessageEventContent::Text(TextMessageEventContent {
-            body: "> <@test:example.com> test\n\ntest reply".to_owned(),
-            format: None,
-            formatted_body: None,
-            relates_to: Some(RelatesTo {
-                in_reply_to: InReplyTo {
-                    event_id: EventId::try_from("$15827405538098VGFWH:example.com").unwrap(),
-                },
+    fn plain_text_has_no_null_fields() {
+        let message_event_content =
+            MessageEventContent::Text(TextMessageEventContent::new_plain("Hello, world!"));
+
+        let json = serde_json::to_string(&message_event_content).unwrap();
+
+        assert_eq!(json, r#"{"body":"Hello, world!","msgtype":"m.text"}"#);
+        assert!(!json.contains("null"));
+    }
+
+    #[test]
+    fn file_info_without_optional_fields() {
+        let message_event_content = MessageEventContent::File(FileMessageEventContent {
+            body: "report.pdf".to_string(),
+            filename: None,
+            info: Some(FileInfo {
+                mimetype: None,
+                size: None,
+                thumbnail_info: None,
+                thumbnail_url: None,
+                thumbnail_file: None,
             }),
+            url: Some("mxc://example.com/report".to_string()),
+            file: None,
+        });
+
+        assert_eq!(
+            to_json_value(&message_event_content).unwrap(),
+            json!({
+                "body": "report.pdf",
+                "info": {},
+                "msgtype": "m.file",
+                "url": "mxc://example.com/report"
+            })
+        );
+    }
+
+    #[test]
+    fn parse_from_str() {
+        let event: MessageEvent = r#"{
+            "content": {
+                "body": "test",
+                "msgtype": "m.text"
+            },
+            "event_id": "$h29iv0s8:example.com",
+            "origin_server_ts": 1,
+            "sender": "@carl:example.com",
+            "type": "m.room.message"
+        }"#
+        .parse()
+        .unwrap();
+
+        assert_eq!(
+            event.content,
+            MessageEventContent::Text(TextMessageEventContent::new_plain("test"))
+        );
+
+        let error = "{ not json".parse::<MessageEvent>().unwrap_err();
+        assert!(error.is_deserialization());
+        assert_eq!(error.json(), &serde_json::Value::Null);
+
+        let error = r#"{ "content": {}, "type": "m.room.message" }"#
+            .parse::<MessageEvent>()
+            .unwrap_err();
+        assert!(error.is_deserialization());
+    }
+
+    #[test]
+    fn relates_to_serialization() {
+        let message_event_content = MessageEventContent::Text(TextMessageEventContent {
+            body: "> <@test:example.com> test\n\ntest reply".to_owned(),
+            format: None,
+            formatted_body: None,
+            relates_to: Some(RelatesTo {
+                in_reply_to: Some(InReplyTo {
+                    event_id: EventId::try_from("$15827405538098VGFWH:example.com").unwrap(),
+                }),
+                replacement: None,
+                extra: Map::new(),
+            }),
+            new_content: None,
+            extensible_blocks: None,
         });
 
         let json_data = json!({
@@ -1090,6 +1902,354 @@ mod tests {
         assert_eq!(to_json_value(&message_event_content).unwrap(), json_data);
     }
 
+    fn reply_target(content: serde_json::Value) -> EventId {
+        match from_json_value::<EventResult<MessageEventContent>>(content)
+            .unwrap()
+            .into_result()
+            .unwrap()
+        {
+            MessageEventContent::Text(TextMessageEventContent {
+                relates_to: Some(relates_to),
+                ..
+            }) => relates_to.in_reply_to.unwrap().event_id,
+            _ => panic!("expected a text message with a relation"),
+        }
+    }
+
+    #[test]
+    fn canonical_reply() {
+        let event_id = reply_target(json!({
+            "body": "> <@alice:example.com> hi\n\nhello",
+            "msgtype": "m.text",
+            "m.relates_to": {
+                "m.in_reply_to": { "event_id": "$original:example.com" }
+            }
+        }));
+
+        assert_eq!(
+            event_id,
+            EventId::try_from("$original:example.com").unwrap()
+        );
+    }
+
+    #[test]
+    fn legacy_reply_shapes() {
+        let at_root = reply_target(json!({
+            "body": "hello",
+            "msgtype": "m.text",
+            "m.in_reply_to": { "event_id": "$original:example.com" }
+        }));
+        let unprefixed = reply_target(json!({
+            "body": "hello",
+            "msgtype": "m.text",
+            "m.relates_to": {
+                "in_reply_to": { "event_id": "$original:example.com" }
+            }
+        }));
+
+        assert_eq!(at_root, EventId::try_from("$original:example.com").unwrap());
+        assert_eq!(unprefixed, at_root);
+    }
+
+    #[test]
+    fn legacy_reply_serializes_canonically() {
+        let content = from_json_value::<EventResult<MessageEventContent>>(json!({
+            "body": "hello",
+            "msgtype": "m.notice",
+            "m.in_reply_to": { "event_id": "$original:example.com" }
+        }))
+        .unwrap()
+        .into_result()
+        .unwrap();
+
+        assert_eq!(
+            to_json_value(&content).unwrap(),
+            json!({
+                "body": "hello",
+                "msgtype": "m.notice",
+                "m.relates_to": {
+                    "m.in_reply_to": { "event_id": "$original:example.com" }
+                }
+            })
+        );
+    }
+
+    #[test]
+    fn other_relations_round_trip() {
+        let thread = json!({
+            "body": "hello",
+            "msgtype": "m.text",
+            "m.relates_to": {
+                "rel_type": "m.thread",
+                "event_id": "$root:example.com",
+                "is_falling_back": true,
+                "m.in_reply_to": { "event_id": "$latest:example.com" }
+            }
+        });
+        let unknown = json!({
+            "body": "hello",
+            "msgtype": "m.text",
+            "m.relates_to": {
+                "rel_type": "org.example.relation",
+                "event_id": "$other:example.com"
+            }
+        });
+
+        assert_eq!(
+            reply_target(thread.clone()),
+            EventId::try_from("$latest:example.com").unwrap()
+        );
+        for json in &[thread, unknown] {
+            let content = from_json_value::<EventResult<MessageEventContent>>(json.clone())
+                .unwrap()
+                .into_result()
+                .unwrap();
+
+            assert_eq!(to_json_value(&content).unwrap(), *json);
+        }
+    }
+
+    #[test]
+    fn edit_round_trip() {
+        let json_data = json!({
+            "content": {
+                "body": "* hello world",
+                "msgtype": "m.text",
+                "m.new_content": {
+                    "body": "hello world",
+                    "msgtype": "m.text",
+                    "m.relates_to": {
+                        "m.in_reply_to": { "event_id": "$question:example.com" }
+                    }
+                },
+                "m.relates_to": {
+                    "rel_type": "m.replace",
+                    "event_id": "$original:example.com"
+                }
+            },
+            "event_id": "$edit:example.com",
+            "origin_server_ts": 1,
+            "sender": "@carl:example.com",
+            "type": "m.room.message"
+        });
+
+        let event = from_json_value::<EventResult<MessageEvent>>(json_data.clone())
+            .unwrap()
+            .into_result()
+            .unwrap();
+
+        let content = match &event.content {
+            MessageEventContent::Text(content) => content,
+            _ => panic!("expected a text message"),
+        };
+        assert_eq!(
+            content.relates_to,
+            Some(RelatesTo {
+                in_reply_to: None,
+                replacement: Some(Replacement {
+                    event_id: EventId::try_from("$original:example.com").unwrap(),
+                }),
+                extra: Map::new(),
+            })
+        );
+
+        let new_content = match content.new_content {
+            Some(ref new_content) => match **new_content {
+                MessageEventContent::Text(ref new_content) => new_content,
+                _ => panic!("expected text as the new content"),
+            },
+            _ => panic!("expected text as the new content"),
+        };
+        assert_eq!(new_content.body, "hello world");
+        assert_eq!(
+            new_content.relates_to.as_ref().unwrap().in_reply_to,
+            Some(InReplyTo {
+                event_id: EventId::try_from("$question:example.com").unwrap(),
+            })
+        );
+
+        let serialized = to_json_value(&event).unwrap();
+        let serialized_content = serialized["content"].as_object().unwrap();
+        assert_eq!(
+            serialized_content
+                .keys()
+                .filter(|key| *key == "m.relates_to")
+                .count(),
+            1
+        );
+        assert!(serialized_content["m.relates_to"]
+            .get("m.relates_to")
+            .is_none());
+        assert_eq!(serialized, json_data);
+    }
+
+    fn image_message(url: Option<&str>, with_file: bool) -> serde_json::Value {
+        let mut json = json!({
+            "content": {
+                "body": "image.png",
+                "msgtype": "m.image"
+            },
+            "event_id": "$h29iv0s8:example.com",
+            "origin_server_ts": 1,
+            "sender": "@carl:example.com",
+            "type": "m.room.message"
+        });
+
+        if let Some(url) = url {
+            json["content"]["url"] = json!(url);
+        }
+
+        if with_file {
+            json["content"]["file"] = json!({
+                "url": "mxc://example.com/encrypted",
+                "key": {
+                    "kty": "oct",
+                    "key_ops": ["encrypt", "decrypt"],
+                    "alg": "A256CTR",
+                    "k": "aWF6-32KGYaC3A_FEUCk1Bt0JA37zP0wrStgmdCaW-0",
+                    "ext": true
+                },
+                "iv": "X85+XgHN+HEAAAAAAAAAAA",
+                "hashes": { "sha256": "aWOHudBnDkJ9IwaR1Nd8XKoI7DOrqDTwt6xDPfVGN6Q" },
+                "v": "v2"
+            });
+        }
+
+        json
+    }
+
+    #[test]
+    fn media_with_url_or_file() {
+        let url_only = image_message(Some("mxc://example.com/image"), false);
+        let file_only = image_message(None, true);
+
+        assert!(from_json_value::<EventResult<MessageEvent>>(url_only)
+            .unwrap()
+            .into_result()
+            .is_ok());
+        assert!(from_json_value::<EventResult<MessageEvent>>(file_only)
+            .unwrap()
+            .into_result()
+            .is_ok());
+    }
+
+    #[test]
+    fn debug_hides_key() {
+        let event = from_json_value::<EventResult<MessageEvent>>(image_message(None, true))
+            .unwrap()
+            .into_result()
+            .unwrap();
+        let debug = format!("{:?}", event);
+
+        assert!(debug.contains("A256CTR"));
+        assert!(!debug.contains("aWF6-32KGYaC3A_FEUCk1Bt0JA37zP0wrStgmdCaW-0"));
+    }
+
+    #[test]
+    fn media_with_url_and_file() {
+        let error = from_json_value::<EventResult<MessageEvent>>(image_message(
+            Some("mxc://example.com/image"),
+            true,
+        ))
+        .unwrap()
+        .into_result()
+        .unwrap_err();
+
+        assert!(error.is_validation());
+        assert_eq!(error.message(), "`url` must not be present if `file` is");
+    }
+
+    #[test]
+    fn media_without_url_or_file() {
+        let error = from_json_value::<EventResult<MessageEvent>>(image_message(None, false))
+            .unwrap()
+            .into_result()
+            .unwrap_err();
+
+        assert!(error.is_validation());
+        assert_eq!(error.message(), "`url` or `file` is required");
+    }
+
+    #[test]
+    fn from_variant_content() {
+        let content: MessageEventContent = TextMessageEventContent::new_plain("hello").into();
+        assert!(match content {
+            MessageEventContent::Text(ref text) => text.body == "hello",
+            _ => false,
+        });
+
+        let content: MessageEventContent = NoticeMessageEventContent {
+            body: "notice".to_string(),
+            relates_to: None,
+            new_content: None,
+        }
+        .into();
+        assert!(match content {
+            MessageEventContent::Notice(_) => true,
+            _ => false,
+        });
+
+        let content: MessageEventContent = EmoteMessageEventContent {
+            body: "waves".to_string(),
+            format: None,
+            formatted_body: None,
+            relates_to: None,
+        }
+        .into();
+        assert!(match content {
+            MessageEventContent::Emote(_) => true,
+            _ => false,
+        });
+
+        let content: MessageEventContent = AudioMessageEventContent {
+            body: "test".to_string(),
+            info: None,
+            url: Some("http://example.com/audio.mp3".to_string()),
+            file: None,
+        }
+        .into();
+        assert!(match content {
+            MessageEventContent::Audio(_) => true,
+            _ => false,
+        });
+
+        let content: MessageEventContent = from_json_value::<LocationMessageEventContent>(json!({
+            "body": "Big Ben, London, UK",
+            "geo_uri": "geo:51.5008,0.1247"
+        }))
+        .unwrap()
+        .into();
+        assert!(match content {
+            MessageEventContent::Location(_) => true,
+            _ => false,
+        });
+
+        let media = json!({ "body": "media", "url": "mxc://example.com/media" });
+        let content: MessageEventContent =
+            from_json_value::<FileMessageEventContent>(media.clone())
+                .unwrap()
+                .into();
+        assert!(match content {
+            MessageEventContent::File(_) => true,
+            _ => false,
+        });
+        let content: MessageEventContent =
+            from_json_value::<ImageMessageEventContent>(media.clone())
+                .unwrap()
+                .into();
+        assert!(match content {
+            MessageEventContent::Image(_) => true,
+            _ => false,
+        });
+        let content: MessageEventContent = from_json_value::<VideoMessageEventContent>(media)
+            .unwrap()
+            .into();
+        assert!(match content {
+            MessageEventContent::Video(_) => true,
+            _ => false,
+        });
+    }
+
     #[test]
     fn deserialization() {
         let message_event_content = MessageEventContent::Audio(AudioMessageEventContent {
@@ -1127,4 +2287,505 @@ mod tests {
                 .is_err()
         );
     }
+
+    #[test]
+    fn variant_follows_msgtype() {
+        let content = from_json_value::<EventResult<MessageEventContent>>(json!({
+            "body": "image.png",
+            "msgtype": "m.file",
+            "url": "mxc://example.com/image"
+        }))
+        .unwrap()
+        .into_result()
+        .unwrap();
+
+        assert!(match content {
+            MessageEventContent::File(ref file) => file.body == "image.png",
+            _ => false,
+        });
+        assert_eq!(to_json_value(&content).unwrap()["msgtype"], "m.file");
+    }
+
+    #[test]
+    fn payload_not_matching_msgtype() {
+        let result = from_json_value::<EventResult<MessageEventContent>>(json!({
+            "body": "image.png",
+            "msgtype": "m.location",
+            "url": "mxc://example.com/image"
+        }))
+        .unwrap()
+        .into_result();
+
+        assert!(match result {
+            Err(ref error) => !error.is_validation() && error.message().contains("geo_uri"),
+            Ok(_) => false,
+        });
+    }
+
+    #[test]
+    fn legacy_text_round_trip() {
+        let json_data = json!({
+            "body": "hello",
+            "format": "org.matrix.custom.html",
+            "formatted_body": "<b>hello</b>",
+            "msgtype": "m.text"
+        });
+
+        let content = from_json_value::<EventResult<MessageEventContent>>(json_data.clone())
+            .unwrap()
+            .into_result()
+            .unwrap();
+
+        match &content {
+            MessageEventContent::Text(text) => assert!(text.extensible_blocks.is_none()),
+            _ => panic!("expected a text message"),
+        }
+        assert_eq!(to_json_value(&content).unwrap(), json_data);
+    }
+
+    #[test]
+    fn extensible_text_round_trip() {
+        let json_data = json!({
+            "m.text": [
+                { "body": "<b>hello</b>", "mimetype": "text/html" },
+                { "body": "hello" }
+            ],
+            "org.example.block": { "answer": 42 }
+        });
+
+        let content = from_json_value::<EventResult<MessageEventContent>>(json_data.clone())
+            .unwrap()
+            .into_result()
+            .unwrap();
+
+        match &content {
+            MessageEventContent::Text(text) => {
+                assert_eq!(text.body, "hello");
+                assert_eq!(text.format.as_ref().unwrap(), "org.matrix.custom.html");
+                assert_eq!(text.formatted_body.as_ref().unwrap(), "<b>hello</b>");
+                assert_eq!(
+                    text.extensible_blocks.as_ref().unwrap()["org.example.block"],
+                    json!({ "answer": 42 })
+                );
+            }
+            _ => panic!("expected a text message"),
+        }
+        assert_eq!(to_json_value(&content).unwrap(), json_data);
+    }
+
+    #[test]
+    fn mixed_text_round_trip() {
+        let json_data = json!({
+            "body": "hello",
+            "msgtype": "m.text",
+            "m.text": [{ "body": "hello" }],
+            "org.example.block": { "answer": 42 }
+        });
+
+        let content = from_json_value::<EventResult<MessageEventContent>>(json_data.clone())
+            .unwrap()
+            .into_result()
+            .unwrap();
+
+        match &content {
+            MessageEventContent::Text(text) => {
+                assert_eq!(text.body, "hello");
+
+                let blocks = text.extensible_blocks.as_ref().unwrap();
+                assert_eq!(blocks["m.text"], json!([{ "body": "hello" }]));
+                assert_eq!(blocks["org.example.block"], json!({ "answer": 42 }));
+                assert!(!blocks.contains_key("body"));
+            }
+            _ => panic!("expected a text message"),
+        }
+        assert_eq!(to_json_value(&content).unwrap(), json_data);
+    }
+
+    #[test]
+    fn unstable_extensible_text() {
+        let content = from_json_value::<EventResult<MessageEventContent>>(json!({
+            "org.matrix.msc1767.text": [{ "body": "hello" }]
+        }))
+        .unwrap()
+        .into_result()
+        .unwrap();
+
+        assert_eq!(
+            to_json_value(&content).unwrap(),
+            json!({ "m.text": [{ "body": "hello" }] })
+        );
+    }
+
+    #[test]
+    fn extensible_text_without_plain_representation() {
+        let json_data = json!({
+            "m.text": [{ "body": "<b>hello</b>", "mimetype": "text/html" }]
+        });
+
+        assert!(
+            from_json_value::<EventResult<MessageEventContent>>(json_data)
+                .unwrap()
+                .into_result()
+                .is_err()
+        );
+    }
+
+    #[test]
+    fn transaction_id() {
+        let mut json_data = json!({
+            "content": {
+                "body": "hello",
+                "msgtype": "m.text"
+            },
+            "event_id": "$h29iv0s8:example.com",
+            "origin_server_ts": 1,
+            "room_id": "!n8f893n9:example.com",
+            "sender": "@carl:example.com",
+            "type": "m.room.message"
+        });
+
+        let event = from_json_value::<EventResult<MessageEvent>>(json_data.clone())
+            .unwrap()
+            .into_result()
+            .unwrap();
+        assert_eq!(event.transaction_id(), None);
+
+        json_data["unsigned"] = json!({ "age": 20, "transaction_id": "m1476648745605.19" });
+        let event = from_json_value::<EventResult<MessageEvent>>(json_data)
+            .unwrap()
+            .into_result()
+            .unwrap();
+        assert_eq!(event.transaction_id(), Some("m1476648745605.19"));
+    }
+
+    #[test]
+    fn notice_reply_fallback() {
+        let content = NoticeMessageEventContent {
+            body: "> <@alice:example.com> !ping\n> please\n\npong".to_string(),
+            relates_to: Some(RelatesTo {
+                in_reply_to: Some(InReplyTo {
+                    event_id: EventId::try_from("$15827405538098VGFWH:example.com").unwrap(),
+                }),
+                replacement: None,
+                extra: Map::new(),
+            }),
+            new_content: None,
+        };
+
+        assert_eq!(content.body_without_reply_fallback(), "pong");
+
+        let content = NoticeMessageEventContent {
+            body: "pong".to_string(),
+            relates_to: None,
+            new_content: None,
+        };
+
+        assert_eq!(content.body_without_reply_fallback(), "pong");
+    }
+
+    #[test]
+    fn quote_without_reply_is_kept() {
+        let content = NoticeMessageEventContent {
+            body: "> quote\n\nThat's what they said.".to_string(),
+            relates_to: None,
+            new_content: None,
+        };
+
+        assert_eq!(
+            content.body_without_reply_fallback(),
+            "> quote\n\nThat's what they said."
+        );
+
+        let content = TextMessageEventContent {
+            format: Some("org.matrix.custom.html".to_string()),
+            formatted_body: Some("<mx-reply>not a reply</mx-reply>text".to_string()),
+            ..TextMessageEventContent::new_plain("> quote\n\ntext")
+        };
+
+        assert_eq!(content.body_without_reply_fallback(), "> quote\n\ntext");
+        assert_eq!(
+            content.formatted_body_without_reply_fallback(),
+            Some("<mx-reply>not a reply</mx-reply>text")
+        );
+    }
+
+    #[test]
+    fn emote_html_reply_fallback() {
+        let content = EmoteMessageEventContent {
+            body: "> * <@alice:example.com> waves\n\nwaves back".to_string(),
+            format: Some("org.matrix.custom.html".to_string()),
+            formatted_body: Some(
+                "<mx-reply><blockquote><a href=\"https://matrix.to/#/!room:example.com/$event:example.com\">\
+                 In reply to</a> * <a href=\"https://matrix.to/#/@alice:example.com\">@alice:example.com</a> \
+                 waves</blockquote></mx-reply>waves <b>back</b>"
+                    .to_string(),
+            ),
+            relates_to: Some(RelatesTo {
+                in_reply_to: Some(InReplyTo {
+                    event_id: EventId::try_from("$event:example.com").unwrap(),
+                }),
+                replacement: None,
+                extra: Map::new(),
+            }),
+        };
+
+        assert_eq!(content.body_without_reply_fallback(), "waves back");
+        assert_eq!(
+            content.formatted_body_without_reply_fallback(),
+            Some("waves <b>back</b>")
+        );
+    }
+
+    #[test]
+    fn malformed_info() {
+        let json_data = json!({
+            "body": "image.png",
+            "info": [],
+            "msgtype": "m.image",
+            "url": "mxc://example.com/image"
+        });
+
+        match from_json_value::<EventResult<MessageEventContent>>(json_data)
+            .unwrap()
+            .into_result()
+            .unwrap()
+        {
+            MessageEventContent::Image(content) => {
+                assert_eq!(content.body, "image.png");
+                assert_eq!(content.url.unwrap(), "mxc://example.com/image");
+                assert!(content.info.is_none());
+            }
+            _ => panic!("expected an image message"),
+        }
+
+        let json_data = json!({
+            "body": "report.pdf",
+            "info": "application/pdf",
+            "msgtype": "m.file",
+            "url": "mxc://example.com/report"
+        });
+
+        match from_json_value::<EventResult<MessageEventContent>>(json_data)
+            .unwrap()
+            .into_result()
+            .unwrap()
+        {
+            MessageEventContent::File(content) => assert!(content.info.is_none()),
+            _ => panic!("expected a file message"),
+        }
+    }
+
+    #[test]
+    fn well_formed_info() {
+        let json_data = json!({
+            "body": "image.png",
+            "info": {
+                "h": 480,
+                "mimetype": "image/png",
+                "size": 1024,
+                "w": 640
+            },
+            "msgtype": "m.image",
+            "url": "mxc://example.com/image"
+        });
+
+        match from_json_value::<EventResult<MessageEventContent>>(json_data)
+            .unwrap()
+            .into_result()
+            .unwrap()
+        {
+            MessageEventContent::Image(content) => {
+                let info = content.info.unwrap();
+                assert_eq!(info.mimetype.unwrap(), "image/png");
+                assert_eq!(info.width, Some(UInt::from(640_u32)));
+            }
+            _ => panic!("expected an image message"),
+        }
+    }
+
+    #[test]
+    fn media_info_accessors() {
+        let audio = from_json_value::<EventResult<MessageEventContent>>(json!({
+            "body": "audio.ogg",
+            "info": { "mimetype": "audio/ogg", "size": 2048 },
+            "msgtype": "m.audio",
+            "url": "mxc://example.com/audio"
+        }))
+        .unwrap()
+        .into_result()
+        .unwrap();
+        let file = from_json_value::<EventResult<MessageEventContent>>(json!({
+            "body": "report.pdf",
+            "info": { "mimetype": "application/pdf" },
+            "msgtype": "m.file",
+            "url": "mxc://example.com/report"
+        }))
+        .unwrap()
+        .into_result()
+        .unwrap();
+        let image = from_json_value::<EventResult<MessageEventContent>>(json!({
+            "body": "image.png",
+            "info": { "size": 1024 },
+            "msgtype": "m.image",
+            "url": "mxc://example.com/image"
+        }))
+        .unwrap()
+        .into_result()
+        .unwrap();
+        let video = from_json_value::<EventResult<MessageEventContent>>(json!({
+            "body": "video.mp4",
+            "msgtype": "m.video",
+            "url": "mxc://example.com/video"
+        }))
+        .unwrap()
+        .into_result()
+        .unwrap();
+
+        match (audio, file, image, video) {
+            (
+                MessageEventContent::Audio(audio),
+                MessageEventContent::File(file),
+                MessageEventContent::Image(image),
+                MessageEventContent::Video(video),
+            ) => {
+                assert_eq!(audio.mimetype(), Some("audio/ogg"));
+                assert_eq!(audio.file_size(), Some(UInt::from(2048_u32)));
+                assert_eq!(file.mimetype(), Some("application/pdf"));
+                assert_eq!(file.file_size(), None);
+                assert_eq!(image.mimetype(), None);
+                assert_eq!(image.file_size(), Some(UInt::from(1024_u32)));
+                assert_eq!(video.mimetype(), None);
+                assert_eq!(video.file_size(), None);
+            }
+            _ => panic!("messages deserialized into the wrong message types"),
+        }
+    }
+
+    #[test]
+    fn key_verification_request() {
+        let content =
+            MessageEventContent::KeyVerificationRequest(KeyVerificationRequestEventContent::new(
+                "AliceDevice1".to_string(),
+                vec![VerificationMethod::MSasV1],
+                UserId::try_from("@bob:example.com").unwrap(),
+            ));
+
+        let json_data = to_json_value(&content).unwrap();
+
+        assert_eq!(json_data["msgtype"], json!("m.key.verification.request"));
+        assert_eq!(json_data["methods"], json!(["m.sas.v1"]));
+        assert_eq!(json_data["from_device"], json!("AliceDevice1"));
+        assert_eq!(json_data["to"], json!("@bob:example.com"));
+        assert!(json_data["body"].is_string());
+
+        assert_eq!(
+            from_json_value::<EventResult<MessageEventContent>>(json_data)
+                .unwrap()
+                .into_result()
+                .unwrap(),
+            content
+        );
+    }
+
+    #[test]
+    fn in_room_key_verification_request() {
+        let json_data = json!({
+            "content": {
+                "body": "Alice is requesting to verify your device, but your client does not support verification.",
+                "from_device": "AliceDevice1",
+                "methods": ["m.sas.v1"],
+                "msgtype": "m.key.verification.request",
+                "to": "@bob:example.com"
+            },
+            "event_id": "$h29iv0s8:example.com",
+            "origin_server_ts": 1,
+            "sender": "@alice:example.com",
+            "type": "m.room.message"
+        });
+
+        let event = from_json_value::<EventResult<RoomEvent>>(json_data.clone())
+            .unwrap()
+            .into_result()
+            .unwrap();
+
+        match &event {
+            RoomEvent::RoomMessage(MessageEvent {
+                content: MessageEventContent::KeyVerificationRequest(content),
+                ..
+            }) => {
+                assert_eq!(content.from_device, "AliceDevice1");
+                assert_eq!(content.methods, vec![VerificationMethod::MSasV1]);
+            }
+            _ => panic!("expected an in-room key verification request"),
+        }
+        assert_eq!(to_json_value(&event).unwrap(), json_data);
+    }
+
+    #[test]
+    fn long_body_len() {
+        let content: MessageEventContent =
+            TextMessageEventContent::new_plain("ä".repeat(5000)).into();
+
+        assert_eq!(content.body_len(), 5000);
+        assert_eq!(content.mention_count(), 0);
+    }
+
+    #[test]
+    fn three_pills() {
+        let content = from_json_value::<EventResult<MessageEventContent>>(json!({
+            "body": "alice, bob, carl: hi",
+            "format": "org.matrix.custom.html",
+            "formatted_body": "<mx-reply><blockquote>In reply to \
+                <a href=\"https://matrix.to/#/@dan:example.com\">@dan:example.com</a>\
+                </blockquote></mx-reply>\
+                <a href=\"https://matrix.to/#/@alice:example.com\">alice</a>, \
+                <a href=\"https://matrix.to/#/@bob:example.com\">bob</a>, \
+                <a href=\"https://matrix.to/#/%40carl%3Aexample.com\">carl</a>: hi",
+            "m.relates_to": {
+                "m.in_reply_to": { "event_id": "$dan:example.com" }
+            },
+            "msgtype": "m.text"
+        }))
+        .unwrap()
+        .into_result()
+        .unwrap();
+
+        assert_eq!(content.mention_count(), 3);
+        assert_eq!(content.body_len(), 20);
+    }
+
+    #[test]
+    fn pills_only_in_href() {
+        let content = from_json_value::<EventResult<MessageEventContent>>(json!({
+            "body": "see https://matrix.to/#/@alice:example.com, bob, carl",
+            "format": "org.matrix.custom.html",
+            "formatted_body": "see <code>https://matrix.to/#/@alice:example.com</code>, \
+                <a title=\"https://matrix.to/#/@dan:example.com\" \
+                HREF='https://matrix.to/#/@bob:example.com'>bob</a>, \
+                <a href=https://matrix.to/#/%40carl%3Aexample.com>carl</a>, \
+                <a href=\"https://example.com/#/@erin:example.com\">erin</a>",
+            "msgtype": "m.text"
+        }))
+        .unwrap()
+        .into_result()
+        .unwrap();
+
+        assert_eq!(content.mention_count(), 2);
+    }
+
+    #[test]
+    fn deterministic_encrypted_file_serialization() {
+        let mut json = image_message(None, true);
+        json["content"]["file"]["hashes"] = (0..8)
+            .map(|i| {
+                (
+                    format!("sha{}", i),
+                    json!("aWOHudBnDkJ9IwaR1Nd8XKoI7DOrqDTwt6xDPfVGN6Q"),
+                )
+            })
+            .collect::<serde_json::Map<_, _>>()
+            .into();
+
+        assert_deterministic_serialization::<MessageEventContent>(json["content"].clone());
+    }
 }
@@ -2,7 +2,7 @@
 
 use js_int::UInt;
 use ruma_identifiers::{EventId, RoomId, UserId};
-use serde::{Deserialize, Serialize};
+use serde::{Deserialize, Serialize, Serializer};
 use serde_json::{Map, Value};
 
 use crate::{util::empty_string_as_none, EventType, InvalidInput, TryFromRaw};
@@ -44,9 +44,21 @@ pub struct NameEvent {
 #[derive(Clone, Debug, PartialEq, Serialize)]
 pub struct NameEventContent {
     /// The name of the room. This MUST NOT exceed 255 bytes.
+    ///
+    /// Serialized as an empty string if `None`, which servers accept for removing the name.
+    #[serde(serialize_with = "serialize_name")]
     pub(crate) name: Option<String>,
 }
 
+// TODO: Switch to `Option::as_deref` and remove this attribute once MSRV is >= 1.40
+#[allow(clippy::option_as_ref_deref, unknown_lints)]
+fn serialize_name<S>(name: &Option<String>, serializer: S) -> Result<S::Ok, S::Error>
+where
+    S: Serializer,
+{
+    serializer.serialize_str(name.as_ref().map(String::as_str).unwrap_or(""))
+}
+
 impl TryFromRaw for NameEvent {
     type Raw = raw::NameEvent;
     type Err = InvalidInput;
@@ -99,6 +111,11 @@ impl NameEventContent {
         }
     }
 
+    /// Creates a `NameEventContent` that removes the name of the room.
+    pub fn clear() -> Self {
+        Self { name: None }
+    }
+
     /// The name of the room, if any.
     pub fn name(&self) -> Option<&str> {
         self.name.as_ref().map(String::as_ref)
@@ -119,6 +136,7 @@ pub(crate) mod raw {
 
         /// Timestamp (milliseconds since the UNIX epoch) on originating homeserver when this
         /// event was sent.
+        #[serde(deserialize_with = "crate::__private::uint_or_string")]
         pub origin_server_ts: UInt,
 
         /// The previous content for this state key, if any.
@@ -356,4 +374,12 @@ mod tests {
             name
         );
     }
+
+    #[test]
+    fn cleared_name_serialization() {
+        assert_eq!(
+            to_json_value(NameEventContent::clear()).unwrap(),
+            json!({ "name": "" })
+        );
+    }
 }
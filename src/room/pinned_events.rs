@@ -15,6 +15,33 @@ ruma_event! {
     }
 }
 
+impl PinnedEventsEvent {
+    /// The events pinned and unpinned by this event, in that order, compared to `prev_content`.
+    ///
+    /// If there is no `prev_content`, all pinned events are considered to have been added.
+    pub fn pinned_changes(&self) -> (Vec<EventId>, Vec<EventId>) {
+        let prev_pinned: &[EventId] = match &self.prev_content {
+            Some(prev_content) => &prev_content.pinned,
+            None => &[],
+        };
+
+        let added = self
+            .content
+            .pinned
+            .iter()
+            .filter(|event_id| !prev_pinned.contains(event_id))
+            .cloned()
+            .collect();
+        let removed = prev_pinned
+            .iter()
+            .filter(|event_id| !self.content.pinned.contains(event_id))
+            .cloned()
+            .collect();
+
+        (added, removed)
+    }
+}
+
 #[cfg(test)]
 mod tests {
     use std::convert::TryFrom;
@@ -64,4 +91,47 @@ mod tests {
         assert_eq!(parsed_event.content().pinned[0], content.pinned[0]);
         assert_eq!(parsed_event.content().pinned[1], content.pinned[1]);
     }
+
+    fn pinned_event(pinned: &[&EventId], prev_pinned: Option<&[&EventId]>) -> PinnedEventsEvent {
+        let content = |pinned: &[&EventId]| PinnedEventsEventContent {
+            pinned: pinned.iter().map(|&event_id| event_id.clone()).collect(),
+        };
+
+        PinnedEventsEvent {
+            content: content(pinned),
+            event_id: EventId::new("example.com").unwrap(),
+            origin_server_ts: UInt::try_from(1_432_804_485_886u64).unwrap(),
+            prev_content: prev_pinned.map(content),
+            room_id: Some(RoomId::new("example.com").unwrap()),
+            sender: UserId::new("example.com").unwrap(),
+            state_key: "".to_string(),
+            unsigned: Map::new(),
+        }
+    }
+
+    #[test]
+    fn pin_added() {
+        let first = EventId::try_from("$first:example.com").unwrap();
+        let second = EventId::try_from("$second:example.com").unwrap();
+
+        assert_eq!(
+            pinned_event(&[&first, &second], Some(&[&first])).pinned_changes(),
+            (vec![second.clone()], vec![])
+        );
+        assert_eq!(
+            pinned_event(&[&first, &second], None).pinned_changes(),
+            (vec![first, second], vec![])
+        );
+    }
+
+    #[test]
+    fn pin_removed() {
+        let first = EventId::try_from("$first:example.com").unwrap();
+        let second = EventId::try_from("$second:example.com").unwrap();
+
+        assert_eq!(
+            pinned_event(&[&second], Some(&[&first, &second])).pinned_changes(),
+            (vec![], vec![first])
+        );
+    }
 }
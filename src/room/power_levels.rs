@@ -3,11 +3,11 @@
 use std::collections::HashMap;
 
 use js_int::{Int, UInt};
-use ruma_identifiers::{EventId, RoomId, UserId};
+use ruma_identifiers::{EventId, RoomId, RoomVersionId, UserId};
 use serde::{Deserialize, Serialize};
 use serde_json::{Map, Value};
 
-use crate::{EventType, FromRaw};
+use crate::{util::cmp_display, EventType, FromRaw};
 
 /// Defines the power levels (privileges) of users in the room.
 #[derive(Clone, Debug, PartialEq, Serialize)]
@@ -52,7 +52,10 @@ pub struct PowerLevelsEventContent {
     /// The level required to send specific event types.
     ///
     /// This is a mapping from event type to power level required.
-    #[serde(skip_serializing_if = "HashMap::is_empty")]
+    #[serde(
+        skip_serializing_if = "HashMap::is_empty",
+        serialize_with = "crate::util::sorted_map"
+    )]
     pub events: HashMap<EventType, Int>,
 
     /// The default level required to send message events.
@@ -78,7 +81,10 @@ pub struct PowerLevelsEventContent {
     /// The power levels for specific users.
     ///
     /// This is a mapping from `user_id` to power level for that user.
-    #[serde(skip_serializing_if = "HashMap::is_empty")]
+    #[serde(
+        skip_serializing_if = "HashMap::is_empty",
+        serialize_with = "crate::util::sorted_map"
+    )]
     pub users: HashMap<UserId, Int>,
 
     /// The default power level for every user in the room.
@@ -134,6 +140,151 @@ impl_state_event!(
     EventType::RoomPowerLevels
 );
 
+impl PowerLevelsEventContent {
+    /// The power levels of a new room of the given version, created by `creator`.
+    ///
+    /// `invite` is set to 0, which the authorization rules of every room version use when it is
+    /// absent, even though it was documented as 50 in earlier versions of the spec. The creator
+    /// gets power level 100, except in room version 12 and later, where creators implicitly have
+    /// the highest power level and must not be listed in `users`.
+    pub fn default_for_version(version: &RoomVersionId, creator: UserId) -> Self {
+        let creator_is_implicit = version
+            .to_string()
+            .parse::<u32>()
+            .map(|version| version >= 12)
+            .unwrap_or(false);
+
+        let mut users = HashMap::new();
+        if !creator_is_implicit {
+            users.insert(creator, Int::from(100));
+        }
+
+        Self {
+            ban: default_power_level(),
+            events: HashMap::new(),
+            events_default: Int::from(0),
+            invite: Int::from(0),
+            kick: default_power_level(),
+            redact: default_power_level(),
+            state_default: default_power_level(),
+            users,
+            users_default: Int::from(0),
+            notifications: NotificationPowerLevels::default(),
+        }
+    }
+
+    /// Returns a copy of this content with the power level of `user` set to `level`.
+    ///
+    /// Since the whole content has to be sent to change a single power level, this makes sure
+    /// that all other power levels are preserved.
+    pub fn with_user_level(&self, user: UserId, level: Int) -> Self {
+        let mut content = self.clone();
+        content.users.insert(user, level);
+        content
+    }
+
+    /// Returns a copy of this content with the level required to send events of `event_type` set
+    /// to `level`.
+    pub fn with_event_level(&self, event_type: EventType, level: Int) -> Self {
+        let mut content = self.clone();
+        content.events.insert(event_type, level);
+        content
+    }
+
+    /// Returns a copy of this content with the default level required to send state events set
+    /// to `level`.
+    pub fn with_state_default(&self, level: Int) -> Self {
+        Self {
+            state_default: level,
+            ..self.clone()
+        }
+    }
+
+    /// The users with an explicit power level in `users`, sorted by level from highest to
+    /// lowest, then by user ID.
+    ///
+    /// Users that only have the `users_default` level are not included.
+    pub fn users_by_level(&self) -> Vec<(&UserId, Int)> {
+        let mut users: Vec<_> = self
+            .users
+            .iter()
+            .map(|(user, &level)| (user, level))
+            .collect();
+
+        users.sort_by(|(a_user, a_level), (b_user, b_level)| {
+            b_level
+                .cmp(a_level)
+                .then_with(|| cmp_display(*a_user, *b_user))
+        });
+
+        users
+    }
+
+    /// The power level of the given user: their entry in `users`, or `users_default`.
+    pub fn user_level(&self, user: &UserId) -> Int {
+        self.users.get(user).cloned().unwrap_or(self.users_default)
+    }
+
+    /// The power level required to perform the given action.
+    pub fn required_level(&self, action: &PowerLevelAction) -> Int {
+        match action {
+            PowerLevelAction::Ban => self.ban,
+            PowerLevelAction::Kick => self.kick,
+            PowerLevelAction::Redact => self.redact,
+            PowerLevelAction::Invite => self.invite,
+            PowerLevelAction::SendMessage(event_type) => self
+                .events
+                .get(event_type)
+                .cloned()
+                .unwrap_or(self.events_default),
+            PowerLevelAction::SendState(event_type) => self
+                .events
+                .get(event_type)
+                .cloned()
+                .unwrap_or(self.state_default),
+            PowerLevelAction::Notify => self.notifications.room,
+            PowerLevelAction::__Nonexhaustive => {
+                panic!("__Nonexhaustive enum variant is not intended for use.")
+            }
+        }
+    }
+
+    /// Whether the given user has the power level required to perform the given action.
+    pub fn user_can(&self, user: &UserId, action: &PowerLevelAction) -> bool {
+        self.user_level(user) >= self.required_level(action)
+    }
+}
+
+/// An action in a room that requires a certain power level.
+#[derive(Clone, Debug, PartialEq)]
+pub enum PowerLevelAction {
+    /// Banning a user.
+    Ban,
+
+    /// Kicking a user.
+    Kick,
+
+    /// Redacting an event sent by another user.
+    Redact,
+
+    /// Inviting a user.
+    Invite,
+
+    /// Sending a message event of the given type.
+    SendMessage(EventType),
+
+    /// Sending a state event of the given type.
+    SendState(EventType),
+
+    /// Triggering an `@room` notification.
+    Notify,
+
+    /// Additional variants may be added in the future and will not be considered breaking changes
+    /// to ruma-events.
+    #[doc(hidden)]
+    __Nonexhaustive,
+}
+
 pub(crate) mod raw {
     use super::*;
 
@@ -148,6 +299,7 @@ pub(crate) mod raw {
 
         /// Timestamp (milliseconds since the UNIX epoch) on originating homeserver when this
         /// event was sent.
+        #[serde(deserialize_with = "crate::__private::uint_or_string")]
         pub origin_server_ts: UInt,
 
         /// The previous content for this state key, if any.
@@ -266,13 +418,14 @@ mod tests {
 
     use js_int::{Int, UInt};
     use maplit::hashmap;
-    use ruma_identifiers::{EventId, RoomId, UserId};
-    use serde_json::{json, to_value as to_json_value, Map};
+    use ruma_identifiers::{EventId, RoomId, RoomVersionId, UserId};
+    use serde_json::{from_value as from_json_value, json, to_value as to_json_value, Map, Value};
 
     use super::{
-        default_power_level, NotificationPowerLevels, PowerLevelsEvent, PowerLevelsEventContent,
+        default_power_level, NotificationPowerLevels, PowerLevelAction, PowerLevelsEvent,
+        PowerLevelsEventContent,
     };
-    use crate::EventType;
+    use crate::{util::assert_deterministic_serialization, EventResult, EventType};
 
     #[test]
     fn serialization_with_optional_fields_as_none() {
@@ -413,4 +566,238 @@ mod tests {
 
         assert_eq!(actual, expected);
     }
+
+    #[test]
+    fn partial_updates_preserve_other_fields() {
+        let alice = UserId::try_from("@alice:example.com").unwrap();
+        let bob = UserId::try_from("@bob:example.com").unwrap();
+        let content = PowerLevelsEventContent {
+            ban: Int::from(75),
+            events: hashmap! {
+                EventType::RoomName => Int::from(60)
+            },
+            events_default: Int::from(10),
+            invite: Int::from(0),
+            kick: Int::from(75),
+            redact: Int::from(25),
+            state_default: Int::from(50),
+            users: hashmap! {
+                alice.clone() => Int::from(100)
+            },
+            users_default: Int::from(5),
+            notifications: NotificationPowerLevels {
+                room: Int::from(20),
+            },
+        };
+
+        let updated = content
+            .with_user_level(bob.clone(), Int::from(50))
+            .with_event_level(EventType::RoomTopic, Int::from(40))
+            .with_state_default(Int::from(60));
+
+        assert_eq!(updated.users[&alice], Int::from(100));
+        assert_eq!(updated.users[&bob], Int::from(50));
+        assert_eq!(updated.events[&EventType::RoomName], Int::from(60));
+        assert_eq!(updated.events[&EventType::RoomTopic], Int::from(40));
+        assert_eq!(updated.state_default, Int::from(60));
+
+        assert_eq!(
+            PowerLevelsEventContent {
+                users: content.users.clone(),
+                events: content.events.clone(),
+                state_default: content.state_default,
+                ..updated
+            },
+            content
+        );
+    }
+
+    #[test]
+    fn users_sorted_by_level() {
+        let admin = UserId::try_from("@admin:example.com").unwrap();
+        let alice = UserId::try_from("@alice:example.com").unwrap();
+        let bob = UserId::try_from("@bob:example.com").unwrap();
+        let muted = UserId::try_from("@muted:example.com").unwrap();
+        let content = PowerLevelsEventContent {
+            users: hashmap! {
+                muted.clone() => Int::from(0),
+                bob.clone() => Int::from(50),
+                admin.clone() => Int::from(100),
+                alice.clone() => Int::from(50),
+            },
+            ban: Int::from(50),
+            events: HashMap::new(),
+            events_default: Int::from(0),
+            invite: Int::from(50),
+            kick: Int::from(50),
+            redact: Int::from(50),
+            state_default: Int::from(50),
+            users_default: Int::from(0),
+            notifications: NotificationPowerLevels::default(),
+        };
+
+        assert_eq!(
+            content.users_by_level(),
+            vec![
+                (&admin, Int::from(100)),
+                (&alice, Int::from(50)),
+                (&bob, Int::from(50)),
+                (&muted, Int::from(0)),
+            ]
+        );
+    }
+
+    #[test]
+    fn deterministic_serialization() {
+        let events: Map<String, Value> = (0..8)
+            .map(|i| (format!("org.example.event_{}", i), json!(i)))
+            .collect();
+        let users: Map<String, Value> = (0..8)
+            .map(|i| (format!("@user{}:example.com", i), json!(i)))
+            .collect();
+
+        assert_deterministic_serialization::<PowerLevelsEventContent>(json!({
+            "events": events,
+            "users": users
+        }));
+    }
+
+    #[test]
+    fn required_levels() {
+        let content = PowerLevelsEventContent {
+            ban: Int::from(1),
+            events: hashmap! {
+                EventType::RoomName => Int::from(2),
+                EventType::Sticker => Int::from(3),
+            },
+            events_default: Int::from(4),
+            invite: Int::from(5),
+            kick: Int::from(6),
+            redact: Int::from(7),
+            state_default: Int::from(8),
+            users: HashMap::new(),
+            users_default: Int::from(0),
+            notifications: NotificationPowerLevels { room: Int::from(9) },
+        };
+        let level = |action| content.required_level(&action);
+
+        assert_eq!(level(PowerLevelAction::Ban), Int::from(1));
+        assert_eq!(level(PowerLevelAction::Kick), Int::from(6));
+        assert_eq!(level(PowerLevelAction::Redact), Int::from(7));
+        assert_eq!(level(PowerLevelAction::Invite), Int::from(5));
+        assert_eq!(
+            level(PowerLevelAction::SendMessage(EventType::Sticker)),
+            Int::from(3)
+        );
+        assert_eq!(
+            level(PowerLevelAction::SendMessage(EventType::RoomMessage)),
+            Int::from(4)
+        );
+        assert_eq!(
+            level(PowerLevelAction::SendState(EventType::RoomName)),
+            Int::from(2)
+        );
+        assert_eq!(
+            level(PowerLevelAction::SendState(EventType::RoomTopic)),
+            Int::from(8)
+        );
+        assert_eq!(level(PowerLevelAction::Notify), Int::from(9));
+    }
+
+    #[test]
+    fn user_can() {
+        let moderator = UserId::try_from("@mod:example.com").unwrap();
+        let user = UserId::try_from("@user:example.com").unwrap();
+        let content = PowerLevelsEventContent {
+            users: hashmap! { moderator.clone() => Int::from(50) },
+            ban: Int::from(50),
+            events: HashMap::new(),
+            events_default: Int::from(0),
+            invite: Int::from(50),
+            kick: Int::from(50),
+            redact: Int::from(50),
+            state_default: Int::from(50),
+            users_default: Int::from(0),
+            notifications: NotificationPowerLevels::default(),
+        };
+
+        assert_eq!(content.user_level(&user), Int::from(0));
+        assert!(content.user_can(&moderator, &PowerLevelAction::Kick));
+        assert!(!content.user_can(&user, &PowerLevelAction::Kick));
+        assert!(content.user_can(
+            &user,
+            &PowerLevelAction::SendMessage(EventType::RoomMessage)
+        ));
+    }
+
+    fn parse_ban_level(ban: Value) -> EventResult<PowerLevelsEventContent> {
+        from_json_value(json!({ "ban": ban })).unwrap()
+    }
+
+    #[test]
+    fn integer_level() {
+        let content = parse_ban_level(json!(-100)).into_result().unwrap();
+        assert_eq!(content.ban, Int::from(-100));
+    }
+
+    #[test]
+    fn float_level() {
+        let error = parse_ban_level(json!(50.5)).into_result().unwrap_err();
+
+        assert!(error.is_deserialization());
+        assert!(error.message().contains("floating point"));
+    }
+
+    #[test]
+    fn out_of_range_level() {
+        let error = parse_ban_level(json!(9_007_199_254_740_992_i64))
+            .into_result()
+            .unwrap_err();
+
+        assert!(error.is_deserialization());
+        assert!(error
+            .message()
+            .contains("an integer between -2^53 + 1 and 2^53 - 1"));
+    }
+
+    fn version_default(version: &str) -> PowerLevelsEventContent {
+        PowerLevelsEventContent::default_for_version(
+            &RoomVersionId::try_from(version).unwrap(),
+            UserId::try_from("@carl:example.com").unwrap(),
+        )
+    }
+
+    #[test]
+    fn version_1_default() {
+        let content = version_default("1");
+
+        assert_eq!(content.invite, Int::from(0));
+        assert_eq!(
+            content.user_level(&UserId::try_from("@carl:example.com").unwrap()),
+            Int::from(100)
+        );
+        assert_eq!(
+            to_json_value(&content).unwrap(),
+            json!({
+                "invite": 0,
+                "users": { "@carl:example.com": 100 }
+            })
+        );
+    }
+
+    #[test]
+    fn version_10_default() {
+        let content = version_default("10");
+
+        assert_eq!(content.invite, Int::from(0));
+        assert_eq!(content, version_default("1"));
+    }
+
+    #[test]
+    fn version_12_default() {
+        let content = version_default("12");
+
+        assert_eq!(content.invite, Int::from(0));
+        assert!(content.users.is_empty());
+    }
 }
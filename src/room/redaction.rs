@@ -9,8 +9,13 @@ ruma_event! {
         kind: RoomEvent,
         event_type: "m.room.redaction",
         fields: {
-            /// The ID of the event that was redacted.
-            pub redacts: EventId,
+            /// The IDs of the events that were redacted.
+            ///
+            /// Current room versions only allow a single ID, which is serialized as a string. Lists
+            /// of IDs, as proposed for future room versions, are also accepted, and are serialized
+            /// as an array if there is more than one ID. Serializing an event without any ID fails.
+            #[serde(with = "redacts_serde")]
+            pub redacts: Vec<EventId>,
         },
         content: {
             /// The reason for the redaction, if any.
@@ -19,3 +24,158 @@ ruma_event! {
         }
     }
 }
+
+impl RedactionEvent {
+    /// The IDs of the events that were redacted.
+    pub fn redacts(&self) -> &[EventId] {
+        &self.redacts
+    }
+
+    /// Whether this event redacts itself, which makes it malformed.
+    ///
+    /// Whether the redacted events exist and belong to the same room can't be determined from the
+    /// event alone, and must be checked by the caller.
+    pub fn is_self_referential(&self) -> bool {
+        self.redacts.contains(&self.event_id)
+    }
+}
+
+/// Serialization and deserialization of `redacts` as either a single event ID or a non-empty list
+/// of them.
+mod redacts_serde {
+    use ruma_identifiers::EventId;
+    use serde::{
+        de::Error as _, ser::Error as _, Deserialize, Deserializer, Serialize, Serializer,
+    };
+
+    #[derive(Deserialize)]
+    #[serde(untagged)]
+    enum OneOrMany {
+        One(EventId),
+        Many(Vec<EventId>),
+    }
+
+    #[allow(clippy::ptr_arg)]
+    pub fn serialize<S>(redacts: &Vec<EventId>, serializer: S) -> Result<S::Ok, S::Error>
+    where
+        S: Serializer,
+    {
+        match redacts.as_slice() {
+            [] => Err(S::Error::custom(
+                "`redacts` must contain at least one event ID",
+            )),
+            [event_id] => event_id.serialize(serializer),
+            event_ids => event_ids.serialize(serializer),
+        }
+    }
+
+    pub fn deserialize<'de, D>(deserializer: D) -> Result<Vec<EventId>, D::Error>
+    where
+        D: Deserializer<'de>,
+    {
+        match OneOrMany::deserialize(deserializer)? {
+            OneOrMany::One(event_id) => Ok(vec![event_id]),
+            OneOrMany::Many(ref event_ids) if event_ids.is_empty() => {
+                Err(D::Error::invalid_length(0, &"at least one event ID"))
+            }
+            OneOrMany::Many(event_ids) => Ok(event_ids),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::convert::TryFrom;
+
+    use ruma_identifiers::EventId;
+    use serde_json::{from_value as from_json_value, json, to_value as to_json_value};
+
+    use super::RedactionEvent;
+    use crate::{util::try_from_raw_round_trip, EventResult};
+
+    fn redaction(redacts: serde_json::Value) -> serde_json::Value {
+        json!({
+            "content": { "reason": "Spam" },
+            "event_id": "$redaction:example.com",
+            "origin_server_ts": 1,
+            "redacts": redacts,
+            "sender": "@admin:example.com",
+            "type": "m.room.redaction"
+        })
+    }
+
+    #[test]
+    fn scalar_redacts() {
+        let json = redaction(json!("$spam:example.com"));
+        let event = from_json_value::<EventResult<RedactionEvent>>(json.clone())
+            .unwrap()
+            .into_result()
+            .unwrap();
+
+        assert_eq!(
+            event.redacts(),
+            &[EventId::try_from("$spam:example.com").unwrap()]
+        );
+        try_from_raw_round_trip::<RedactionEvent>(json);
+    }
+
+    #[test]
+    fn array_redacts() {
+        let json = redaction(json!(["$spam:example.com", "$more_spam:example.com"]));
+        let event = from_json_value::<EventResult<RedactionEvent>>(json.clone())
+            .unwrap()
+            .into_result()
+            .unwrap();
+
+        assert_eq!(
+            event.redacts(),
+            &[
+                EventId::try_from("$spam:example.com").unwrap(),
+                EventId::try_from("$more_spam:example.com").unwrap()
+            ]
+        );
+        try_from_raw_round_trip::<RedactionEvent>(json);
+    }
+
+    #[test]
+    fn empty_redacts() {
+        assert!(
+            from_json_value::<EventResult<RedactionEvent>>(redaction(json!([])))
+                .unwrap()
+                .into_result()
+                .unwrap_err()
+                .is_deserialization()
+        );
+    }
+
+    #[test]
+    fn serialize_empty_redacts() {
+        let mut event =
+            from_json_value::<EventResult<RedactionEvent>>(redaction(json!("$spam:example.com")))
+                .unwrap()
+                .into_result()
+                .unwrap();
+        event.redacts.clear();
+
+        assert_eq!(
+            to_json_value(&event).unwrap_err().to_string(),
+            "`redacts` must contain at least one event ID"
+        );
+    }
+
+    #[test]
+    fn self_referential_redaction() {
+        let redaction = |redacts| {
+            from_json_value::<EventResult<RedactionEvent>>(redaction(redacts))
+                .unwrap()
+                .into_result()
+                .unwrap()
+        };
+
+        assert!(redaction(json!("$redaction:example.com")).is_self_referential());
+        assert!(
+            redaction(json!(["$spam:example.com", "$redaction:example.com"])).is_self_referential()
+        );
+        assert!(!redaction(json!("$spam:example.com")).is_self_referential());
+    }
+}
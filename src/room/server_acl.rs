@@ -1,11 +1,16 @@
 //! Types for the *m.room.server_acl* event.
 
+use std::net::{Ipv4Addr, Ipv6Addr};
+
 use js_int::UInt;
 use ruma_identifiers::{EventId, RoomId, UserId};
 use serde::{Deserialize, Serialize};
 use serde_json::{Map, Value};
 
-use crate::{util::default_true, EventType, FromRaw};
+use crate::{
+    util::{default_true, glob_matches},
+    EventType, FromRaw,
+};
 
 /// An event to indicate which servers are permitted to participate in the room.
 #[derive(Clone, Debug, PartialEq, Serialize)]
@@ -104,6 +109,72 @@ impl_state_event!(
     EventType::RoomServerAcl
 );
 
+impl ServerAclEvent {
+    /// Whether the server with the given name was allowed to participate in the room before this
+    /// event, but isn't anymore.
+    ///
+    /// A room without a previous ACL allowed every server.
+    pub fn newly_denies(&self, server_name: &str) -> bool {
+        self.was_allowed(server_name) && !self.content.is_allowed(server_name)
+    }
+
+    /// Whether the server with the given name wasn't allowed to participate in the room before
+    /// this event, but is now.
+    pub fn newly_allows(&self, server_name: &str) -> bool {
+        !self.was_allowed(server_name) && self.content.is_allowed(server_name)
+    }
+
+    fn was_allowed(&self, server_name: &str) -> bool {
+        match &self.prev_content {
+            Some(prev_content) => prev_content.is_allowed(server_name),
+            None => true,
+        }
+    }
+}
+
+impl ServerAclEventContent {
+    /// Whether the server with the given name is allowed to participate in the room.
+    ///
+    /// Any port in `server_name` is ignored. Servers matching an entry of `deny` are never allowed,
+    /// others are allowed if they match an entry of `allow`.
+    pub fn is_allowed(&self, server_name: &str) -> bool {
+        let host = strip_port(server_name);
+
+        if !self.allow_ip_literals && is_ip_literal(host) {
+            return false;
+        }
+
+        if self.deny.iter().any(|pattern| glob_matches(pattern, host)) {
+            return false;
+        }
+
+        self.allow.iter().any(|pattern| glob_matches(pattern, host))
+    }
+}
+
+/// Removes the port, if any, from a server name.
+fn strip_port(server_name: &str) -> &str {
+    if server_name.starts_with('[') {
+        match server_name.find(']') {
+            Some(end) => &server_name[..=end],
+            None => server_name,
+        }
+    } else {
+        match server_name.rfind(':') {
+            Some(colon) => &server_name[..colon],
+            None => server_name,
+        }
+    }
+}
+
+/// Whether a host is an IPv4 address or a bracketed IPv6 address.
+fn is_ip_literal(host: &str) -> bool {
+    host.parse::<Ipv4Addr>().is_ok()
+        || (host.starts_with('[')
+            && host.ends_with(']')
+            && host[1..host.len() - 1].parse::<Ipv6Addr>().is_ok())
+}
+
 pub(crate) mod raw {
     use super::*;
 
@@ -118,6 +189,7 @@ pub(crate) mod raw {
 
         /// Timestamp (milliseconds since the UNIX epoch) on originating homeserver when this
         /// event was sent.
+        #[serde(deserialize_with = "crate::__private::uint_or_string")]
         pub origin_server_ts: UInt,
 
         /// The previous content for this state key, if any.
@@ -172,8 +244,8 @@ pub(crate) mod raw {
 mod tests {
     use serde_json::{from_value as from_json_value, json};
 
-    use super::ServerAclEvent;
-    use crate::EventResult;
+    use super::{ServerAclEvent, ServerAclEventContent};
+    use crate::{util::glob_matches, EventResult};
 
     #[test]
     fn default_values() {
@@ -193,4 +265,56 @@ mod tests {
         assert!(server_acl_event.content.allow.is_empty());
         assert!(server_acl_event.content.deny.is_empty());
     }
+
+    fn acl(allow: &[&str], deny: &[&str]) -> ServerAclEventContent {
+        ServerAclEventContent {
+            allow_ip_literals: false,
+            allow: allow.iter().map(|server| server.to_string()).collect(),
+            deny: deny.iter().map(|server| server.to_string()).collect(),
+        }
+    }
+
+    #[test]
+    fn glob_patterns() {
+        assert!(glob_matches("*", "example.com"));
+        assert!(glob_matches("*.example.com", "matrix.example.com"));
+        assert!(!glob_matches("*.example.com", "example.com"));
+        assert!(glob_matches("matrix?.example.com", "matrix2.example.com"));
+        assert!(!glob_matches("matrix?.example.com", "matrix.example.com"));
+        assert!(glob_matches("*a*b", "xaxxab"));
+    }
+
+    #[test]
+    fn is_allowed() {
+        let content = acl(&["*"], &["evil.example.com"]);
+
+        assert!(content.is_allowed("example.com"));
+        assert!(content.is_allowed("example.com:8448"));
+        assert!(!content.is_allowed("evil.example.com:8448"));
+        assert!(!content.is_allowed("1.2.3.4"));
+        assert!(!content.is_allowed("[::1]:8448"));
+        assert!(!acl(&[], &[]).is_allowed("example.com"));
+    }
+
+    #[test]
+    fn server_moved_from_allow_to_deny() {
+        let json_data = json!({
+            "content": { "allow": ["*"], "deny": ["evil.example.com"] },
+            "event_id": "$h29iv0s8:example.com",
+            "origin_server_ts": 1,
+            "prev_content": { "allow": ["evil.example.com", "example.com"] },
+            "sender": "@carl:example.com",
+            "state_key": "",
+            "type": "m.room.server_acl"
+        });
+        let event: ServerAclEvent = from_json_value::<EventResult<_>>(json_data)
+            .unwrap()
+            .into_result()
+            .unwrap();
+
+        assert!(event.newly_denies("evil.example.com"));
+        assert!(!event.newly_allows("evil.example.com"));
+        assert!(!event.newly_denies("example.com"));
+        assert!(event.newly_allows("other.example.com"));
+    }
 }
@@ -3,6 +3,8 @@
 use ruma_events_macros::ruma_event;
 use serde::{Deserialize, Serialize};
 
+use crate::room::member::SignedContent;
+
 ruma_event! {
     /// An invitation to a room issued to a third party identifier, rather than a matrix user ID.
     ///
@@ -42,3 +44,103 @@ pub struct PublicKey {
     /// A Base64-encoded Ed25519 key with which the token must be signed.
     pub public_key: String,
 }
+
+impl ThirdPartyInviteEventContent {
+    /// All keys with which the token may be signed: `public_key` followed by the keys in
+    /// `public_keys`.
+    pub fn signing_keys(&self) -> Vec<&str> {
+        let mut keys = vec![self.public_key.as_str()];
+        keys.extend(
+            self.public_keys
+                .iter()
+                .flatten()
+                .map(|key| key.public_key.as_str()),
+        );
+
+        keys
+    }
+}
+
+impl ThirdPartyInviteEvent {
+    /// Whether the `signed` object of an *m.room.member* invite refers to this invitation.
+    ///
+    /// This checks that the token of `signed` is the state key of this event, and that `signed`
+    /// contains an Ed25519 signature and this event a key to check it with.
+    ///
+    /// The signature is **not** checked, so a forged `signed` object with the right token matches
+    /// as well. Before accepting the invite, the signature must be verified over the canonical JSON
+    /// of `signed` with one of the `signing_keys` of the content.
+    pub fn matches_signed(&self, signed: &SignedContent) -> bool {
+        let has_ed25519_signature = signed
+            .signatures
+            .values()
+            .flat_map(|keys| keys.keys())
+            .any(|key_id| key_id.starts_with("ed25519:"));
+
+        signed.token == self.state_key
+            && has_ed25519_signature
+            && !self.content.public_key.is_empty()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::convert::TryFrom;
+
+    use maplit::hashmap;
+    use ruma_identifiers::UserId;
+    use serde_json::{from_value as from_json_value, json};
+
+    use super::ThirdPartyInviteEvent;
+    use crate::{room::member::SignedContent, EventResult};
+
+    fn invite_event() -> ThirdPartyInviteEvent {
+        from_json_value::<EventResult<ThirdPartyInviteEvent>>(json!({
+            "content": {
+                "display_name": "Alice Margatroid",
+                "key_validity_url": "https://magic.forest/verifykey",
+                "public_key": "abc123",
+                "public_keys": [
+                    {
+                        "key_validity_url": "https://magic.forest/verifykey",
+                        "public_key": "def456"
+                    }
+                ]
+            },
+            "event_id": "$143273582443PhrSn:example.org",
+            "origin_server_ts": 1,
+            "room_id": "!jEsUZKDJdhlrceRyVU:example.org",
+            "sender": "@example:example.org",
+            "state_key": "pc98",
+            "type": "m.room.third_party_invite"
+        }))
+        .unwrap()
+        .into_result()
+        .unwrap()
+    }
+
+    fn signed(token: &str) -> SignedContent {
+        SignedContent {
+            mxid: UserId::try_from("@alice:example.org").unwrap(),
+            signatures: hashmap! {
+                "magic.forest".to_string() => hashmap! {
+                    "ed25519:3".to_string() => "fQpGIW1Snz+pwLZu6W4T3XJ6l8elZIuRcCu5GdG5w2M".to_string()
+                }
+            },
+            token: token.to_string(),
+        }
+    }
+
+    #[test]
+    fn matching_token() {
+        let event = invite_event();
+
+        assert!(event.matches_signed(&signed("pc98")));
+        assert_eq!(event.content.signing_keys(), vec!["abc123", "def456"]);
+    }
+
+    #[test]
+    fn mismatched_token() {
+        assert!(!invite_event().matches_signed(&signed("pc99")));
+    }
+}
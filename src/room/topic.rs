@@ -13,3 +13,27 @@ ruma_event! {
         },
     }
 }
+
+impl TopicEventContent {
+    /// Creates a `TopicEventContent` that removes the topic of the room.
+    pub fn clear() -> Self {
+        Self {
+            topic: String::new(),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use serde_json::{json, to_value as to_json_value};
+
+    use super::TopicEventContent;
+
+    #[test]
+    fn cleared_topic_serialization() {
+        assert_eq!(
+            to_json_value(TopicEventContent::clear()).unwrap(),
+            json!({ "topic": "" })
+        );
+    }
+}
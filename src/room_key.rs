@@ -1,31 +1,195 @@
 //! Types for the *m.room_key* event.
 
-use ruma_events_macros::ruma_event;
+use std::fmt::{Debug, Formatter, Result as FmtResult};
+
 use ruma_identifiers::RoomId;
+use serde::{Deserialize, Serialize};
+
+use super::EventEncryptionAlgorithm;
+use crate::{util::Redacted, BadEvent, EventType, TryFromRaw};
+
+/// This event type is used to exchange keys for end-to-end encryption.
+///
+/// Typically it is encrypted as an *m.room.encrypted* event, then sent as a to-device event.
+#[derive(Clone, Debug, PartialEq, Serialize)]
+#[serde(rename = "m.room_key", tag = "type")]
+pub struct RoomKeyEvent {
+    /// The event's content.
+    pub content: RoomKeyEventContent,
+}
+
+/// The payload of an *m.room_key* event.
+///
+/// The `Debug` output doesn't contain the session key.
+#[derive(Clone, PartialEq, Serialize)]
+pub struct RoomKeyEventContent {
+    /// The encryption algorithm the key in this event is to be used with.
+    ///
+    /// Must be `m.megolm.v1.aes-sha2`.
+    pub algorithm: EventEncryptionAlgorithm,
+
+    /// The room where the key is used.
+    ///
+    /// Always present for keys using the `m.megolm.v1.aes-sha2` algorithm.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub room_id: Option<RoomId>,
+
+    /// The ID of the session that the key is for.
+    pub session_id: String,
+
+    /// The key to be exchanged.
+    pub session_key: String,
+}
+
+impl Debug for RoomKeyEventContent {
+    fn fmt(&self, f: &mut Formatter<'_>) -> FmtResult {
+        f.debug_struct("RoomKeyEventContent")
+            .field("algorithm", &self.algorithm)
+            .field("room_id", &self.room_id)
+            .field("session_id", &self.session_id)
+            .field("session_key", &Redacted)
+            .finish()
+    }
+}
+
+impl TryFromRaw for RoomKeyEvent {
+    type Raw = raw::RoomKeyEvent;
+    type Err = BadEvent;
+
+    fn try_from_raw(raw: raw::RoomKeyEvent) -> Result<Self, Self::Err> {
+        RoomKeyEventContent::try_from_raw(raw.content).map(|content| Self { content })
+    }
+}
+
+impl_event!(RoomKeyEvent, RoomKeyEventContent, EventType::RoomKey);
+
+impl TryFromRaw for RoomKeyEventContent {
+    type Raw = raw::RoomKeyEventContent;
+    type Err = BadEvent;
+
+    fn try_from_raw(raw: raw::RoomKeyEventContent) -> Result<Self, Self::Err> {
+        if raw.algorithm == EventEncryptionAlgorithm::MegolmV1AesSha2 && raw.room_id.is_none() {
+            return Err(BadEvent::Validation {
+                field: "room_id",
+                message: "is required for the m.megolm.v1.aes-sha2 algorithm".to_string(),
+            });
+        }
+
+        Ok(Self {
+            algorithm: raw.algorithm,
+            room_id: raw.room_id,
+            session_id: raw.session_id,
+            session_key: raw.session_key,
+        })
+    }
+}
 
-use super::Algorithm;
+pub(crate) mod raw {
+    use super::*;
 
-ruma_event! {
     /// This event type is used to exchange keys for end-to-end encryption.
     ///
     /// Typically it is encrypted as an *m.room.encrypted* event, then sent as a to-device event.
-    RoomKeyEvent {
-        kind: Event,
-        event_type: "m.room_key",
-        content: {
-            /// The encryption algorithm the key in this event is to be used with.
-            ///
-            /// Must be `m.megolm.v1.aes-sha2`.
-            pub algorithm: Algorithm,
-
-            /// The room where the key is used.
-            pub room_id: RoomId,
-
-            /// The ID of the session that the key is for.
-            pub session_id: String,
-
-            /// The key to be exchanged.
-            pub session_key: String,
-        }
+    #[derive(Clone, Debug, Deserialize, PartialEq)]
+    pub struct RoomKeyEvent {
+        /// The event's content.
+        pub content: RoomKeyEventContent,
+    }
+
+    /// The payload of an *m.room_key* event.
+    #[derive(Clone, Debug, Deserialize, PartialEq)]
+    pub struct RoomKeyEventContent {
+        /// The encryption algorithm the key in this event is to be used with.
+        pub algorithm: EventEncryptionAlgorithm,
+
+        /// The room where the key is used.
+        pub room_id: Option<RoomId>,
+
+        /// The ID of the session that the key is for.
+        pub session_id: String,
+
+        /// The key to be exchanged.
+        pub session_key: String,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::convert::TryFrom;
+
+    use ruma_identifiers::RoomId;
+    use serde_json::{from_value as from_json_value, json};
+
+    use super::RoomKeyEventContent;
+    use crate::{util::try_from_raw_round_trip, EventEncryptionAlgorithm, EventResult};
+
+    #[test]
+    fn megolm_with_room_id() {
+        let json = json!({
+            "algorithm": "m.megolm.v1.aes-sha2",
+            "room_id": "!test:localhost",
+            "session_id": "fake_id",
+            "session_key": "fake_key"
+        });
+
+        let content = from_json_value::<EventResult<RoomKeyEventContent>>(json.clone())
+            .unwrap()
+            .into_result()
+            .unwrap();
+
+        assert_eq!(
+            content.room_id,
+            Some(RoomId::try_from("!test:localhost").unwrap())
+        );
+        try_from_raw_round_trip::<RoomKeyEventContent>(json);
+    }
+
+    #[test]
+    fn megolm_without_room_id() {
+        let error = from_json_value::<EventResult<RoomKeyEventContent>>(json!({
+            "algorithm": "m.megolm.v1.aes-sha2",
+            "session_id": "fake_id",
+            "session_key": "fake_key"
+        }))
+        .unwrap()
+        .into_result()
+        .unwrap_err();
+
+        assert!(error.is_validation());
+        assert_eq!(
+            error.message(),
+            "`room_id` is required for the m.megolm.v1.aes-sha2 algorithm"
+        );
+    }
+
+    #[test]
+    fn olm_without_room_id() {
+        let json = json!({
+            "algorithm": "m.olm.v1.curve25519-aes-sha2",
+            "session_id": "fake_id",
+            "session_key": "fake_key"
+        });
+
+        let content = from_json_value::<EventResult<RoomKeyEventContent>>(json.clone())
+            .unwrap()
+            .into_result()
+            .unwrap();
+
+        assert_eq!(content.room_id, None);
+        try_from_raw_round_trip::<RoomKeyEventContent>(json);
+    }
+
+    #[test]
+    fn debug_hides_session_key() {
+        let content = RoomKeyEventContent {
+            algorithm: EventEncryptionAlgorithm::MegolmV1AesSha2,
+            room_id: Some(RoomId::try_from("!test:localhost").unwrap()),
+            session_id: "fake_id".to_string(),
+            session_key: "secret_session_key".to_string(),
+        };
+        let debug = format!("{:?}", content);
+
+        assert!(debug.contains("fake_id"));
+        assert!(!debug.contains("secret_session_key"));
     }
 }
@@ -4,7 +4,7 @@ use ruma_events_macros::ruma_event;
 use ruma_identifiers::{DeviceId, RoomId};
 use serde::{Deserialize, Serialize};
 
-use super::Algorithm;
+use super::EventEncryptionAlgorithm;
 
 ruma_event! {
     /// This event type is used to request keys for end-to-end encryption.
@@ -20,6 +20,7 @@ ruma_event! {
             /// Information about the requested key.
             ///
             /// Required when action is `request`.
+            #[serde(skip_serializing_if = "Option::is_none")]
             pub body: Option<RequestedKeyInfo>,
 
             /// ID of the device requesting the key.
@@ -63,7 +64,7 @@ impl_enum! {
 #[derive(Clone, Debug, Deserialize, PartialEq, Serialize)]
 pub struct RequestedKeyInfo {
     /// The encryption algorithm the requested key in this event is to be used with.
-    pub algorithm: Algorithm,
+    pub algorithm: EventEncryptionAlgorithm,
 
     /// The room where the key is used.
     pub room_id: RoomId,
@@ -74,3 +75,59 @@ pub struct RequestedKeyInfo {
     /// The ID of the session that the key is for.
     pub session_id: String,
 }
+
+#[cfg(test)]
+mod tests {
+    use std::convert::TryFrom;
+
+    use ruma_identifiers::RoomId;
+    use serde_json::{from_value as from_json_value, json};
+
+    use super::{Action, RequestedKeyInfo, RoomKeyRequestEventContent};
+    use crate::{util::serde_json_eq_try_from_raw, EventEncryptionAlgorithm, EventResult};
+
+    #[test]
+    fn requesting_device_id_round_trip() {
+        let content = RoomKeyRequestEventContent {
+            action: Action::Request,
+            body: Some(RequestedKeyInfo {
+                algorithm: EventEncryptionAlgorithm::MegolmV1AesSha2,
+                room_id: RoomId::try_from("!Cuyf34gef24t:localhost").unwrap(),
+                sender_key: "RF3s+E7RkTQTGF2d8Deol0FkQvgII2aJDf3/Jp5mxVU".to_string(),
+                session_id: "X3lUlvLELLYxeTx4yOVu6UDpasGEVO0Jbu+QFnm0cKQ".to_string(),
+            }),
+            requesting_device_id: "RJYKSTBOIE".to_string(),
+            request_id: "1495474790150.19".to_string(),
+        };
+
+        serde_json_eq_try_from_raw(
+            content,
+            json!({
+                "action": "request",
+                "body": {
+                    "algorithm": "m.megolm.v1.aes-sha2",
+                    "room_id": "!Cuyf34gef24t:localhost",
+                    "sender_key": "RF3s+E7RkTQTGF2d8Deol0FkQvgII2aJDf3/Jp5mxVU",
+                    "session_id": "X3lUlvLELLYxeTx4yOVu6UDpasGEVO0Jbu+QFnm0cKQ"
+                },
+                "requesting_device_id": "RJYKSTBOIE",
+                "request_id": "1495474790150.19"
+            }),
+        );
+    }
+
+    #[test]
+    fn malformed_requesting_device_id() {
+        let error = from_json_value::<EventResult<RoomKeyRequestEventContent>>(json!({
+            "action": "request_cancellation",
+            "requesting_device_id": 42,
+            "request_id": "1495474790150.19"
+        }))
+        .unwrap()
+        .into_result()
+        .unwrap_err();
+
+        assert!(error.is_deserialization());
+        assert!(error.message().contains("expected a string"));
+    }
+}
@@ -0,0 +1,206 @@
+//! Types for the *m.space.child* event.
+
+use std::convert::TryFrom;
+
+use ruma_events_macros::ruma_event;
+use ruma_identifiers::RoomId;
+use serde::Deserializer;
+
+ruma_event! {
+    /// The relationship of a space to one of its child rooms.
+    ///
+    /// The state key is the ID of the child room.
+    SpaceChildEvent {
+        kind: StateEvent,
+        event_type: "m.space.child",
+        content: {
+            /// A list of servers to try to join the child room through.
+            ///
+            /// If this is absent or empty, the child room is not considered part of the space.
+            #[serde(default, skip_serializing_if = "Option::is_none")]
+            pub via: Option<Vec<String>>,
+
+            /// A string which is used to order the children of a space, lexicographically.
+            ///
+            /// An `order` that isn't valid according to the specification is dropped during
+            /// deserialization, so the child falls back to being ordered by timestamp.
+            #[serde(
+                default,
+                deserialize_with = "valid_order_or_none",
+                skip_serializing_if = "Option::is_none"
+            )]
+            pub order: Option<String>,
+
+            /// Whether the child room is suggested to members of the space.
+            #[serde(default, skip_serializing_if = "crate::util::is_false")]
+            pub suggested: bool,
+        },
+    }
+}
+
+impl SpaceChildEventContent {
+    /// The key to use when ordering the children of a space, if `order` is valid.
+    ///
+    /// A valid `order` consists of at most 50 printable ASCII characters (0x20 to 0x7E).
+    pub fn ordering_key(&self) -> Option<&str> {
+        // TODO: Switch to `as_deref` and remove this attribute once MSRV is >= 1.40
+        #[allow(clippy::option_as_ref_deref, unknown_lints)]
+        self.order
+            .as_ref()
+            .map(String::as_str)
+            .filter(|order| is_valid_order(order))
+    }
+}
+
+impl SpaceChildEvent {
+    /// The ID of the child room, taken from the state key.
+    ///
+    /// Returns `None` if the state key is not a valid room ID.
+    pub fn child_room_id(&self) -> Option<RoomId> {
+        RoomId::try_from(self.state_key.as_str()).ok()
+    }
+}
+
+fn is_valid_order(order: &str) -> bool {
+    order.len() <= 50 && order.bytes().all(|b| (0x20..=0x7E).contains(&b))
+}
+
+/// Deserializes an `order`, replacing it with `None` if it isn't valid, including if it isn't a
+/// string.
+fn valid_order_or_none<'de, D>(deserializer: D) -> Result<Option<String>, D::Error>
+where
+    D: Deserializer<'de>,
+{
+    let order = crate::util::invalid_as_none::<_, String>(deserializer)?;
+    Ok(order.filter(|order| is_valid_order(order)))
+}
+
+#[cfg(test)]
+mod tests {
+    use serde_json::{from_value as from_json_value, json, to_value as to_json_value};
+
+    use super::{SpaceChildEvent, SpaceChildEventContent};
+    use crate::{collections::all::StateEvent, EventResult};
+
+    #[test]
+    fn suggested_child_round_trip() {
+        let content = SpaceChildEventContent {
+            via: Some(vec!["example.org".to_string()]),
+            order: Some("lexicographically_sortable".to_string()),
+            suggested: true,
+        };
+        let json = json!({
+            "via": ["example.org"],
+            "order": "lexicographically_sortable",
+            "suggested": true
+        });
+
+        assert_eq!(to_json_value(&content).unwrap(), json);
+        assert_eq!(
+            from_json_value::<EventResult<SpaceChildEventContent>>(json)
+                .unwrap()
+                .into_result()
+                .unwrap(),
+            content
+        );
+    }
+
+    #[test]
+    fn valid_order() {
+        let content = from_json_value::<EventResult<SpaceChildEventContent>>(json!({
+            "via": ["example.org"],
+            "order": "a~"
+        }))
+        .unwrap()
+        .into_result()
+        .unwrap();
+
+        assert_eq!(content.ordering_key(), Some("a~"));
+    }
+
+    #[test]
+    fn order_with_control_character() {
+        let content = from_json_value::<EventResult<SpaceChildEventContent>>(json!({
+            "via": ["example.org"],
+            "order": "a\u{7}"
+        }))
+        .unwrap()
+        .into_result()
+        .unwrap();
+
+        assert_eq!(content.order, None);
+        assert_eq!(content.ordering_key(), None);
+        assert_eq!(content.via, Some(vec!["example.org".to_string()]));
+    }
+
+    #[test]
+    fn order_of_wrong_type() {
+        for order in &[json!(1), json!(["a"]), json!({ "order": "a" })] {
+            let content = from_json_value::<EventResult<SpaceChildEventContent>>(json!({
+                "via": ["example.org"],
+                "order": order
+            }))
+            .unwrap()
+            .into_result()
+            .unwrap();
+
+            assert_eq!(content.order, None);
+            assert_eq!(content.via, Some(vec!["example.org".to_string()]));
+        }
+    }
+
+    #[test]
+    fn ordering_key_ignores_invalid_order() {
+        let content = SpaceChildEventContent {
+            via: None,
+            order: Some("x".repeat(51)),
+            suggested: false,
+        };
+
+        assert_eq!(content.ordering_key(), None);
+    }
+
+    #[test]
+    fn absent_optional_fields() {
+        let content = from_json_value::<EventResult<SpaceChildEventContent>>(json!({}))
+            .unwrap()
+            .into_result()
+            .unwrap();
+
+        assert_eq!(content.via, None);
+        assert_eq!(content.order, None);
+        assert_eq!(content.ordering_key(), None);
+        assert!(!content.suggested);
+        assert_eq!(to_json_value(&content).unwrap(), json!({}));
+    }
+
+    #[test]
+    fn child_room_id_from_state_key() {
+        let json = json!({
+            "content": { "via": ["example.org"] },
+            "event_id": "$h29iv0s8:example.com",
+            "origin_server_ts": 1,
+            "sender": "@carl:example.com",
+            "state_key": "!child:example.org",
+            "type": "m.space.child"
+        });
+
+        let event = from_json_value::<EventResult<SpaceChildEvent>>(json.clone())
+            .unwrap()
+            .into_result()
+            .unwrap();
+        assert_eq!(
+            event.child_room_id().unwrap().to_string(),
+            "!child:example.org"
+        );
+
+        match from_json_value::<EventResult<StateEvent>>(json)
+            .unwrap()
+            .into_result()
+            .unwrap()
+        {
+            StateEvent::SpaceChild(collection_event) => assert_eq!(collection_event, event),
+            _ => panic!("m.space.child event deserialized into the wrong variant"),
+        }
+    }
+}
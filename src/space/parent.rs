@@ -0,0 +1,74 @@
+//! Types for the *m.space.parent* event.
+
+use std::convert::TryFrom;
+
+use ruma_events_macros::ruma_event;
+use ruma_identifiers::RoomId;
+
+ruma_event! {
+    /// The relationship of a room to one of the spaces it belongs to.
+    ///
+    /// The state key is the ID of the parent space.
+    SpaceParentEvent {
+        kind: StateEvent,
+        event_type: "m.space.parent",
+        content: {
+            /// A list of servers to try to join the parent space through.
+            #[serde(default, skip_serializing_if = "Option::is_none")]
+            pub via: Option<Vec<String>>,
+
+            /// Whether this is the main parent of the room.
+            #[serde(default, skip_serializing_if = "crate::util::is_false")]
+            pub canonical: bool,
+        },
+    }
+}
+
+impl SpaceParentEvent {
+    /// The ID of the parent space, taken from the state key.
+    ///
+    /// Returns `None` if the state key is not a valid room ID.
+    pub fn parent_room_id(&self) -> Option<RoomId> {
+        RoomId::try_from(self.state_key.as_str()).ok()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use serde_json::{from_value as from_json_value, json, to_value as to_json_value};
+
+    use super::SpaceParentEventContent;
+    use crate::EventResult;
+
+    #[test]
+    fn canonical_parent_round_trip() {
+        let content = SpaceParentEventContent {
+            via: Some(vec!["example.org".to_string()]),
+            canonical: true,
+        };
+        let json = json!({
+            "via": ["example.org"],
+            "canonical": true
+        });
+
+        assert_eq!(to_json_value(&content).unwrap(), json);
+        assert_eq!(
+            from_json_value::<EventResult<SpaceParentEventContent>>(json)
+                .unwrap()
+                .into_result()
+                .unwrap(),
+            content
+        );
+    }
+
+    #[test]
+    fn absent_optional_fields() {
+        let content = from_json_value::<EventResult<SpaceParentEventContent>>(json!({}))
+            .unwrap()
+            .into_result()
+            .unwrap();
+
+        assert_eq!(content.via, None);
+        assert!(!content.canonical);
+    }
+}
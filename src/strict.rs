@@ -0,0 +1,224 @@
+//! Opt-in strict parsing of events.
+
+use crate::{BadEvent, TryFromRaw};
+
+/// A wrapper around an event or event content that rejects unknown fields.
+///
+/// By default, ruma-events ignores fields it doesn't know about. Deserializing an
+/// `EventResult<StrictEvent<T>>` instead of an `EventResult<T>` additionally fails validation with
+/// `BadEvent::UnknownFields` if the JSON contains fields that `T` ignores, at any depth. The `type`
+/// key of events is always accepted, since event types with a fixed event type don't read it.
+///
+/// Contents that parse their JSON into a `serde_json::Value` first to decide on their variant, like
+/// those of *m.room.message*, use all of it, so unknown fields within them are not detected.
+#[derive(Clone, Debug, PartialEq)]
+pub struct StrictEvent<T>(pub T);
+
+impl<T> StrictEvent<T> {
+    /// Consumes the wrapper, returning the event.
+    pub fn into_inner(self) -> T {
+        self.0
+    }
+}
+
+impl<T> TryFromRaw for StrictEvent<T>
+where
+    T: TryFromRaw,
+    T::Err: Into<BadEvent>,
+{
+    type Raw = raw::StrictEvent<T::Raw>;
+    type Err = BadEvent;
+
+    fn try_from_raw(raw: raw::StrictEvent<T::Raw>) -> Result<Self, Self::Err> {
+        let event = T::try_from_raw(raw.raw).map_err(Into::into)?;
+
+        if raw.unknown_fields.is_empty() {
+            Ok(StrictEvent(event))
+        } else {
+            Err(BadEvent::UnknownFields(raw.unknown_fields))
+        }
+    }
+}
+
+pub(crate) mod raw {
+    use serde::{de::DeserializeOwned, Deserialize, Deserializer};
+    use serde_ignored::Path;
+
+    /// The raw version of `StrictEvent`, along with the fields that `R` ignored.
+    #[derive(Clone, Debug)]
+    pub struct StrictEvent<R> {
+        pub(super) raw: R,
+        pub(super) unknown_fields: Vec<String>,
+    }
+
+    impl<'de, R> Deserialize<'de> for StrictEvent<R>
+    where
+        R: DeserializeOwned,
+    {
+        fn deserialize<D>(deserializer: D) -> Result<Self, D::Error>
+        where
+            D: Deserializer<'de>,
+        {
+            let mut unknown_fields = Vec::new();
+            let raw = serde_ignored::deserialize(deserializer, |path| {
+                let path = field_path(&path);
+                if path != "type" {
+                    unknown_fields.push(path);
+                }
+            })?;
+            unknown_fields.sort();
+
+            Ok(Self {
+                raw,
+                unknown_fields,
+            })
+        }
+    }
+
+    /// Formats `path` as keys and indices separated by dots, leaving out the transparent steps
+    /// through `Option`s and newtypes.
+    fn field_path(path: &Path<'_>) -> String {
+        let (parent, segment) = match path {
+            Path::Root => return String::new(),
+            Path::Seq { parent, index } => (parent, index.to_string()),
+            Path::Map { parent, key } => (parent, key.clone()),
+            Path::Some { parent }
+            | Path::NewtypeStruct { parent }
+            | Path::NewtypeVariant { parent } => return field_path(parent),
+        };
+
+        match field_path(parent) {
+            ref parent if parent.is_empty() => segment,
+            parent => format!("{}.{}", parent, segment),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use serde_json::{from_value as from_json_value, json};
+
+    use super::StrictEvent;
+    use crate::{
+        room::{power_levels::PowerLevelsEventContent, topic::TopicEvent},
+        EventResult,
+    };
+
+    fn topic_event() -> serde_json::Value {
+        json!({
+            "content": {
+                "topic": "Testing room"
+            },
+            "event_id": "$h29iv0s8:example.com",
+            "origin_server_ts": 1,
+            "room_id": "!n8f893n9:example.com",
+            "sender": "@carl:example.com",
+            "state_key": "",
+            "type": "m.room.topic",
+            "unsigned": {
+                "age": 100
+            }
+        })
+    }
+
+    #[test]
+    fn strict_accepts_known_fields() {
+        let event = from_json_value::<EventResult<StrictEvent<TopicEvent>>>(topic_event())
+            .unwrap()
+            .into_result()
+            .unwrap()
+            .into_inner();
+
+        assert_eq!(event.content.topic, "Testing room");
+    }
+
+    #[test]
+    fn strict_rejects_unknown_fields() {
+        let mut json = topic_event();
+        json["io.ruma.extra"] = json!(true);
+        json["content"]["io.ruma.extra"] = json!("extra");
+
+        assert!(from_json_value::<EventResult<TopicEvent>>(json.clone())
+            .unwrap()
+            .into_result()
+            .is_ok());
+
+        let error = from_json_value::<EventResult<StrictEvent<TopicEvent>>>(json)
+            .unwrap()
+            .into_result()
+            .unwrap_err();
+
+        assert!(error.is_validation());
+        assert_eq!(
+            error.message(),
+            "unknown fields: `content.io.ruma.extra`, `io.ruma.extra`"
+        );
+    }
+
+    #[test]
+    fn strict_rejects_nested_unknown_fields() {
+        let json = json!({
+            "ban": 50,
+            "notifications": {
+                "room": 50,
+                "io.ruma.extra": true
+            }
+        });
+
+        assert!(
+            from_json_value::<EventResult<PowerLevelsEventContent>>(json.clone())
+                .unwrap()
+                .into_result()
+                .is_ok()
+        );
+
+        let error = from_json_value::<EventResult<StrictEvent<PowerLevelsEventContent>>>(json)
+            .unwrap()
+            .into_result()
+            .unwrap_err();
+
+        assert_eq!(
+            error.message(),
+            "unknown fields: `notifications.io.ruma.extra`"
+        );
+    }
+
+    #[test]
+    fn strict_accepts_default_values() {
+        let json = json!({
+            "ban": 50,
+            "events": {},
+            "events_default": 0,
+            "invite": 50,
+            "kick": 50,
+            "redact": 50,
+            "state_default": 50,
+            "users": {},
+            "users_default": 0,
+            "notifications": {
+                "room": 50
+            }
+        });
+
+        assert!(
+            from_json_value::<EventResult<StrictEvent<PowerLevelsEventContent>>>(json)
+                .unwrap()
+                .into_result()
+                .is_ok()
+        );
+    }
+
+    #[test]
+    fn strict_reports_deserialization_errors() {
+        let mut json = topic_event();
+        json["content"] = json!({});
+
+        assert!(
+            from_json_value::<EventResult<StrictEvent<TopicEvent>>>(json)
+                .unwrap()
+                .into_result()
+                .unwrap_err()
+                .is_deserialization()
+        );
+    }
+}
@@ -5,18 +5,26 @@
 //! state event to be created, when the other fields can be inferred from a larger context, or where
 //! the other fields are otherwise inapplicable.
 
-use ruma_identifiers::UserId;
+use js_int::UInt;
+use ruma_identifiers::{EventId, UserId};
 use serde::{de::DeserializeOwned, Deserialize, Deserializer, Serialize};
-use serde_json::Value;
+use serde_json::{Map, Value};
 
 use crate::{
+    collections::all::StateEvent,
     room::{
-        aliases::AliasesEventContent, avatar::AvatarEventContent,
-        canonical_alias::CanonicalAliasEventContent, create::CreateEventContent,
-        guest_access::GuestAccessEventContent, history_visibility::HistoryVisibilityEventContent,
-        join_rules::JoinRulesEventContent, member::MemberEventContent, name::NameEventContent,
-        power_levels::PowerLevelsEventContent, third_party_invite::ThirdPartyInviteEventContent,
-        topic::TopicEventContent,
+        aliases::{AliasesEvent, AliasesEventContent},
+        avatar::{AvatarEvent, AvatarEventContent},
+        canonical_alias::{CanonicalAliasEvent, CanonicalAliasEventContent},
+        create::{CreateEvent, CreateEventContent},
+        guest_access::{GuestAccessEvent, GuestAccessEventContent},
+        history_visibility::{HistoryVisibilityEvent, HistoryVisibilityEventContent},
+        join_rules::{JoinRulesEvent, JoinRulesEventContent},
+        member::{MemberEvent, MemberEventContent},
+        name::{NameEvent, NameEventContent},
+        power_levels::{PowerLevelsEvent, PowerLevelsEventContent},
+        third_party_invite::{ThirdPartyInviteEvent, ThirdPartyInviteEventContent},
+        topic::{TopicEvent, TopicEventContent},
     },
     util::get_field,
     EventType, TryFromRaw,
@@ -118,6 +126,61 @@ pub type StrippedRoomThirdPartyInvite = StrippedStateEvent<ThirdPartyInviteEvent
 /// A stripped-down version of the *m.room.topic* event.
 pub type StrippedRoomTopic = StrippedStateEvent<TopicEventContent>;
 
+impl_from_str!(AnyStrippedStateEvent);
+
+impl AnyStrippedStateEvent {
+    /// Converts this stripped event into a full state event, so that code written against
+    /// `StateEvent` can also handle stripped state, e.g. from an invite.
+    ///
+    /// Stripped events have no ID, so the caller has to provide a unique `event_id`, e.g. one it
+    /// generated locally. The conversion is lossy: the other envelope fields that stripped events
+    /// don't have are filled with placeholders and are not authoritative. `origin_server_ts` is
+    /// zero, `prev_content` and `room_id` are `None` and `unsigned` is empty.
+    pub fn into_state_event(self, event_id: EventId) -> StateEvent {
+        macro_rules! upgrade {
+            ($variant:ident, $event_ty:ident, $stripped:expr) => {{
+                let stripped = $stripped;
+
+                StateEvent::$variant($event_ty {
+                    content: stripped.content,
+                    event_id,
+                    origin_server_ts: UInt::from(0u32),
+                    prev_content: None,
+                    room_id: None,
+                    sender: stripped.sender,
+                    state_key: stripped.state_key,
+                    unsigned: Map::new(),
+                })
+            }};
+        }
+
+        match self {
+            AnyStrippedStateEvent::RoomAliases(e) => upgrade!(RoomAliases, AliasesEvent, e),
+            AnyStrippedStateEvent::RoomAvatar(e) => upgrade!(RoomAvatar, AvatarEvent, e),
+            AnyStrippedStateEvent::RoomCanonicalAlias(e) => {
+                upgrade!(RoomCanonicalAlias, CanonicalAliasEvent, e)
+            }
+            AnyStrippedStateEvent::RoomCreate(e) => upgrade!(RoomCreate, CreateEvent, e),
+            AnyStrippedStateEvent::RoomGuestAccess(e) => {
+                upgrade!(RoomGuestAccess, GuestAccessEvent, e)
+            }
+            AnyStrippedStateEvent::RoomHistoryVisibility(e) => {
+                upgrade!(RoomHistoryVisibility, HistoryVisibilityEvent, e)
+            }
+            AnyStrippedStateEvent::RoomJoinRules(e) => upgrade!(RoomJoinRules, JoinRulesEvent, e),
+            AnyStrippedStateEvent::RoomMember(e) => upgrade!(RoomMember, MemberEvent, e),
+            AnyStrippedStateEvent::RoomName(e) => upgrade!(RoomName, NameEvent, e),
+            AnyStrippedStateEvent::RoomPowerLevels(e) => {
+                upgrade!(RoomPowerLevels, PowerLevelsEvent, e)
+            }
+            AnyStrippedStateEvent::RoomThirdPartyInvite(e) => {
+                upgrade!(RoomThirdPartyInvite, ThirdPartyInviteEvent, e)
+            }
+            AnyStrippedStateEvent::RoomTopic(e) => upgrade!(RoomTopic, TopicEvent, e),
+        }
+    }
+}
+
 impl TryFromRaw for AnyStrippedStateEvent {
     type Raw = raw::StrippedState;
     type Err = String;
@@ -236,7 +299,7 @@ mod raw {
 
     /// A stripped-down version of a state event that is included along with some other events.
     #[derive(Clone, Debug)]
-    #[allow(clippy::large_enum_variant)]
+    #[allow(clippy::large_enum_variant, clippy::enum_variant_names)]
     pub enum StrippedState {
         /// A stripped-down version of the *m.room.aliases* event.
         RoomAliases(StrippedRoomAliases),
@@ -311,11 +374,12 @@ mod tests {
     use std::convert::TryFrom;
 
     use js_int::UInt;
-    use ruma_identifiers::UserId;
+    use ruma_identifiers::{EventId, UserId};
     use serde_json::{from_value as from_json_value, json, to_value as to_json_value};
 
     use super::{AnyStrippedStateEvent, StrippedRoomName, StrippedRoomTopic};
     use crate::{
+        collections::all::StateEvent,
         room::{join_rules::JoinRule, topic::TopicEventContent},
         EventResult, EventType,
     };
@@ -448,4 +512,32 @@ mod tests {
             _ => unreachable!(),
         };
     }
+
+    #[test]
+    fn stripped_name_into_state_event() {
+        let stripped = from_json_value::<EventResult<AnyStrippedStateEvent>>(json!({
+            "type": "m.room.name",
+            "state_key": "",
+            "sender": "@example:localhost",
+            "content": { "name": "Ruma" }
+        }))
+        .unwrap()
+        .into_result()
+        .unwrap();
+
+        let event_id = EventId::try_from("$invite-state-0:example.com").unwrap();
+
+        match stripped.into_state_event(event_id.clone()) {
+            StateEvent::RoomName(event) => {
+                assert_eq!(event.content.name(), Some("Ruma"));
+                assert_eq!(event.state_key, "");
+                assert_eq!(event.sender.to_string(), "@example:localhost");
+                assert_eq!(event.event_id, event_id);
+                assert_eq!(event.origin_server_ts, UInt::from(0u32));
+                assert!(event.prev_content.is_none());
+                assert!(event.room_id.is_none());
+            }
+            _ => unreachable!(),
+        }
+    }
 }
@@ -1,10 +1,13 @@
 //! Types for the *m.tag* event.
 
-use std::collections::HashMap;
+use std::{cmp::Ordering, collections::HashMap};
 
 use ruma_events_macros::ruma_event;
+use ruma_identifiers::RoomId;
 use serde::{Deserialize, Serialize};
 
+use crate::util::cmp_display;
+
 ruma_event! {
     /// Informs the client of tags on a room.
     TagEvent {
@@ -12,6 +15,7 @@ ruma_event! {
         event_type: "m.tag",
         content: {
             /// A map of tag names to tag info.
+            #[serde(serialize_with = "crate::util::sorted_map")]
             pub tags: HashMap<String, TagInfo>,
         },
     }
@@ -24,3 +28,126 @@ pub struct TagInfo {
     #[serde(skip_serializing_if = "Option::is_none")]
     pub order: Option<f64>,
 }
+
+// `ruma_event!` doesn't support deriving additional traits for the content.
+#[allow(clippy::derivable_impls, unknown_lints)]
+impl Default for TagEventContent {
+    fn default() -> Self {
+        Self {
+            tags: HashMap::new(),
+        }
+    }
+}
+
+impl TagEventContent {
+    /// The order of the room within the given tag, if the room has that tag and it has an order.
+    pub fn order(&self, tag: &str) -> Option<f64> {
+        self.tags.get(tag).and_then(|info| info.order)
+    }
+}
+
+/// Compares two rooms for sorting them within the given tag, using their *m.tag* contents.
+///
+/// Rooms with an `order` are sorted by it in ascending order, and come before rooms without one,
+/// including rooms that don't have the tag at all. Ties are broken by room ID.
+pub fn compare_tagged_rooms(
+    tag: &str,
+    (a, a_tags): (&RoomId, &TagEventContent),
+    (b, b_tags): (&RoomId, &TagEventContent),
+) -> Ordering {
+    let by_order = match (a_tags.order(tag), b_tags.order(tag)) {
+        (Some(a_order), Some(b_order)) => a_order.partial_cmp(&b_order).unwrap_or(Ordering::Equal),
+        (Some(_), None) => Ordering::Less,
+        (None, Some(_)) => Ordering::Greater,
+        (None, None) => Ordering::Equal,
+    };
+
+    by_order.then_with(|| cmp_display(a, b))
+}
+
+#[cfg(test)]
+mod tests {
+    use std::{cmp::Ordering, collections::HashMap, convert::TryFrom};
+
+    use ruma_identifiers::RoomId;
+    use serde_json::{json, to_value as to_json_value};
+
+    use super::{compare_tagged_rooms, TagEventContent, TagInfo};
+    use crate::util::assert_deterministic_serialization;
+
+    fn room_id(id: &str) -> RoomId {
+        RoomId::try_from(id).unwrap()
+    }
+
+    fn tags(order: Option<f64>) -> TagEventContent {
+        let mut tags = HashMap::new();
+        tags.insert("u.work".to_string(), TagInfo { order });
+
+        TagEventContent { tags }
+    }
+
+    #[test]
+    fn ordered_rooms() {
+        let (a, b) = (room_id("!b:example.com"), room_id("!a:example.com"));
+
+        assert_eq!(
+            compare_tagged_rooms("u.work", (&a, &tags(Some(0.2))), (&b, &tags(Some(0.5)))),
+            Ordering::Less
+        );
+        assert_eq!(
+            compare_tagged_rooms("u.work", (&a, &tags(Some(0.9))), (&b, &tags(Some(0.5)))),
+            Ordering::Greater
+        );
+    }
+
+    #[test]
+    fn ordered_before_unordered() {
+        let (a, b) = (room_id("!b:example.com"), room_id("!a:example.com"));
+        let untagged = TagEventContent {
+            tags: HashMap::new(),
+        };
+
+        assert_eq!(
+            compare_tagged_rooms("u.work", (&a, &tags(Some(1.0))), (&b, &tags(None))),
+            Ordering::Less
+        );
+        assert_eq!(
+            compare_tagged_rooms("u.work", (&b, &untagged), (&a, &tags(Some(0.0)))),
+            Ordering::Greater
+        );
+    }
+
+    #[test]
+    fn ties_broken_by_room_id() {
+        let (a, b) = (room_id("!a:example.com"), room_id("!b:example.com"));
+
+        assert_eq!(
+            compare_tagged_rooms("u.work", (&a, &tags(Some(0.5))), (&b, &tags(Some(0.5)))),
+            Ordering::Less
+        );
+        assert_eq!(
+            compare_tagged_rooms("u.work", (&b, &tags(None)), (&a, &tags(None))),
+            Ordering::Greater
+        );
+    }
+
+    #[test]
+    fn default_is_empty() {
+        assert_eq!(
+            to_json_value(TagEventContent::default()).unwrap(),
+            json!({ "tags": {} })
+        );
+    }
+
+    #[test]
+    fn deterministic_serialization() {
+        assert_deterministic_serialization::<TagEventContent>(json!({
+            "tags": {
+                "m.favourite": { "order": 0.25 },
+                "m.lowpriority": {},
+                "u.personal": { "order": 0.5 },
+                "u.work": { "order": 1.0 }
+            }
+        }));
+    }
+}
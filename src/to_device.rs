@@ -4,11 +4,14 @@
 //! fields. To-device events are sent directly from one device to the other
 //! without the need to create a room.
 
-use std::collections::HashMap;
+use std::{collections::HashMap, str::FromStr, vec::IntoIter};
 
 use ruma_identifiers::UserId;
-use serde::{de::DeserializeOwned, Deserialize, Deserializer, Serialize};
-use serde_json::Value;
+use serde::{
+    de::{DeserializeOwned, Error as _},
+    Deserialize, Deserializer, Serialize,
+};
+use serde_json::{json, value::RawValue, Value};
 
 use crate::{
     dummy::DummyEventContent,
@@ -20,8 +23,8 @@ use crate::{
     room::encrypted::EncryptedEventContent,
     room_key::RoomKeyEventContent,
     room_key_request::RoomKeyRequestEventContent,
-    util::{get_field, get_field_or_default},
-    TryFromRaw,
+    util::{get_field, get_field_or_default, split_event_array},
+    BadEvent, EventResult, EventType, InvalidEvent, TryFromRaw,
 };
 
 /// To-device versions of events that will appear in the to-device part of a
@@ -53,6 +56,102 @@ pub enum AnyToDeviceEvent {
     KeyVerificationRequest(ToDeviceVerificationRequest),
 }
 
+/// The content of any to-device event, without the surrounding envelope.
+///
+/// This is useful for constructing outgoing to-device messages, where the sender is supplied
+/// separately.
+#[derive(Clone, Debug, PartialEq, Serialize)]
+#[serde(untagged)]
+#[allow(clippy::large_enum_variant)]
+pub enum AnyToDeviceEventContent {
+    /// Content of the "m.dummy" event.
+    Dummy(DummyEventContent),
+    /// Content of the *m.room_key* event.
+    RoomKey(RoomKeyEventContent),
+    /// Content of the *m.room.encrypted* event.
+    RoomEncrypted(EncryptedEventContent),
+    /// Content of the *m.forwarded_room_key* event.
+    ForwardedRoomKey(ForwardedRoomKeyEventContent),
+    /// Content of the *m.room_key_request* event.
+    RoomKeyRequest(RoomKeyRequestEventContent),
+    /// Content of the *m.key.verification.start* event.
+    KeyVerificationStart(StartEventContent),
+    /// Content of the *m.key.verification.accept* event.
+    KeyVerificationAccept(AcceptEventContent),
+    /// Content of the *m.key.verification.key* event.
+    KeyVerificationKey(KeyEventContent),
+    /// Content of the *m.key.verification.mac* event.
+    KeyVerificationMac(MacEventContent),
+    /// Content of the *m.key.verification.cancel* event.
+    KeyVerificationCancel(CancelEventContent),
+    /// Content of the *m.key.verification.request* event.
+    KeyVerificationRequest(RequestEventContent),
+}
+
+impl AnyToDeviceEventContent {
+    /// The type of the event this content belongs to.
+    pub fn event_type(&self) -> EventType {
+        match self {
+            AnyToDeviceEventContent::Dummy(_) => EventType::Dummy,
+            AnyToDeviceEventContent::RoomKey(_) => EventType::RoomKey,
+            AnyToDeviceEventContent::RoomEncrypted(_) => EventType::RoomEncrypted,
+            AnyToDeviceEventContent::ForwardedRoomKey(_) => EventType::ForwardedRoomKey,
+            AnyToDeviceEventContent::RoomKeyRequest(_) => EventType::RoomKeyRequest,
+            AnyToDeviceEventContent::KeyVerificationStart(_) => EventType::KeyVerificationStart,
+            AnyToDeviceEventContent::KeyVerificationAccept(_) => EventType::KeyVerificationAccept,
+            AnyToDeviceEventContent::KeyVerificationKey(_) => EventType::KeyVerificationKey,
+            AnyToDeviceEventContent::KeyVerificationMac(_) => EventType::KeyVerificationMac,
+            AnyToDeviceEventContent::KeyVerificationCancel(_) => EventType::KeyVerificationCancel,
+            AnyToDeviceEventContent::KeyVerificationRequest(_) => EventType::KeyVerificationRequest,
+        }
+    }
+
+    /// Converts this content into the JSON of an event to be sent to a device, consisting of its
+    /// `type` and `content`.
+    ///
+    /// The `sender` is left out, since the homeserver adds it when delivering the event.
+    pub fn to_send_json(&self) -> Value {
+        json!({
+            "type": self.event_type(),
+            "content": self,
+        })
+    }
+}
+
+impl From<AnyToDeviceEvent> for AnyToDeviceEventContent {
+    fn from(event: AnyToDeviceEvent) -> Self {
+        match event {
+            AnyToDeviceEvent::Dummy(e) => AnyToDeviceEventContent::Dummy(e.content),
+            AnyToDeviceEvent::RoomKey(e) => AnyToDeviceEventContent::RoomKey(e.content),
+            AnyToDeviceEvent::RoomEncrypted(e) => AnyToDeviceEventContent::RoomEncrypted(e.content),
+            AnyToDeviceEvent::ForwardedRoomKey(e) => {
+                AnyToDeviceEventContent::ForwardedRoomKey(e.content)
+            }
+            AnyToDeviceEvent::RoomKeyRequest(e) => {
+                AnyToDeviceEventContent::RoomKeyRequest(e.content)
+            }
+            AnyToDeviceEvent::KeyVerificationStart(e) => {
+                AnyToDeviceEventContent::KeyVerificationStart(e.content)
+            }
+            AnyToDeviceEvent::KeyVerificationAccept(e) => {
+                AnyToDeviceEventContent::KeyVerificationAccept(e.content)
+            }
+            AnyToDeviceEvent::KeyVerificationKey(e) => {
+                AnyToDeviceEventContent::KeyVerificationKey(e.content)
+            }
+            AnyToDeviceEvent::KeyVerificationMac(e) => {
+                AnyToDeviceEventContent::KeyVerificationMac(e.content)
+            }
+            AnyToDeviceEvent::KeyVerificationCancel(e) => {
+                AnyToDeviceEventContent::KeyVerificationCancel(e.content)
+            }
+            AnyToDeviceEvent::KeyVerificationRequest(e) => {
+                AnyToDeviceEventContent::KeyVerificationRequest(e.content)
+            }
+        }
+    }
+}
+
 #[derive(Clone, Debug, PartialEq, Serialize)]
 /// To-device event.
 pub struct ToDeviceEvent<C> {
@@ -62,13 +161,65 @@ pub struct ToDeviceEvent<C> {
     pub content: C,
     /// The recipient of the to-device message, in case the message was
     /// encrypted.
+    #[serde(skip_serializing_if = "Option::is_none")]
     pub recipient: Option<UserId>,
     /// Public identity keys of the sender that encrypted the message.
+    #[serde(serialize_with = "crate::util::sorted_map")]
     pub keys: HashMap<String, String>,
     /// Public identity keys of the recipient.
+    #[serde(serialize_with = "crate::util::sorted_map")]
     pub recipient_keys: HashMap<String, String>,
 }
 
+/// The content of a to-device event, which determines the event's `type`.
+///
+/// Deserializing a `ToDeviceEvent` fails if its `type` doesn't match the content type.
+pub trait ToDeviceEventContent {
+    /// The type of the to-device events with this content.
+    fn event_type() -> EventType;
+}
+
+/// Implements `ToDeviceEventContent` for the given contents and their raw versions.
+macro_rules! impl_to_device_event_content {
+    ($($content:ty $(, $raw:ty)? => $event_type:ident;)*) => {
+        $(
+            impl ToDeviceEventContent for $content {
+                fn event_type() -> EventType {
+                    EventType::$event_type
+                }
+            }
+
+            $(
+                impl ToDeviceEventContent for $raw {
+                    fn event_type() -> EventType {
+                        EventType::$event_type
+                    }
+                }
+            )?
+        )*
+    };
+}
+
+impl_to_device_event_content! {
+    DummyEventContent => Dummy;
+    RoomKeyEventContent, crate::room_key::raw::RoomKeyEventContent => RoomKey;
+    EncryptedEventContent, crate::room::encrypted::raw::EncryptedEventContent => RoomEncrypted;
+    ForwardedRoomKeyEventContent, crate::forwarded_room_key::raw::ForwardedRoomKeyEventContent
+        => ForwardedRoomKey;
+    RoomKeyRequestEventContent, crate::room_key_request::raw::RoomKeyRequestEventContent
+        => RoomKeyRequest;
+    StartEventContent, crate::key::verification::start::raw::StartEventContent
+        => KeyVerificationStart;
+    AcceptEventContent, crate::key::verification::accept::raw::AcceptEventContent
+        => KeyVerificationAccept;
+    KeyEventContent, crate::key::verification::key::raw::KeyEventContent => KeyVerificationKey;
+    MacEventContent, crate::key::verification::mac::raw::MacEventContent => KeyVerificationMac;
+    CancelEventContent, crate::key::verification::cancel::raw::CancelEventContent
+        => KeyVerificationCancel;
+    RequestEventContent, crate::key::verification::request::raw::RequestEventContent
+        => KeyVerificationRequest;
+}
+
 /// To-device version of the *m.dummy* event.
 pub type ToDeviceDummy = ToDeviceEvent<DummyEventContent>;
 
@@ -102,12 +253,89 @@ pub type ToDeviceVerificationCancel = ToDeviceEvent<CancelEventContent>;
 /// To-device version of the *m.key.verification.request* event.
 pub type ToDeviceVerificationRequest = ToDeviceEvent<RequestEventContent>;
 
+impl AnyToDeviceEvent {
+    /// The type of this event.
+    pub fn event_type(&self) -> EventType {
+        match self {
+            AnyToDeviceEvent::Dummy(_) => EventType::Dummy,
+            AnyToDeviceEvent::RoomKey(_) => EventType::RoomKey,
+            AnyToDeviceEvent::RoomEncrypted(_) => EventType::RoomEncrypted,
+            AnyToDeviceEvent::ForwardedRoomKey(_) => EventType::ForwardedRoomKey,
+            AnyToDeviceEvent::RoomKeyRequest(_) => EventType::RoomKeyRequest,
+            AnyToDeviceEvent::KeyVerificationStart(_) => EventType::KeyVerificationStart,
+            AnyToDeviceEvent::KeyVerificationAccept(_) => EventType::KeyVerificationAccept,
+            AnyToDeviceEvent::KeyVerificationKey(_) => EventType::KeyVerificationKey,
+            AnyToDeviceEvent::KeyVerificationMac(_) => EventType::KeyVerificationMac,
+            AnyToDeviceEvent::KeyVerificationCancel(_) => EventType::KeyVerificationCancel,
+            AnyToDeviceEvent::KeyVerificationRequest(_) => EventType::KeyVerificationRequest,
+        }
+    }
+
+    /// Converts this event into the JSON it has on the wire, consisting of its `type`, `sender`
+    /// and `content`.
+    pub fn to_json_value(&self) -> Value {
+        fn wire_json<C: Serialize>(event_type: EventType, event: &ToDeviceEvent<C>) -> Value {
+            json!({
+                "type": event_type,
+                "sender": event.sender,
+                "content": event.content,
+            })
+        }
+
+        let event_type = self.event_type();
+
+        match self {
+            AnyToDeviceEvent::Dummy(e) => wire_json(event_type, e),
+            AnyToDeviceEvent::RoomKey(e) => wire_json(event_type, e),
+            AnyToDeviceEvent::RoomEncrypted(e) => wire_json(event_type, e),
+            AnyToDeviceEvent::ForwardedRoomKey(e) => wire_json(event_type, e),
+            AnyToDeviceEvent::RoomKeyRequest(e) => wire_json(event_type, e),
+            AnyToDeviceEvent::KeyVerificationStart(e) => wire_json(event_type, e),
+            AnyToDeviceEvent::KeyVerificationAccept(e) => wire_json(event_type, e),
+            AnyToDeviceEvent::KeyVerificationKey(e) => wire_json(event_type, e),
+            AnyToDeviceEvent::KeyVerificationMac(e) => wire_json(event_type, e),
+            AnyToDeviceEvent::KeyVerificationCancel(e) => wire_json(event_type, e),
+            AnyToDeviceEvent::KeyVerificationRequest(e) => wire_json(event_type, e),
+        }
+    }
+
+    /// The transaction ID of the verification process this event belongs to.
+    ///
+    /// Returns `None` if this is not a key verification event.
+    pub fn verification_transaction_id(&self) -> Option<&str> {
+        match self {
+            AnyToDeviceEvent::KeyVerificationStart(e) => Some(e.content.transaction_id()),
+            AnyToDeviceEvent::KeyVerificationAccept(e) => Some(&e.content.transaction_id),
+            AnyToDeviceEvent::KeyVerificationKey(e) => Some(&e.content.transaction_id),
+            AnyToDeviceEvent::KeyVerificationMac(e) => Some(&e.content.transaction_id),
+            AnyToDeviceEvent::KeyVerificationCancel(e) => Some(&e.content.transaction_id),
+            AnyToDeviceEvent::KeyVerificationRequest(e) => Some(&e.content.transaction_id),
+            AnyToDeviceEvent::Dummy(_)
+            | AnyToDeviceEvent::RoomKey(_)
+            | AnyToDeviceEvent::RoomEncrypted(_)
+            | AnyToDeviceEvent::ForwardedRoomKey(_)
+            | AnyToDeviceEvent::RoomKeyRequest(_) => None,
+        }
+    }
+
+    /// Whether this is an *m.room.encrypted* event, i.e. one that has to be decrypted.
+    ///
+    /// All other to-device events arrive in plaintext. Events like *m.room_key* are usually sent
+    /// inside an *m.room.encrypted* event and only appear as their own type after decryption.
+    pub fn is_encrypted(&self) -> bool {
+        match self {
+            AnyToDeviceEvent::RoomEncrypted(_) => true,
+            _ => false,
+        }
+    }
+}
+
 impl TryFromRaw for AnyToDeviceEvent {
     type Raw = raw::AnyToDeviceEvent;
-    type Err = String;
+    type Err = BadEvent;
 
     fn try_from_raw(raw: raw::AnyToDeviceEvent) -> Result<Self, Self::Err> {
-        use crate::util::try_convert_variant as conv;
+        use crate::util::try_convert_variant_bad_event as conv;
         use raw::AnyToDeviceEvent::*;
 
         match raw {
@@ -122,13 +350,77 @@ impl TryFromRaw for AnyToDeviceEvent {
             KeyVerificationMac(c) => conv(AnyToDeviceEvent::KeyVerificationMac, c),
             KeyVerificationCancel(c) => conv(AnyToDeviceEvent::KeyVerificationCancel, c),
             KeyVerificationRequest(c) => conv(AnyToDeviceEvent::KeyVerificationRequest, c),
+            Unknown(event_type) => Err(BadEvent::UnknownEventType(event_type)),
+        }
+    }
+}
+
+impl_from_str!(AnyToDeviceEvent);
+
+/// An iterator over the to-device events in a JSON array, such as the `to_device.events` of a
+/// `/sync` response.
+///
+/// Only the positions of the events are determined upfront. Each event is deserialized and
+/// validated when the iterator reaches it, so an invalid event results in an `Err` at its position
+/// without affecting the others. If the JSON is not an array, the iterator yields a single `Err`.
+#[derive(Debug)]
+pub struct ToDeviceStream<'a> {
+    events: IntoIter<&'a RawValue>,
+    error: Option<InvalidEvent>,
+}
+
+impl<'a> ToDeviceStream<'a> {
+    /// Creates an iterator over the events in the given JSON array.
+    pub fn new(events: &'a RawValue) -> Self {
+        match split_event_array(events) {
+            Ok(events) => Self {
+                events: events.into_iter(),
+                error: None,
+            },
+            Err(error) => Self {
+                events: Vec::new().into_iter(),
+                error: Some(error),
+            },
+        }
+    }
+}
+
+impl Iterator for ToDeviceStream<'_> {
+    type Item = Result<AnyToDeviceEvent, InvalidEvent>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        if let Some(error) = self.error.take() {
+            return Some(Err(error));
         }
+
+        self.events
+            .next()
+            .map(|event| EventResult::from_json_str(event.get()).into_result())
+    }
+
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        let len = self.events.len() + usize::from(self.error.is_some());
+        (len, Some(len))
+    }
+}
+
+impl<C> FromStr for ToDeviceEvent<C>
+where
+    C: TryFromRaw,
+    C::Raw: ToDeviceEventContent,
+{
+    type Err = InvalidEvent;
+
+    /// Deserializes and validates the event from a string of JSON.
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        EventResult::from_json_str(s).into_result()
     }
 }
 
 impl<C> TryFromRaw for ToDeviceEvent<C>
 where
     C: TryFromRaw,
+    C::Raw: ToDeviceEventContent,
 {
     type Raw = ToDeviceEvent<C::Raw>;
     type Err = C::Err;
@@ -146,7 +438,7 @@ where
 
 impl<'de, C> Deserialize<'de> for ToDeviceEvent<C>
 where
-    C: DeserializeOwned,
+    C: DeserializeOwned + ToDeviceEventContent,
 {
     fn deserialize<D>(deserializer: D) -> Result<Self, D::Error>
     where
@@ -156,12 +448,29 @@ where
         // comment?
         let value = Value::deserialize(deserializer)?;
 
+        let event_type: EventType = get_field(&value, "type")?;
+        if event_type != C::event_type() {
+            return Err(D::Error::custom(format!(
+                "expected a to-device event of type `{}`, found `{}`",
+                C::event_type(),
+                event_type
+            )));
+        }
+
+        // Contents without any required fields, like the one of *m.dummy*, can be omitted.
+        let content = match value.get("content") {
+            Some(_) => get_field(&value, "content")?,
+            None => {
+                serde_json::from_value(json!({})).map_err(|_| D::Error::missing_field("content"))?
+            }
+        };
+
         Ok(Self {
-            content: get_field(&value, "content")?,
+            content,
             sender: get_field(&value, "sender")?,
             recipient: get_field_or_default(&value, "recipient"),
             keys: get_field_or_default(&value, "keys"),
-            recipient_keys: get_field_or_default(&value, "keys"),
+            recipient_keys: get_field_or_default(&value, "recipient_keys"),
         })
     }
 }
@@ -234,6 +543,8 @@ mod raw {
         KeyVerificationCancel(ToDeviceVerificationCancel),
         /// To-device version of the *m.key.verification.request* event.
         KeyVerificationRequest(ToDeviceVerificationRequest),
+        /// An event whose type is not a known to-device event type.
+        Unknown(String),
     }
 
     impl<'de> Deserialize<'de> for AnyToDeviceEvent {
@@ -242,7 +553,6 @@ mod raw {
             D: Deserializer<'de>,
         {
             use crate::{util::try_variant_from_value as from_value, EventType::*};
-            use serde::de::Error as _;
 
             // TODO: Optimize, what should be optimized here? Can we expand this
             // comment?
@@ -263,7 +573,7 @@ mod raw {
                 KeyVerificationRequest => {
                     from_value(value, AnyToDeviceEvent::KeyVerificationRequest)
                 }
-                _ => Err(D::Error::custom("unknown to-device event")),
+                _ => Ok(AnyToDeviceEvent::Unknown(event_type.to_string())),
             }
         }
     }
@@ -276,17 +586,30 @@ mod tests {
     use js_int::UInt;
 
     use ruma_identifiers::{RoomId, UserId};
-    use serde_json::{from_value as from_json_value, json};
+    use serde_json::{from_value as from_json_value, json, to_value as to_json_value};
 
-    use super::AnyToDeviceEvent;
+    use super::{
+        raw, AnyToDeviceEvent, AnyToDeviceEventContent, ToDeviceEncrypted, ToDeviceEvent,
+        ToDeviceRoomKey, ToDeviceStream,
+    };
     use crate::{
+        dummy::DummyEventContent,
+        forwarded_room_key::ForwardedRoomKeyEventContent,
         key::verification::{
-            cancel::CancelCode, start::StartEventContent, HashAlgorithm, KeyAgreementProtocol,
-            MessageAuthenticationCode, ShortAuthenticationString, VerificationMethod,
+            accept::AcceptEventContent,
+            cancel::{CancelCode, CancelEventContent},
+            key::KeyEventContent,
+            mac::MacEventContent,
+            request::RequestEventContent,
+            start::StartEventContent,
+            HashAlgorithm, KeyAgreementProtocol, MessageAuthenticationCode,
+            ShortAuthenticationString, VerificationMethod,
         },
         room::encrypted::EncryptedEventContent,
-        room_key_request::Action,
-        Algorithm, Empty, EventResult,
+        room_key::RoomKeyEventContent,
+        room_key_request::{Action, RoomKeyRequestEventContent},
+        util::try_from_raw_round_trip,
+        BadEvent, Empty, EventEncryptionAlgorithm, EventResult, EventType, TryFromRaw,
     };
 
     macro_rules! deserialize {
@@ -330,6 +653,32 @@ mod tests {
         assert_eq!(event.content, Empty);
     }
 
+    #[test]
+    fn dummy_without_content() {
+        let dummy = json!({
+            "sender": "@alice:example.org",
+            "type": "m.dummy"
+        });
+
+        let event = deserialize! {dummy, AnyToDeviceEvent::Dummy};
+
+        assert_eq!(event.content, Empty);
+    }
+
+    #[test]
+    fn room_key_without_content() {
+        let room_key = json!({
+            "sender": "@alice:example.org",
+            "type": "m.room_key"
+        });
+
+        assert!(from_json_value::<EventResult<AnyToDeviceEvent>>(room_key)
+            .unwrap()
+            .into_result()
+            .unwrap_err()
+            .is_deserialization());
+    }
+
     #[test]
     fn room_key() {
         let room_key = json!({
@@ -347,11 +696,14 @@ mod tests {
 
         assert_eq!(
             event.content.room_id,
-            RoomId::try_from("!test:localhost").unwrap()
+            Some(RoomId::try_from("!test:localhost").unwrap())
         );
         assert_eq!(event.content.session_id, "fake_id");
         assert_eq!(event.content.session_key, "fake_key");
-        assert_eq!(event.content.algorithm, Algorithm::MegolmV1AesSha2);
+        assert_eq!(
+            event.content.algorithm,
+            EventEncryptionAlgorithm::MegolmV1AesSha2
+        );
     }
 
     #[test]
@@ -382,7 +734,10 @@ mod tests {
             _ => panic!("Wrong content type, expected a OlmV1 content"),
         };
 
-        assert_eq!(content.algorithm, Algorithm::OlmV1Curve25519AesSha2);
+        assert_eq!(
+            content.algorithm,
+            EventEncryptionAlgorithm::OlmV1Curve25519AesSha2
+        );
         assert_eq!(content.sender_key, "test_sender_key");
         assert_eq!(content.ciphertext.len(), 2);
         assert_eq!(content.ciphertext["sender_key_0"].body, "ciphertext0");
@@ -417,7 +772,10 @@ mod tests {
         );
         assert_eq!(event.content.session_id, "fake_id");
         assert_eq!(event.content.session_key, "fake_key");
-        assert_eq!(event.content.algorithm, Algorithm::MegolmV1AesSha2);
+        assert_eq!(
+            event.content.algorithm,
+            EventEncryptionAlgorithm::MegolmV1AesSha2
+        );
         assert_eq!(
             event.content.forwarding_curve25519_key_chain,
             ["hPQNcabIABgGnx3/ACv/jmMmiQHoeFfuLB17tzWp6Hw"]
@@ -665,4 +1023,747 @@ mod tests {
             UInt::new(1_559_598_944_869).unwrap()
         );
     }
+
+    #[test]
+    fn verification_transaction_id() {
+        let verification_contents = vec![
+            (
+                "m.key.verification.start",
+                json!({
+                    "from_device": "AliceDevice1",
+                    "hashes": ["sha256"],
+                    "key_agreement_protocols": ["curve25519"],
+                    "message_authentication_codes": ["hkdf-hmac-sha256"],
+                    "method": "m.sas.v1",
+                    "short_authentication_string": ["decimal"],
+                    "transaction_id": "S0meUniqueAndOpaqueString"
+                }),
+            ),
+            (
+                "m.key.verification.accept",
+                json!({
+                    "commitment": "fQpGIW1Snz+pwLZu6sTy2aHy/DYWWTspTJRPyNp0PKkymfIsNffysMl6ObMMFdIJhk6g6pwlIqZ54rxo8SLmAg",
+                    "hash": "sha256",
+                    "key_agreement_protocol": "curve25519",
+                    "message_authentication_code": "hkdf-hmac-sha256",
+                    "method": "m.sas.v1",
+                    "short_authentication_string": ["decimal"],
+                    "transaction_id": "S0meUniqueAndOpaqueString"
+                }),
+            ),
+            (
+                "m.key.verification.key",
+                json!({
+                    "key": "fQpGIW1Snz+pwLZu6sTy2aHy/DYWWTspTJRPyNp0PKkymfIsNffysMl6ObMMFdIJhk6g6pwlIqZ54rxo8SLmAg",
+                    "transaction_id": "S0meUniqueAndOpaqueString"
+                }),
+            ),
+            (
+                "m.key.verification.mac",
+                json!({
+                    "keys": "2Wptgo4CwmLo/Y8B8qinxApKaCkBG2fjTWB7AbP5Uy+aIbygsSdLOFzvdDjww8zUVKCmI02eP9xtyJxc/cLiBA",
+                    "mac": {},
+                    "transaction_id": "S0meUniqueAndOpaqueString"
+                }),
+            ),
+            (
+                "m.key.verification.cancel",
+                json!({
+                    "code": "m.user",
+                    "reason": "Some reason",
+                    "transaction_id": "S0meUniqueAndOpaqueString"
+                }),
+            ),
+            (
+                "m.key.verification.request",
+                json!({
+                    "from_device": "AliceDevice2",
+                    "methods": ["m.sas.v1"],
+                    "timestamp": 1_559_598_944_869_u64,
+                    "transaction_id": "S0meUniqueAndOpaqueString"
+                }),
+            ),
+        ];
+
+        for (event_type, content) in verification_contents {
+            let event = from_json_value::<EventResult<AnyToDeviceEvent>>(json!({
+                "content": content,
+                "type": event_type,
+                "sender": "@alice:example.org"
+            }))
+            .unwrap()
+            .into_result()
+            .unwrap();
+
+            assert_eq!(
+                event.verification_transaction_id(),
+                Some("S0meUniqueAndOpaqueString"),
+                "{}",
+                event_type
+            );
+        }
+
+        let dummy = from_json_value::<EventResult<AnyToDeviceEvent>>(json!({
+            "content": {},
+            "type": "m.dummy",
+            "sender": "@alice:example.org"
+        }))
+        .unwrap()
+        .into_result()
+        .unwrap();
+
+        assert_eq!(dummy.verification_transaction_id(), None);
+    }
+
+    #[test]
+    fn content_round_trips() {
+        try_from_raw_round_trip::<DummyEventContent>(json!({}));
+
+        try_from_raw_round_trip::<RoomKeyEventContent>(json!({
+            "algorithm": "m.megolm.v1.aes-sha2",
+            "room_id": "!test:localhost",
+            "session_id": "fake_id",
+            "session_key": "fake_key"
+        }));
+
+        try_from_raw_round_trip::<EncryptedEventContent>(json!({
+            "algorithm": "m.olm.v1.curve25519-aes-sha2",
+            "ciphertext": {
+                "sender_key_0": {
+                    "body": "ciphertext0",
+                    "type": 0
+                }
+            },
+            "sender_key": "test_sender_key"
+        }));
+
+        try_from_raw_round_trip::<EncryptedEventContent>(json!({
+            "algorithm": "m.megolm.v1.aes-sha2",
+            "ciphertext": "ciphertext",
+            "device_id": "device_id",
+            "sender_key": "sender_key",
+            "session_id": "session_id"
+        }));
+
+        try_from_raw_round_trip::<ForwardedRoomKeyEventContent>(json!({
+            "algorithm": "m.megolm.v1.aes-sha2",
+            "forwarding_curve25519_key_chain": [
+                "hPQNcabIABgGnx3/ACv/jmMmiQHoeFfuLB17tzWp6Hw"
+            ],
+            "room_id": "!test:localhost",
+            "sender_claimed_ed25519_key": "aj40p+aw64yPIdsxoog8jhPu9i7l7NcFRecuOQblE3Y",
+            "sender_key": "RF3s+E7RkTQTGF2d8Deol0FkQvgII2aJDf3/Jp5mxVU",
+            "session_id": "fake_id",
+            "session_key": "fake_key"
+        }));
+
+        try_from_raw_round_trip::<RoomKeyRequestEventContent>(json!({
+            "action": "request",
+            "body": {
+                "algorithm": "m.megolm.v1.aes-sha2",
+                "room_id": "!test:localhost",
+                "sender_key": "RF3s+E7RkTQTGF2d8Deol0FkQvgII2aJDf3/Jp5mxVU",
+                "session_id": "X3lUlvLELLYxeTx4yOVu6UDpasGEVO0Jbu+QFnm0cKQ"
+            },
+            "request_id": "1495474790150.19",
+            "requesting_device_id": "RJYKSTBOIE"
+        }));
+
+        try_from_raw_round_trip::<RoomKeyRequestEventContent>(json!({
+            "action": "request_cancellation",
+            "request_id": "1495474790150.19",
+            "requesting_device_id": "RJYKSTBOIE"
+        }));
+
+        try_from_raw_round_trip::<StartEventContent>(json!({
+            "from_device": "AliceDevice1",
+            "hashes": ["sha256"],
+            "key_agreement_protocols": ["curve25519"],
+            "message_authentication_codes": ["hkdf-hmac-sha256"],
+            "method": "m.sas.v1",
+            "short_authentication_string": ["decimal", "emoji"],
+            "transaction_id": "S0meUniqueAndOpaqueString"
+        }));
+
+        try_from_raw_round_trip::<AcceptEventContent>(json!({
+            "commitment": "fQpGIW1Snz+pwLZu6sTy2aHy/DYWWTspTJRPyNp0PKkymfIsNffysMl6ObMMFdIJhk6g6pwlIqZ54rxo8SLmAg",
+            "hash": "sha256",
+            "key_agreement_protocol": "curve25519",
+            "message_authentication_code": "hkdf-hmac-sha256",
+            "method": "m.sas.v1",
+            "short_authentication_string": ["decimal", "emoji"],
+            "transaction_id": "S0meUniqueAndOpaqueString"
+        }));
+
+        try_from_raw_round_trip::<KeyEventContent>(json!({
+            "key": "fQpGIW1Snz+pwLZu6sTy2aHy/DYWWTspTJRPyNp0PKkymfIsNffysMl6ObMMFdIJhk6g6pwlIqZ54rxo8SLmAg",
+            "transaction_id": "S0meUniqueAndOpaqueString"
+        }));
+
+        try_from_raw_round_trip::<MacEventContent>(json!({
+            "keys": "2Wptgo4CwmLo/Y8B8qinxApKaCkBG2fjTWB7AbP5Uy+aIbygsSdLOFzvdDjww8zUVKCmI02eP9xtyJxc/cLiBA",
+            "mac": {
+                "ed25519:ABCDEF": "fQpGIW1Snz+pwLZu6sTy2aHy/DYWWTspTJRPyNp0PKkymfIsNffysMl6ObMMFdIJhk6g6pwlIqZ54rxo8SLmAg"
+            },
+            "transaction_id": "S0meUniqueAndOpaqueString"
+        }));
+
+        try_from_raw_round_trip::<CancelEventContent>(json!({
+            "code": "m.user",
+            "reason": "Some reason",
+            "transaction_id": "S0meUniqueAndOpaqueString"
+        }));
+
+        try_from_raw_round_trip::<RequestEventContent>(json!({
+            "from_device": "AliceDevice2",
+            "methods": ["m.sas.v1"],
+            "timestamp": 1_559_598_944_869_u64,
+            "transaction_id": "S0meUniqueAndOpaqueString"
+        }));
+    }
+
+    #[test]
+    fn wire_json_value() {
+        let source = json!({
+            "content": {
+                "key": "fQpGIW1Snz+pwLZu6sTy2aHy/DYWWTspTJRPyNp0PKkymfIsNffysMl6ObMMFdIJhk6g6pwlIqZ54rxo8SLmAg",
+                "transaction_id": "S0meUniqueAndOpaqueString"
+            },
+            "sender": "@alice:example.org",
+            "type": "m.key.verification.key"
+        });
+
+        let event = from_json_value::<EventResult<AnyToDeviceEvent>>(source.clone())
+            .unwrap()
+            .into_result()
+            .unwrap();
+
+        assert_eq!(event.event_type(), EventType::KeyVerificationKey);
+        assert_eq!(event.to_json_value(), source);
+
+        let dummy = from_json_value::<EventResult<AnyToDeviceEvent>>(json!({
+            "content": {},
+            "sender": "@alice:example.org",
+            "type": "m.dummy"
+        }))
+        .unwrap()
+        .into_result()
+        .unwrap();
+
+        assert_eq!(dummy.to_json_value()["type"], json!("m.dummy"));
+    }
+
+    #[test]
+    fn into_content() {
+        let source = json!({
+            "content": {
+                "key": "fQpGIW1Snz+pwLZu6sTy2aHy/DYWWTspTJRPyNp0PKkymfIsNffysMl6ObMMFdIJhk6g6pwlIqZ54rxo8SLmAg",
+                "transaction_id": "S0meUniqueAndOpaqueString"
+            },
+            "type": "m.key.verification.key",
+            "sender": "@alice:example.org"
+        });
+
+        let event = from_json_value::<EventResult<AnyToDeviceEvent>>(source)
+            .unwrap()
+            .into_result()
+            .unwrap();
+        let content = AnyToDeviceEventContent::from(event);
+
+        assert_eq!(content.event_type(), EventType::KeyVerificationKey);
+        assert_eq!(
+            to_json_value(&content).unwrap(),
+            json!({
+                "key": "fQpGIW1Snz+pwLZu6sTy2aHy/DYWWTspTJRPyNp0PKkymfIsNffysMl6ObMMFdIJhk6g6pwlIqZ54rxo8SLmAg",
+                "transaction_id": "S0meUniqueAndOpaqueString"
+            })
+        );
+
+        let dummy = from_json_value::<EventResult<AnyToDeviceEvent>>(json!({
+            "content": {},
+            "sender": "@alice:example.org",
+            "type": "m.dummy"
+        }))
+        .unwrap()
+        .into_result()
+        .unwrap();
+
+        match AnyToDeviceEventContent::from(dummy) {
+            AnyToDeviceEventContent::Dummy(content) => assert_eq!(content, Empty),
+            _ => panic!("m.dummy event converted into the wrong content type"),
+        }
+    }
+
+    #[test]
+    fn room_key_send_json() {
+        let content = AnyToDeviceEventContent::RoomKey(RoomKeyEventContent {
+            algorithm: EventEncryptionAlgorithm::MegolmV1AesSha2,
+            room_id: Some(RoomId::try_from("!test:localhost").unwrap()),
+            session_id: "fake_id".to_string(),
+            session_key: "fake_key".to_string(),
+        });
+
+        assert_eq!(
+            content.to_send_json(),
+            json!({
+                "type": "m.room_key",
+                "content": {
+                    "algorithm": "m.megolm.v1.aes-sha2",
+                    "room_id": "!test:localhost",
+                    "session_id": "fake_id",
+                    "session_key": "fake_key"
+                }
+            })
+        );
+    }
+
+    #[test]
+    fn encrypted_send_json() {
+        let content = json!({
+            "algorithm": "m.olm.v1.curve25519-aes-sha2",
+            "ciphertext": {
+                "recipient_key": { "body": "ciphertext", "type": 0 }
+            },
+            "sender_key": "test_sender_key"
+        });
+        let encrypted = from_json_value::<EventResult<EncryptedEventContent>>(content.clone())
+            .unwrap()
+            .into_result()
+            .unwrap();
+
+        assert_eq!(
+            AnyToDeviceEventContent::RoomEncrypted(encrypted).to_send_json(),
+            json!({ "type": "m.room.encrypted", "content": content })
+        );
+    }
+
+    #[test]
+    fn mismatched_event_type() {
+        let room_key = json!({
+            "content": {
+                "algorithm": "m.megolm.v1.aes-sha2",
+                "room_id": "!test:localhost",
+                "session_id": "fake_id",
+                "session_key": "fake_key"
+            },
+            "sender": "@alice:example.org",
+            "type": "m.room_key"
+        });
+
+        let error = from_json_value::<EventResult<ToDeviceEncrypted>>(room_key.clone())
+            .unwrap()
+            .into_result()
+            .unwrap_err();
+
+        assert!(error.is_deserialization());
+        assert_eq!(
+            error.message(),
+            "expected a to-device event of type `m.room.encrypted`, found `m.room_key`"
+        );
+        assert!(from_json_value::<EventResult<ToDeviceRoomKey>>(room_key)
+            .unwrap()
+            .into_result()
+            .is_ok());
+    }
+
+    #[test]
+    fn unknown_event_type() {
+        let source = json!({
+            "content": {},
+            "sender": "@alice:example.org",
+            "type": "m.room.message"
+        });
+
+        let raw = from_json_value::<raw::AnyToDeviceEvent>(source.clone()).unwrap();
+        assert_eq!(
+            AnyToDeviceEvent::try_from_raw(raw),
+            Err(BadEvent::UnknownEventType("m.room.message".to_string()))
+        );
+
+        let error = from_json_value::<EventResult<AnyToDeviceEvent>>(source)
+            .unwrap()
+            .into_result()
+            .unwrap_err();
+        assert!(error.is_validation());
+        assert_eq!(error.message(), "unknown event type `m.room.message`");
+    }
+
+    #[test]
+    fn invalid_field() {
+        let source = json!({
+            "content": {
+                "from_device": "AliceDevice1",
+                "hashes": [],
+                "key_agreement_protocols": ["curve25519"],
+                "message_authentication_codes": ["hkdf-hmac-sha256"],
+                "method": "m.sas.v1",
+                "short_authentication_string": ["decimal"],
+                "transaction_id": "S0meUniqueAndOpaqueString"
+            },
+            "type": "m.key.verification.start",
+            "sender": "@alice:example.org"
+        });
+
+        let raw = from_json_value::<raw::AnyToDeviceEvent>(source).unwrap();
+        match AnyToDeviceEvent::try_from_raw(raw) {
+            Err(BadEvent::Validation { field, .. }) => assert_eq!(field, "hashes"),
+            other => panic!("expected a validation error, got {:?}", other),
+        }
+    }
+
+    #[test]
+    fn missing_field() {
+        let source = json!({
+            "content": {
+                "key": "fQpGIW1Snz+pwLZu6sTy2aHy/DYWWTspTJRPyNp0PKkymfIsNffysMl6ObMMFdIJhk6g6pwlIqZ54rxo8SLmAg"
+            },
+            "type": "m.key.verification.key",
+            "sender": "@alice:example.org"
+        });
+
+        let error = from_json_value::<EventResult<AnyToDeviceEvent>>(source)
+            .unwrap()
+            .into_result()
+            .unwrap_err();
+        assert!(error.is_deserialization());
+        assert!(error.message().contains("missing field `transaction_id`"));
+    }
+
+    /// Payloads in the shape current clients send, one for every to-device event type.
+    mod fixtures {
+        pub const DUMMY: &str = r#"{
+            "content": {},
+            "sender": "@alice:example.org",
+            "type": "m.dummy"
+        }"#;
+
+        pub const ROOM_KEY: &str = r#"{
+            "content": {
+                "algorithm": "m.megolm.v1.aes-sha2",
+                "room_id": "!Cuyf34gef24t:localhost",
+                "session_id": "X3lUlvLELLYxeTx4yOVu6UDpasGEVO0Jbu+QFnm0cKQ",
+                "session_key": "AgAAAADxKHa9uFxcXzwYoNueL5Xqi69IkD4sni8LlfJL7qNBEY..."
+            },
+            "sender": "@alice:example.org",
+            "type": "m.room_key"
+        }"#;
+
+        pub const ENCRYPTED: &str = r#"{
+            "content": {
+                "algorithm": "m.olm.v1.curve25519-aes-sha2",
+                "ciphertext": {
+                    "7qZcfnBmbEGzxxaWfBjElJuvn7BZx+lSz/SvFrDF/z8": {
+                        "body": "AwogGJJzMhf/S3GQFXAOrCZ3iKyGU5ZScVtjI0KypTYrW...",
+                        "type": 0
+                    }
+                },
+                "sender_key": "Szl29ksW/L8yZGWAX+8dY1XyFi+i5wm+DRhTGkbMiwU"
+            },
+            "sender": "@alice:example.org",
+            "type": "m.room.encrypted"
+        }"#;
+
+        pub const FORWARDED_ROOM_KEY: &str = r#"{
+            "content": {
+                "algorithm": "m.megolm.v1.aes-sha2",
+                "forwarding_curve25519_key_chain": [
+                    "hPQNcabIABgGnx3/ACv/jmMmiQHoeFfuLB17tzWp6Hw"
+                ],
+                "room_id": "!Cuyf34gef24t:localhost",
+                "sender_claimed_ed25519_key": "aj40p+aw64yPIdsxoog8jhPu9i7l7NcFRecuOQblE3Y",
+                "sender_key": "RF3s+E7RkTQTGF2d8Deol0FkQvgII2aJDf3/Jp5mxVU",
+                "session_id": "X3lUlvLELLYxeTx4yOVu6UDpasGEVO0Jbu+QFnm0cKQ",
+                "session_key": "AgAAAADxKHa9uFxcXzwYoNueL5Xqi69IkD4sni8Llf..."
+            },
+            "sender": "@alice:example.org",
+            "type": "m.forwarded_room_key"
+        }"#;
+
+        pub const ROOM_KEY_REQUEST: &str = r#"{
+            "content": {
+                "action": "request",
+                "body": {
+                    "algorithm": "m.megolm.v1.aes-sha2",
+                    "room_id": "!Cuyf34gef24t:localhost",
+                    "sender_key": "RF3s+E7RkTQTGF2d8Deol0FkQvgII2aJDf3/Jp5mxVU",
+                    "session_id": "X3lUlvLELLYxeTx4yOVu6UDpasGEVO0Jbu+QFnm0cKQ"
+                },
+                "request_id": "1495474790150.19",
+                "requesting_device_id": "RJYKSTBOIE"
+            },
+            "sender": "@alice:example.org",
+            "type": "m.room_key_request"
+        }"#;
+
+        pub const VERIFICATION_REQUEST: &str = r#"{
+            "content": {
+                "from_device": "AliceDevice2",
+                "methods": ["m.sas.v1"],
+                "timestamp": 1559598944869,
+                "transaction_id": "S0meUniqueAndOpaqueString"
+            },
+            "sender": "@alice:example.org",
+            "type": "m.key.verification.request"
+        }"#;
+
+        pub const VERIFICATION_START: &str = r#"{
+            "content": {
+                "from_device": "BobDevice1",
+                "hashes": ["sha256"],
+                "key_agreement_protocols": ["curve25519"],
+                "message_authentication_codes": ["hkdf-hmac-sha256"],
+                "method": "m.sas.v1",
+                "short_authentication_string": ["decimal", "emoji"],
+                "transaction_id": "S0meUniqueAndOpaqueString"
+            },
+            "sender": "@alice:example.org",
+            "type": "m.key.verification.start"
+        }"#;
+
+        pub const VERIFICATION_ACCEPT: &str = r#"{
+            "content": {
+                "commitment": "fQpGIW1Snz+pwLZu6sTy2aHy/DYWWTspTJRPyNp0PKkymfIsNffysMl6ObMMFdIJhk6g6pwlIqZ54rxo8SLmAg",
+                "hash": "sha256",
+                "key_agreement_protocol": "curve25519",
+                "message_authentication_code": "hkdf-hmac-sha256",
+                "method": "m.sas.v1",
+                "short_authentication_string": ["decimal", "emoji"],
+                "transaction_id": "S0meUniqueAndOpaqueString"
+            },
+            "sender": "@alice:example.org",
+            "type": "m.key.verification.accept"
+        }"#;
+
+        pub const VERIFICATION_KEY: &str = r#"{
+            "content": {
+                "key": "fQpGIW1Snz+pwLZu6sTy2aHy/DYWWTspTJRPyNp0PKkymfIsNffysMl6ObMMFdIJhk6g6pwlIqZ54rxo8SLmAg",
+                "transaction_id": "S0meUniqueAndOpaqueString"
+            },
+            "sender": "@alice:example.org",
+            "type": "m.key.verification.key"
+        }"#;
+
+        pub const VERIFICATION_MAC: &str = r#"{
+            "content": {
+                "keys": "2Wptgo4CwmLo/Y8B8qinxApKaCkBG2fjTWB7AbP5Uy+aIbygsSdLOFzvdDjww8zUVKCmI02eP9xtyJxc/cLiBA",
+                "mac": {
+                    "ed25519:ABCDEF": "fQpGIW1Snz+pwLZu6sTy2aHy/DYWWTspTJRPyNp0PKkymfIsNffysMl6ObMMFdIJhk6g6pwlIqZ54rxo8SLmAg"
+                },
+                "transaction_id": "S0meUniqueAndOpaqueString"
+            },
+            "sender": "@alice:example.org",
+            "type": "m.key.verification.mac"
+        }"#;
+
+        pub const VERIFICATION_CANCEL: &str = r#"{
+            "content": {
+                "code": "m.user",
+                "reason": "User rejected the key verification request",
+                "transaction_id": "S0meUniqueAndOpaqueString"
+            },
+            "sender": "@alice:example.org",
+            "type": "m.key.verification.cancel"
+        }"#;
+    }
+
+    #[test]
+    fn fixture_round_trips() {
+        let cases = [
+            (fixtures::DUMMY, EventType::Dummy),
+            (fixtures::ROOM_KEY, EventType::RoomKey),
+            (fixtures::ENCRYPTED, EventType::RoomEncrypted),
+            (fixtures::FORWARDED_ROOM_KEY, EventType::ForwardedRoomKey),
+            (fixtures::ROOM_KEY_REQUEST, EventType::RoomKeyRequest),
+            (
+                fixtures::VERIFICATION_REQUEST,
+                EventType::KeyVerificationRequest,
+            ),
+            (
+                fixtures::VERIFICATION_START,
+                EventType::KeyVerificationStart,
+            ),
+            (
+                fixtures::VERIFICATION_ACCEPT,
+                EventType::KeyVerificationAccept,
+            ),
+            (fixtures::VERIFICATION_KEY, EventType::KeyVerificationKey),
+            (fixtures::VERIFICATION_MAC, EventType::KeyVerificationMac),
+            (
+                fixtures::VERIFICATION_CANCEL,
+                EventType::KeyVerificationCancel,
+            ),
+        ];
+
+        for (fixture, event_type) in cases.iter() {
+            let event = fixture.parse::<AnyToDeviceEvent>().unwrap_or_else(|error| {
+                panic!("failed to parse {} fixture: {}", event_type, error)
+            });
+
+            assert_eq!(event.event_type(), *event_type);
+            assert_eq!(
+                event.to_json_value(),
+                serde_json::from_str::<serde_json::Value>(fixture).unwrap(),
+                "{} fixture changed in a round trip",
+                event_type
+            );
+        }
+    }
+
+    fn to_device_batch(events: &[serde_json::Value]) -> Box<serde_json::value::RawValue> {
+        serde_json::value::to_raw_value(events).unwrap()
+    }
+
+    #[test]
+    fn stream_of_three_events() {
+        let events = to_device_batch(&[
+            json!({
+                "content": {},
+                "sender": "@alice:example.org",
+                "type": "m.dummy"
+            }),
+            json!({
+                "content": {
+                    "algorithm": "m.megolm.v1.aes-sha2",
+                    "room_id": "!test:localhost",
+                    "session_id": "fake_id",
+                    "session_key": "fake_key"
+                },
+                "sender": "@alice:example.org",
+                "type": "m.room_key"
+            }),
+            json!({
+                "content": {
+                    "code": "m.user",
+                    "reason": "Some reason",
+                    "transaction_id": "S0meUniqueAndOpaqueString"
+                },
+                "sender": "@alice:example.org",
+                "type": "m.key.verification.cancel"
+            }),
+        ]);
+
+        let stream = ToDeviceStream::new(&events);
+        assert_eq!(stream.size_hint(), (3, Some(3)));
+
+        let events: Vec<_> = stream.map(Result::unwrap).collect();
+        match &events[..] {
+            [AnyToDeviceEvent::Dummy(_), AnyToDeviceEvent::RoomKey(_), AnyToDeviceEvent::KeyVerificationCancel(_)] =>
+                {}
+            _ => panic!("unexpected events: {:?}", events),
+        }
+    }
+
+    #[test]
+    fn stream_continues_after_invalid_event() {
+        let events = to_device_batch(&[
+            json!({
+                "content": {},
+                "sender": "@alice:example.org",
+                "type": "m.dummy"
+            }),
+            json!({
+                "content": {
+                    "algorithm": "m.megolm.v1.aes-sha2",
+                    "session_id": "fake_id",
+                    "session_key": "fake_key"
+                },
+                "sender": "@alice:example.org",
+                "type": "m.room_key"
+            }),
+            json!({
+                "content": {},
+                "sender": "@bob:example.org",
+                "type": "m.dummy"
+            }),
+        ]);
+
+        let results: Vec<_> = ToDeviceStream::new(&events).collect();
+
+        assert_eq!(results.len(), 3);
+        assert!(results[0].is_ok());
+        assert!(results[1].as_ref().unwrap_err().is_validation());
+        assert!(results[2].is_ok());
+    }
+
+    #[test]
+    fn stream_of_non_array() {
+        let events = serde_json::value::to_raw_value(&json!({ "events": [] })).unwrap();
+        let results: Vec<_> = ToDeviceStream::new(&events).collect();
+
+        assert_eq!(results.len(), 1);
+        assert!(results[0].as_ref().unwrap_err().is_deserialization());
+    }
+
+    #[test]
+    fn is_encrypted() {
+        let encrypted = json!({
+            "content": {
+                "algorithm": "m.olm.v1.curve25519-aes-sha2",
+                "ciphertext": {
+                    "sender_key_0": { "body": "ciphertext0", "type": 0 }
+                },
+                "sender_key": "test_sender_key"
+            },
+            "sender": "@alice:example.org",
+            "type": "m.room.encrypted"
+        });
+        let room_key = json!({
+            "content": {
+                "algorithm": "m.megolm.v1.aes-sha2",
+                "room_id": "!test:localhost",
+                "session_id": "fake_id",
+                "session_key": "fake_key"
+            },
+            "sender": "@alice:example.org",
+            "type": "m.room_key"
+        });
+
+        let parse = |json| {
+            from_json_value::<EventResult<AnyToDeviceEvent>>(json)
+                .unwrap()
+                .into_result()
+                .unwrap()
+        };
+
+        assert!(parse(encrypted).is_encrypted());
+        assert!(!parse(room_key).is_encrypted());
+    }
+
+    #[test]
+    fn deterministic_serialization() {
+        let keys = json!({
+            "curve25519": "RF3s+E7RkTQTGF2d8Deol0FkQvgII2aJDf3/Jp5mxVU",
+            "ed25519": "aj40p+aw64yPIdsxoog8jhPu9i7l7NcFRecuOQblE3Y",
+            "org.example.a": "a",
+            "org.example.b": "b"
+        });
+        let json = json!({
+            "content": {},
+            "keys": keys,
+            "recipient_keys": keys,
+            "sender": "@alice:example.org",
+            "type": "m.dummy"
+        });
+
+        // `ToDeviceEvent` doesn't serialize its `type`, so this can't use
+        // `assert_deterministic_serialization`.
+        let serialize = || {
+            let event =
+                from_json_value::<EventResult<ToDeviceEvent<DummyEventContent>>>(json.clone())
+                    .unwrap()
+                    .into_result()
+                    .unwrap();
+
+            serde_json::to_string(&event).unwrap()
+        };
+
+        let first = serialize();
+        for _ in 0..4 {
+            assert_eq!(first, serialize());
+        }
+        assert_eq!(
+            serde_json::from_str::<serde_json::Value>(&first).unwrap()["recipient_keys"],
+            keys
+        );
+    }
 }
@@ -0,0 +1,11 @@
+//! Unstable identifiers of features that are also in use under their stable name.
+//!
+//! Features are often implemented under an unstable, namespaced name while their MSC is pending.
+//! Events using these names keep circulating after stabilization, so they are accepted when
+//! parsing, but only the stable names are ever serialized.
+
+/// The unstable name of the `m.text` block of extensible events (MSC1767).
+pub const EXTENSIBLE_TEXT: &str = "org.matrix.msc1767.text";
+
+/// The unstable name of the `m.thread` relation type (MSC3440).
+pub const THREAD_REL_TYPE: &str = "io.element.thread";
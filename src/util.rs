@@ -1,12 +1,17 @@
-use std::fmt::Debug;
+use std::{
+    cmp::Ordering,
+    collections::HashMap,
+    fmt::{Debug, Display, Error as FmtError, Formatter, Result as FmtResult, Write},
+    hash::Hash,
+};
 
 use serde::{
     de::{Deserialize, DeserializeOwned, IntoDeserializer},
-    Serialize,
+    Serialize, Serializer,
 };
-use serde_json::Value;
+use serde_json::{value::RawValue, Value};
 
-use crate::{EventResult, TryFromRaw};
+use crate::{BadEvent, EventResult, InvalidEvent, InvalidEventKind, TryFromRaw};
 
 pub fn try_convert_variant<Enum: TryFromRaw, Content: TryFromRaw>(
     variant: fn(Content) -> Enum,
@@ -17,6 +22,17 @@ pub fn try_convert_variant<Enum: TryFromRaw, Content: TryFromRaw>(
         .map_err(|err| err.to_string())
 }
 
+pub fn try_convert_variant_bad_event<Enum, Content>(
+    variant: fn(Content) -> Enum,
+    raw: Content::Raw,
+) -> Result<Enum, BadEvent>
+where
+    Content: TryFromRaw,
+    Content::Err: Into<BadEvent>,
+{
+    Content::try_from_raw(raw).map(variant).map_err(Into::into)
+}
+
 pub fn try_variant_from_value<T, U, E>(value: Value, variant: fn(T) -> U) -> Result<U, E>
 where
     T: DeserializeOwned,
@@ -27,6 +43,17 @@ where
         .map_err(serde_json_error_to_generic_de_error)
 }
 
+/// Splits a JSON array of events into the raw JSON of each event, without deserializing them.
+///
+/// If `events` is not an array, the error describes the whole of `events`.
+pub fn split_event_array(events: &RawValue) -> Result<Vec<&RawValue>, InvalidEvent> {
+    serde_json::from_str(events.get()).map_err(|error: serde_json::Error| InvalidEvent {
+        message: error.to_string(),
+        json: serde_json::from_str(events.get()).unwrap_or(Value::Null),
+        kind: InvalidEventKind::Deserialization,
+    })
+}
+
 pub fn serde_json_error_to_generic_de_error<E: serde::de::Error>(error: serde_json::Error) -> E {
     E::custom(error.to_string())
 }
@@ -35,13 +62,7 @@ pub fn get_field_or_default<T>(value: &Value, field: &'static str) -> T
 where
     T: DeserializeOwned + Default,
 {
-    serde_json::from_value(
-        value
-            .get(field)
-            .cloned()
-            .unwrap_or_default()
-    )
-    .unwrap_or_default()
+    serde_json::from_value(value.get(field).cloned().unwrap_or_default()).unwrap_or_default()
 }
 
 pub fn get_field<T, E>(value: &Value, field: &'static str) -> Result<T, E>
@@ -75,7 +96,7 @@ where
 {
     let opt = Option::<String>::deserialize(de)?;
     // TODO: Switch to and remove this attribute `opt.as_deref()` once MSRV is >= 1.40
-    #[allow(clippy::option_as_ref_deref, clippy::unknown_clippy_lints)]
+    #[allow(clippy::option_as_ref_deref, unknown_lints)]
     let opt = opt.as_ref().map(String::as_str);
     match opt {
         None | Some("") => Ok(None),
@@ -85,6 +106,23 @@ where
     }
 }
 
+/// Serde deserialization decorator to map values that aren't a valid `T` to `None`, rather than
+/// failing to deserialize the surrounding type.
+///
+/// Useful for optional metadata that some clients are known to send with the wrong type.
+///
+/// To be used like this:
+/// `#[serde(default, deserialize_with = "invalid_as_none")]`
+pub fn invalid_as_none<'de, D, T>(de: D) -> Result<Option<T>, D::Error>
+where
+    D: serde::Deserializer<'de>,
+    T: DeserializeOwned,
+{
+    let value = Value::deserialize(de)?;
+
+    Ok(serde_json::from_value(value).ok())
+}
+
 /// Serde serialization and deserialization functions that map a `Vec<T>` to a `HashMap<T, Empty>`.
 ///
 /// The Matrix spec sometimes specifies lists as hash maps so the list entries can be expanded with
@@ -92,21 +130,25 @@ where
 /// types anyway, we convert them to `Vec`s for simplicity, using this module.
 ///
 /// To be used as `#[serde(with = "vec_as_map_of_empty")]`.
+///
+/// The entries are serialized sorted and without duplicates, so that the output doesn't depend on
+/// the order of the `Vec`.
 pub mod vec_as_map_of_empty {
     use crate::Empty;
     use serde::{Deserialize, Deserializer, Serialize, Serializer};
-    use std::{collections::HashMap, hash::Hash};
+    use std::{collections::HashMap, fmt::Display, hash::Hash};
 
     #[allow(clippy::ptr_arg)]
     pub fn serialize<S, T>(vec: &Vec<T>, serializer: S) -> Result<S::Ok, S::Error>
     where
         S: Serializer,
-        T: Serialize + Hash + Eq,
+        T: Display + Serialize + Hash + Eq,
     {
-        vec.iter()
-            .map(|v| (v, Empty))
-            .collect::<HashMap<_, _>>()
-            .serialize(serializer)
+        let mut entries: Vec<(String, &T)> = vec.iter().map(|v| (v.to_string(), v)).collect();
+        entries.sort_by(|(a, _), (b, _)| a.cmp(b));
+        entries.dedup_by(|(a, _), (b, _)| a == b);
+
+        serializer.collect_map(entries.into_iter().map(|(_, v)| (v, Empty)))
     }
 
     pub fn deserialize<'de, D, T>(deserializer: D) -> Result<Vec<T>, D::Error>
@@ -114,16 +156,218 @@ pub mod vec_as_map_of_empty {
         D: Deserializer<'de>,
         T: Deserialize<'de> + Hash + Eq,
     {
+        // TODO: Switch to `into_keys` and remove this attribute once MSRV is >= 1.54
+        #[allow(clippy::iter_kv_map)]
         HashMap::<T, Empty>::deserialize(deserializer)
             .map(|hashmap| hashmap.into_iter().map(|(k, _)| k).collect())
     }
 }
 
+/// Serializes a `HashMap` with its entries sorted by key, so that the output doesn't depend on the
+/// map's iteration order.
+///
+/// To be used as `#[serde(serialize_with = "crate::util::sorted_map")]`.
+pub fn sorted_map<S, K, V>(map: &HashMap<K, V>, serializer: S) -> Result<S::Ok, S::Error>
+where
+    S: Serializer,
+    K: Display + Eq + Hash + Serialize,
+    V: Serialize,
+{
+    let mut entries: Vec<(&K, &V)> = map.iter().collect();
+    entries.sort_by_cached_key(|(key, _)| key.to_string());
+
+    serializer.collect_map(entries)
+}
+
+/// Like `sorted_map`, for optional maps.
+///
+/// To be used as `#[serde(serialize_with = "crate::util::sorted_optional_map")]`, usually together
+/// with `skip_serializing_if = "Option::is_none"`.
+pub fn sorted_optional_map<S, K, V>(
+    map: &Option<HashMap<K, V>>,
+    serializer: S,
+) -> Result<S::Ok, S::Error>
+where
+    S: Serializer,
+    K: Display + Eq + Hash + Serialize,
+    V: Serialize,
+{
+    match map {
+        Some(map) => sorted_map(map, serializer),
+        None => serializer.serialize_none(),
+    }
+}
+
+/// A placeholder for secret values in `Debug` output, such as keys and ciphertexts.
+pub struct Redacted;
+
+impl Debug for Redacted {
+    fn fmt(&self, f: &mut Formatter<'_>) -> FmtResult {
+        f.write_str("<redacted>")
+    }
+}
+
 /// Used to default the `bool` fields to `true` during deserialization.
 pub fn default_true() -> bool {
     true
 }
 
+/// Used to skip serializing `bool` fields that are `false`, their default value.
+pub fn is_false(b: &bool) -> bool {
+    !*b
+}
+
+/// Decodes a string of standard Base64, as used for keys and MACs throughout the Matrix
+/// specification. The specification mandates unpadded Base64, but padding is tolerated.
+///
+/// Returns `None` if the string is not valid Base64.
+// TODO: Switch to `usize::is_multiple_of` and remove this attribute once MSRV is >= 1.87
+#[allow(clippy::manual_is_multiple_of, unknown_lints)]
+pub fn decode_base64(input: &str) -> Option<Vec<u8>> {
+    fn sextet(c: u8) -> Option<u32> {
+        match c {
+            b'A'..=b'Z' => Some(u32::from(c - b'A')),
+            b'a'..=b'z' => Some(u32::from(c - b'a') + 26),
+            b'0'..=b'9' => Some(u32::from(c - b'0') + 52),
+            b'+' => Some(62),
+            b'/' => Some(63),
+            _ => None,
+        }
+    }
+
+    let input = input.as_bytes();
+    let unpadded_len = input.iter().rposition(|&c| c != b'=').map_or(0, |i| i + 1);
+    let (data, padding) = input.split_at(unpadded_len);
+
+    if padding.len() > 2 || (!padding.is_empty() && input.len() % 4 != 0) || data.len() % 4 == 1 {
+        return None;
+    }
+
+    let mut bytes = Vec::with_capacity(data.len() * 3 / 4);
+
+    for chunk in data.chunks(4) {
+        let mut buffer = 0;
+        for &c in chunk {
+            buffer = (buffer << 6) | sextet(c)?;
+        }
+        buffer <<= 6 * (4 - chunk.len() as u32);
+
+        let decoded = [(buffer >> 16) as u8, (buffer >> 8) as u8, buffer as u8];
+        bytes.extend_from_slice(&decoded[..chunk.len() - 1]);
+    }
+
+    Some(bytes)
+}
+
+/// Matches `name` against a glob pattern in which `*` matches zero or more characters and `?`
+/// matches exactly one character.
+///
+/// This runs in linear time for patterns with a single `*` and in `O(pattern * name)` in the worst
+/// case, so it is safe to use with patterns from untrusted sources such as ban lists.
+pub fn glob_matches(pattern: &str, name: &str) -> bool {
+    let pattern: Vec<char> = pattern.chars().collect();
+    let name: Vec<char> = name.chars().collect();
+
+    let (mut p, mut n) = (0, 0);
+    // The position of the last `*` in the pattern and of the character in `name` it was matched
+    // up to, so that it can be extended when the rest of the pattern doesn't match.
+    let mut backtrack = None;
+
+    while n < name.len() {
+        match pattern.get(p) {
+            Some('*') => {
+                backtrack = Some((p, n));
+                p += 1;
+            }
+            Some(&c) if c == '?' || c == name[n] => {
+                p += 1;
+                n += 1;
+            }
+            _ => match backtrack {
+                Some((star, matched)) => {
+                    backtrack = Some((star, matched + 1));
+                    p = star + 1;
+                    n = matched + 1;
+                }
+                None => return false,
+            },
+        }
+    }
+
+    pattern[p..].iter().all(|&c| c == '*')
+}
+
+/// The maximum length of a Matrix identifier in bytes.
+const MAX_ID_BYTES: usize = 255;
+
+/// Compares two identifiers by their string representation without allocating.
+///
+/// The identifier types don't keep their string representation around, so `a` is written to a
+/// buffer on the stack and `b` is compared against it while it is being written.
+pub fn cmp_display<T: Display>(a: &T, b: &T) -> Ordering {
+    let mut a_buf = StackBuf {
+        bytes: [0; MAX_ID_BYTES],
+        len: 0,
+    };
+    if write!(a_buf, "{}", a).is_err() {
+        return a.to_string().cmp(&b.to_string());
+    }
+
+    let mut cmp = CmpWriter {
+        rest: &a_buf.bytes[..a_buf.len],
+        ordering: Ordering::Equal,
+    };
+    // `CmpWriter` never fails.
+    let _ = write!(cmp, "{}", b);
+
+    match (cmp.ordering, cmp.rest.is_empty()) {
+        (Ordering::Equal, false) => Ordering::Greater,
+        (ordering, _) => ordering,
+    }
+}
+
+struct StackBuf {
+    bytes: [u8; MAX_ID_BYTES],
+    len: usize,
+}
+
+impl Write for StackBuf {
+    fn write_str(&mut self, s: &str) -> FmtResult {
+        let end = self.len + s.len();
+        if end > self.bytes.len() {
+            return Err(FmtError);
+        }
+
+        self.bytes[self.len..end].copy_from_slice(s.as_bytes());
+        self.len = end;
+        Ok(())
+    }
+}
+
+/// Compares the string written to it with the bytes in `rest`.
+struct CmpWriter<'a> {
+    rest: &'a [u8],
+    ordering: Ordering,
+}
+
+impl Write for CmpWriter<'_> {
+    fn write_str(&mut self, s: &str) -> FmtResult {
+        if self.ordering != Ordering::Equal {
+            return Ok(());
+        }
+
+        let s = s.as_bytes();
+        let len = s.len().min(self.rest.len());
+        self.ordering = self.rest[..len].cmp(&s[..len]);
+        if self.ordering == Ordering::Equal && s.len() > self.rest.len() {
+            self.ordering = Ordering::Less;
+        }
+
+        self.rest = &self.rest[len..];
+        Ok(())
+    }
+}
+
 #[cfg(test)]
 pub fn serde_json_eq<T>(de: T, se: serde_json::Value)
 where
@@ -133,6 +377,55 @@ where
     assert_eq!(de, serde_json::from_value(se).unwrap());
 }
 
+/// Deserializes `json` into `T::Raw`, converts it with `try_from_raw` and checks that serializing
+/// the result yields `json` again.
+#[cfg(test)]
+pub fn try_from_raw_round_trip<T>(json: serde_json::Value)
+where
+    T: Serialize + TryFromRaw,
+    T::Raw: DeserializeOwned,
+{
+    let raw: T::Raw = serde_json::from_value(json.clone()).unwrap();
+    let content = T::try_from_raw(raw).unwrap_or_else(|err| panic!("{}", err));
+
+    assert_eq!(serde_json::to_value(content).unwrap(), json);
+}
+
+/// Deserializes `json` into `T` several times and checks that all results serialize to the same
+/// string, and that its canonical JSON form is that of `json`.
+///
+/// Each deserialization creates new maps, so this catches `HashMap`s whose entries are serialized
+/// in iteration order, as long as `json` contains maps with a few entries.
+#[cfg(test)]
+pub fn assert_deterministic_serialization<T>(json: serde_json::Value)
+where
+    T: Serialize + TryFromRaw,
+    T::Raw: DeserializeOwned,
+{
+    let serialize = || {
+        let raw: T::Raw = serde_json::from_value(json.clone()).unwrap();
+        let content = T::try_from_raw(raw).unwrap_or_else(|err| panic!("{}", err));
+
+        serde_json::to_string(&content).unwrap()
+    };
+
+    let first = serialize();
+    for _ in 0..4 {
+        assert_eq!(first, serialize());
+    }
+    assert_eq!(canonical_json(&first), canonical_json(&json.to_string()));
+}
+
+/// The canonical JSON form of `json`: sorted keys and no insignificant whitespace.
+///
+/// `serde_json::Value` keeps objects in a `BTreeMap`, so re-serializing it sorts all keys.
+#[cfg(test)]
+fn canonical_json(json: &str) -> String {
+    serde_json::from_str::<serde_json::Value>(json)
+        .unwrap()
+        .to_string()
+}
+
 // This would be #[cfg(test)] if it wasn't used from external tests
 pub fn serde_json_eq_try_from_raw<T>(de: T, se: serde_json::Value)
 where
@@ -147,3 +440,41 @@ where
             .unwrap()
     );
 }
+
+#[cfg(test)]
+mod tests {
+    use std::{cmp::Ordering, convert::TryFrom};
+
+    use ruma_identifiers::UserId;
+
+    use super::{canonical_json, cmp_display};
+
+    #[test]
+    fn canonical_json_sorts_keys() {
+        assert_eq!(
+            canonical_json(r#"{ "b": 1, "a": { "d": [], "c": null } }"#),
+            r#"{"a":{"c":null,"d":[]},"b":1}"#
+        );
+    }
+
+    #[test]
+    fn cmp_display_matches_string_order() {
+        let ids = [
+            "@alice:example.com",
+            "@alice:example.com:8448",
+            "@alice:example.org",
+            "@alicia:example.com",
+            "@bob:example.com",
+        ];
+
+        for a in ids.iter() {
+            for b in ids.iter() {
+                let a_id = UserId::try_from(*a).unwrap();
+                let b_id = UserId::try_from(*b).unwrap();
+                assert_eq!(cmp_display(&a_id, &b_id), a.cmp(b));
+            }
+        }
+        assert_eq!(cmp_display(&"a", &"ab"), Ordering::Less);
+        assert_eq!(cmp_display(&"ab", &"a"), Ordering::Greater);
+    }
+}